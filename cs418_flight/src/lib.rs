@@ -4,12 +4,13 @@ use std::cell::RefCell;
 use cgmath as cg;
 use console_error_panic_hook;
 use fs_embed::fs_embed;
-use instant::Instant;
-use poisson_renderer::input::{Input, KeyCode, PhysicalKey};
+use poisson_renderer::input::{AxisBinding, BindingsStore, AxisSettings, AxisSource, GamepadAxis, Input, KeyCode, MouseButton, VectorBinding, VectorSettings};
+use poisson_renderer::time::Time;
+use poisson_renderer::context::EngineContext;
 use poisson_renderer::math::utils::perspective;
-use poisson_renderer::render_backend::web::{CreateDrawletWgpu, EguiUiShow, WgpuPipeline, WgpuRenderBackend};
-use poisson_renderer::render_backend::RenderBackend;
-use poisson_renderer::{init_logger, render_backend, run_game, shader, PoissonGame};
+use poisson_renderer::render_backend::web::WgpuRenderBackend;
+use poisson_renderer::render_backend::{EguiUiShow, RenderBackend};
+use poisson_renderer::{init_logger, render_backend, run_game, shader, PoissonGame, WindowConfig};
 use std::error::Error;
 use std::f32::consts::PI;
 use std::ops::Index;
@@ -34,7 +35,8 @@ cfg_if::cfg_if! {
 
 
 use poisson_renderer::render_backend::render_interface::drawlets::colored_mesh::{ColoredMesh, ColoredMeshData, ColoredVertex};
-use poisson_renderer::render_backend::render_interface::drawlets::lit_colored_mesh::{LitColoredMesh, LitColoredMeshData};
+use poisson_renderer::render_backend::render_interface::drawlets::lit_colored_mesh::{LitColoredMesh, LitColoredMeshData, NormalColoredVertex};
+use poisson_renderer::render_backend::render_interface::resources::BufferUsage;
 
 #[cfg_attr(target_arch="wasm32", wasm_bindgen(start))]
 pub async fn run_wasm() {
@@ -52,6 +54,7 @@ pub struct TerrainParams {
     pub grid_size: usize
 }
 
+#[derive(Clone)]
 struct FlightParams {
     pos: cg::Vector3<f32>,
     yaw: f32,
@@ -87,6 +90,14 @@ impl FlightParams {
         self.pos += left * speed;
     }
 
+    pub fn lerp(&self, next: &FlightParams, alpha: f32) -> FlightParams {
+        FlightParams {
+            pos: self.pos + (next.pos - self.pos) * alpha,
+            yaw: self.yaw + (next.yaw - self.yaw) * alpha,
+            pitch: self.pitch + (next.pitch - self.pitch) * alpha,
+        }
+    }
+
     fn yaw_pitch_to_dir(yaw: f32, pitch: f32) -> cg::Vector3<f32> {
         cg::Vector3::new(
             yaw.sin(),
@@ -105,17 +116,22 @@ impl FlightParams {
     }
 }
 
+// radians per physical pixel
+const MOUSE_SENSITIVITY: f32 = 0.004f32;
+const STICK_DEAD_ZONE: f32 = 0.15f32;
+
 pub struct Terrain {
     //document: Option<Document>,
     terrain_mesh: Option<DrawletHandle<LitColoredMesh>>,
+    // rewritten in place when new params are submitted, the drawlet keeps drawing it
+    terrain_buffers: Option<Arc<Mesh<NormalColoredVertex>>>,
     scene_render_pass: Option<PassHandle>,
     lit_colored_mesh_pipeline: Option<PipelineHandle<LitColoredMesh>>,
-    last_time: Instant,
-    elapsed_time: f32,
     assets: fs_embed::Dir,
     egui_state: EguiState,
     terrain_params: Rc<RefCell<Option<TerrainParams>>>,
     flight_params: FlightParams,
+    prev_flight_params: FlightParams,
 }
 
 impl PoissonGame for Terrain {
@@ -129,34 +145,68 @@ impl PoissonGame for Terrain {
             scene_render_pass: None,
             lit_colored_mesh_pipeline: None,
             terrain_mesh: None,
-            last_time: Instant::now(),
-            elapsed_time: 0f32,
+            terrain_buffers: None,
             assets: FILES.clone().auto_dynamic(),
             egui_state: EguiState {},
             terrain_params: Rc::new(RefCell::new(None)),
             flight_params: FlightParams::new(),
+            prev_flight_params: FlightParams::new(),
+        }
+    }
+
+    fn window_config(self: &Self) -> WindowConfig {
+        WindowConfig {
+            title: "cs418 flight".to_string(),
+            size: Some((1280, 720)),
+            ..Default::default()
         }
     }
 
     fn pre_init(self: &mut Self, input: &mut Input) {
-        input.set_mapping("move_forward", vec![PhysicalKey::Code(KeyCode::KeyW)]);
-        input.set_mapping("move_back", vec![PhysicalKey::Code(KeyCode::KeyS)]);
-        input.set_mapping("move_left", vec![PhysicalKey::Code(KeyCode::KeyA)]);
-        input.set_mapping("move_right", vec![PhysicalKey::Code(KeyCode::KeyD)]);
-        input.set_mapping("rotate_right", vec![PhysicalKey::Code(KeyCode::ArrowRight)]);
-        input.set_mapping("rotate_left", vec![PhysicalKey::Code(KeyCode::ArrowLeft)]);
-        input.set_mapping("rotate_up", vec![PhysicalKey::Code(KeyCode::ArrowUp)]);
-        input.set_mapping("rotate_down", vec![PhysicalKey::Code(KeyCode::ArrowDown)]);
+        input.set_vector_binding("move", VectorBinding {
+            x: vec![AxisSource::buttons(vec![KeyCode::KeyD], vec![KeyCode::KeyA]), AxisSource::Gamepad(GamepadAxis::LeftStickX)],
+            y: vec![AxisSource::buttons(vec![KeyCode::KeyW], vec![KeyCode::KeyS]), AxisSource::Gamepad(GamepadAxis::LeftStickY)],
+            settings: VectorSettings { dead_zone: STICK_DEAD_ZONE, ..Default::default() },
+        });
+        // positive yaw turns left, so the stick (positive to the right) is inverted
+        input.set_axis_binding("yaw", AxisBinding {
+            sources: vec![AxisSource::buttons(vec![KeyCode::ArrowRight], vec![KeyCode::ArrowLeft]), AxisSource::Gamepad(GamepadAxis::RightStickX)],
+            settings: AxisSettings { dead_zone: STICK_DEAD_ZONE, inverted: true, ..Default::default() },
+        });
+        input.set_axis_binding("pitch", AxisBinding {
+            sources: vec![AxisSource::buttons(vec![KeyCode::ArrowUp], vec![KeyCode::ArrowDown]), AxisSource::Gamepad(GamepadAxis::RightStickY)],
+            settings: AxisSettings { dead_zone: STICK_DEAD_ZONE, ..Default::default() },
+        });
+        input.set_axis_binding("look_yaw", AxisBinding {
+            sources: vec![AxisSource::MouseX],
+            settings: AxisSettings { sensitivity: MOUSE_SENSITIVITY, inverted: true, ..Default::default() },
+        });
+        input.set_axis_binding("look_pitch", AxisBinding {
+            sources: vec![AxisSource::MouseY],
+            settings: AxisSettings { sensitivity: MOUSE_SENSITIVITY, inverted: true, ..Default::default() },
+        });
+        input.set_mapping("look", vec![MouseButton::Right]);
+
+        // players remap by editing the stored bindings, the defaults above are written out the first time
+        let store = BindingsStore::new("cs418_flight");
+        match input.load_bindings(&store) {
+            Ok(true) => {},
+            Ok(false) => if let Err(err) = input.save_bindings(&store) {
+                log::warn!("{err}");
+            },
+            Err(err) => log::warn!("using default controls, {err}"),
+        }
+        for (button, users) in input.find_conflicts() {
+            log::warn!("{:?} is bound to several controls: {}", button, users.join(", "));
+        }
     }
 
-    fn init(self: &mut Self, _input: &mut Input, renderer: &mut Self::Ren) {
+    fn init(self: &mut Self, _input: &mut Input, renderer: &mut Self::Ren, _context: &mut EngineContext) {
         self.terrain_params = Rc::new(RefCell::new(Some(TerrainParams {
             faults: 50,
             grid_size: 50,
         })));
 
-        self.last_time = Instant::now();
-
         let lit_colored_mesh_shader = self.assets.get_file(shader!("shaders/lit_colored_mesh")).unwrap();
         let lit_colored_mesh_shader_content = lit_colored_mesh_shader.read_str().unwrap();
 
@@ -170,30 +220,54 @@ impl PoissonGame for Terrain {
         self.lit_colored_mesh_pipeline = Some(p_handle);
     }
 
-    fn update(self: &mut Self, input: &mut Input, renderer: &mut Self::Ren) {
+    fn fixed_update(self: &mut Self, input: &mut Input, time: &mut Time) {
+        let delta_time = time.fixed_timestep();
+        self.prev_flight_params = self.flight_params.clone();
+
+        let movement = input.vector("move");
+        self.flight_params.turn_pitch(input.axis("pitch") * delta_time);
+        self.flight_params.turn_yaw(input.axis("yaw") * delta_time);
+        self.flight_params.move_front_back(movement.y * delta_time/2f32);
+        self.flight_params.move_left_right(-movement.x * delta_time/2f32);
+    }
+
+    fn update(self: &mut Self, input: &mut Input, renderer: &mut Self::Ren, time: &mut Time, _context: &mut EngineContext) {
+        // mouse look, applied to both fixed states so interpolation doesn't smear it
+        if input.is_pressed("look") {
+            let (yaw, pitch) = (input.axis("look_yaw"), input.axis("look_pitch"));
+            for params in [&mut self.prev_flight_params, &mut self.flight_params] {
+                params.turn_yaw(yaw);
+                params.turn_pitch(pitch);
+            }
+        }
+
         let params_submitted = self.terrain_params.borrow().is_some();
         if params_submitted {
             {
                 let data = self.terrain_params.borrow();
                 let data = data.as_ref().unwrap();
                 let mesh_grid = mesh::mesh_grid(data.grid_size - 1, data.faults);
-                let vertex_buffer = renderer.create_vertex_buffer(mesh_grid.0.as_slice());
-                let index_buffer = renderer.create_index_buffer(mesh_grid.1.as_slice());
-                let lit_mesh_data = LitColoredMeshData {
-                    mvp_data: cg::Matrix4::identity(),
-                    light_dir: cg::Vector4 {x: 1f32, y: 0f32, z: 0f32, w: 0f32},
-                    view_dir: cg::Vector4 {x: 1f32, y: 0f32, z: 0f32, w: 0f32},
-                    mesh: Arc::new(Mesh {
+                if let Some(terrain_buffers) = &self.terrain_buffers {
+                    terrain_buffers.vertex.set_data(mesh_grid.0.as_slice());
+                    terrain_buffers.index.set_data(mesh_grid.1.as_slice());
+                } else {
+                    let vertex_buffer = renderer.create_vertex_buffer_with_usage(mesh_grid.0.as_slice(), BufferUsage::Dynamic);
+                    let index_buffer = renderer.create_index_buffer_with_usage(mesh_grid.1.as_slice(), BufferUsage::Dynamic);
+                    let terrain_buffers = Arc::new(Mesh {
                         index: index_buffer,
                         vertex: vertex_buffer,
-                    }),
-                };
-                if let Some(drawlet)= self.terrain_mesh.take() {
-                    self.lit_colored_mesh_pipeline.as_mut().unwrap().remove_drawlet(drawlet);
+                    });
+                    let lit_mesh_data = LitColoredMeshData {
+                        mvp_data: cg::Matrix4::identity(),
+                        light_dir: cg::Vector4 {x: 1f32, y: 0f32, z: 0f32, w: 0f32},
+                        view_dir: cg::Vector4 {x: 1f32, y: 0f32, z: 0f32, w: 0f32},
+                        mesh: terrain_buffers.clone(),
+                    };
+
+                    self.terrain_mesh = Some(self.lit_colored_mesh_pipeline.as_mut().unwrap().create_drawlet(lit_mesh_data));
+                    self.terrain_mesh.as_mut().unwrap().set_light_direction(cg::Vector3::<f32>::new(2f32, 2f32, 2f32));
+                    self.terrain_buffers = Some(terrain_buffers);
                 }
-
-                self.terrain_mesh = Some(self.lit_colored_mesh_pipeline.as_mut().unwrap().create_drawlet(lit_mesh_data));
-                self.terrain_mesh.as_mut().unwrap().set_light_direction(cg::Vector3::<f32>::new(2f32, 2f32, 2f32));
             }
             self.terrain_params.replace(None);
         }
@@ -201,26 +275,8 @@ impl PoissonGame for Terrain {
         if let Some(terrain_params) = self.terrain_params.borrow().as_ref() {
             log::info!("TerrainParams: {}, {}", terrain_params.faults, terrain_params.grid_size);
         }
-        let delta_time = self.last_time.elapsed().as_secs_f32();
-        self.last_time = Instant::now();
-
-        self.elapsed_time += delta_time;
-
-        let camera_center = cgmath::Vector3::new(2f32 * self.elapsed_time.cos(), 2f32, 2f32 * self.elapsed_time.sin());
-        let v = cgmath::Matrix4::look_at_rh(
-            cgmath::Point3::from_vec(camera_center),
-            cgmath::Point3::new(0.0, 0.0, 0.0),
-            cgmath::Vector3::new(0.0, 1.0, 0.0));
-        if input.is_pressed("rotate_up") { self.flight_params.turn_pitch(delta_time); }
-        if input.is_pressed("rotate_down") { self.flight_params.turn_pitch(-delta_time); }
-        if input.is_pressed("rotate_left") {self.flight_params.turn_yaw(delta_time); }
-        if input.is_pressed("rotate_right") {self.flight_params.turn_yaw(-delta_time); }
-        if input.is_pressed("move_forward") {self.flight_params.move_front_back(delta_time/2f32); }
-        if input.is_pressed("move_back") {self.flight_params.move_front_back(-delta_time/2f32); }
-        if input.is_pressed("move_left") { self.flight_params.move_left_right(delta_time/2f32); }
-        if input.is_pressed("move_right") { self.flight_params.move_left_right(-delta_time/2f32); }
-
-        let v = self.flight_params.to_view_matrix();
+        let camera = self.prev_flight_params.lerp(&self.flight_params, time.alpha());
+        let v = camera.to_view_matrix();
         let aspect_ratio = (renderer.get_width() as f32)/(renderer.get_height() as f32);
 
         let p = perspective(PI/12f32, aspect_ratio, 0.1, 100.0, Self::Ren::PERSPECTIVE_ALIGNMENT);
@@ -228,7 +284,7 @@ impl PoissonGame for Terrain {
         if let Some(terrain_mesh) = &mut self.terrain_mesh {
             terrain_mesh.set_mvp(p * v);
             terrain_mesh.set_light_direction(cg::Vector3::<f32>::new(0.0, 1.0, -0.5));
-            terrain_mesh.set_view_direction(self.flight_params.pos);
+            terrain_mesh.set_view_direction(camera.pos);
         }
     }
 
@@ -7,7 +7,7 @@ use poisson_renderer::{init_logger, run_game, shader, PoissonGame};
 use console_error_panic_hook;
 use poisson_renderer::input::Input;
 use poisson_renderer::render_backend::{DrawletHandle, Mat4Ubo, PipelineHandle, RenderBackend, PassHandle};
-use poisson_renderer::render_backend::web::{CreateDrawletWgpu, WgpuRenderBackend};
+use poisson_renderer::render_backend::web::WgpuRenderBackend;
 use winit::keyboard::{KeyCode, PhysicalKey};
 use cgmath as cg;
 use cgmath::{SquareMatrix, Vector3};
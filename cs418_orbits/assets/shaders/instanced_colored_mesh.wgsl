@@ -0,0 +1,57 @@
+struct _MatrixStorage_float4x4std140_0
+{
+    @align(16) data_0 : array<vec4<f32>, i32(4)>,
+};
+
+struct Uniform_std140_0
+{
+    @align(16) view_proj_0 : _MatrixStorage_float4x4std140_0,
+};
+
+@binding(0) @group(0) var<uniform> uniform_var_0 : Uniform_std140_0;
+struct VertexStageOutput_0
+{
+    @location(0) color_0 : vec4<f32>,
+    @builtin(position) sv_position_0 : vec4<f32>,
+};
+
+struct vertexInput_0
+{
+    @location(0) position_0 : vec3<f32>,
+    @location(1) color_1 : vec3<f32>,
+    @location(2) model_0_0 : vec4<f32>,
+    @location(3) model_1_0 : vec4<f32>,
+    @location(4) model_2_0 : vec4<f32>,
+    @location(5) model_3_0 : vec4<f32>,
+    @location(6) tint_0 : vec4<f32>,
+};
+
+@vertex
+fn vertex( _S1 : vertexInput_0) -> VertexStageOutput_0
+{
+    var view_proj_1 : mat4x4<f32> = mat4x4<f32>(uniform_var_0.view_proj_0.data_0[i32(0)], uniform_var_0.view_proj_0.data_0[i32(1)], uniform_var_0.view_proj_0.data_0[i32(2)], uniform_var_0.view_proj_0.data_0[i32(3)]);
+    var model_4 : mat4x4<f32> = mat4x4<f32>(_S1.model_0_0, _S1.model_1_0, _S1.model_2_0, _S1.model_3_0);
+    var world_position_0 : vec4<f32> = model_4 * vec4<f32>(_S1.position_0, 1.0f);
+    var _S2 : VertexStageOutput_0;
+    _S2.color_0 = vec4<f32>(_S1.color_1 * _S1.tint_0.xyz, _S1.tint_0.w);
+    _S2.sv_position_0 = view_proj_1 * world_position_0;
+    return _S2;
+}
+
+struct Fragment_0
+{
+    @location(0) color_2 : vec4<f32>,
+};
+
+struct pixelInput_0
+{
+    @location(0) color_3 : vec4<f32>,
+};
+
+@fragment
+fn fragment( _S3 : pixelInput_0) -> Fragment_0
+{
+    var output_0 : Fragment_0;
+    output_0.color_2 = _S3.color_3;
+    return output_0;
+}
@@ -0,0 +1,76 @@
+struct _MatrixStorage_float4x4std140_0
+{
+    @align(16) data_0 : array<vec4<f32>, i32(4)>,
+};
+
+struct Uniform_std140_0
+{
+    @align(16) view_proj_0 : _MatrixStorage_float4x4std140_0,
+};
+
+@binding(0) @group(0) var<uniform> uniform_var_0 : Uniform_std140_0;
+struct Light_std140_0
+{
+    @align(16) light_dir_0 : vec4<f32>,
+};
+
+@binding(0) @group(1) var<uniform> light_var_0 : Light_std140_0;
+struct View_std140_0
+{
+    @align(16) view_dir_0 : vec4<f32>,
+};
+
+@binding(0) @group(2) var<uniform> view_var_0 : View_std140_0;
+struct VertexStageOutput_0
+{
+    @location(0) color_0 : vec4<f32>,
+    @location(1) normal_0 : vec3<f32>,
+    @builtin(position) sv_position_0 : vec4<f32>,
+};
+
+struct vertexInput_0
+{
+    @location(0) position_0 : vec3<f32>,
+    @location(1) color_1 : vec4<f32>,
+    @location(2) normal_1 : vec3<f32>,
+    @location(3) model_0_0 : vec4<f32>,
+    @location(4) model_1_0 : vec4<f32>,
+    @location(5) model_2_0 : vec4<f32>,
+    @location(6) model_3_0 : vec4<f32>,
+    @location(7) tint_0 : vec4<f32>,
+};
+
+@vertex
+fn vertex( _S1 : vertexInput_0) -> VertexStageOutput_0
+{
+    var view_proj_1 : mat4x4<f32> = mat4x4<f32>(uniform_var_0.view_proj_0.data_0[i32(0)], uniform_var_0.view_proj_0.data_0[i32(1)], uniform_var_0.view_proj_0.data_0[i32(2)], uniform_var_0.view_proj_0.data_0[i32(3)]);
+    var model_4 : mat4x4<f32> = mat4x4<f32>(_S1.model_0_0, _S1.model_1_0, _S1.model_2_0, _S1.model_3_0);
+    var world_position_0 : vec4<f32> = model_4 * vec4<f32>(_S1.position_0, 1.0f);
+    var world_normal_0 : vec4<f32> = model_4 * vec4<f32>(_S1.normal_1, 0.0f);
+    var _S2 : VertexStageOutput_0;
+    _S2.color_0 = _S1.color_1 * _S1.tint_0;
+    _S2.normal_0 = world_normal_0.xyz;
+    _S2.sv_position_0 = view_proj_1 * world_position_0;
+    return _S2;
+}
+
+struct Fragment_0
+{
+    @location(0) color_2 : vec4<f32>,
+};
+
+struct pixelInput_0
+{
+    @location(0) color_3 : vec4<f32>,
+    @location(1) normal_2 : vec3<f32>,
+};
+
+@fragment
+fn fragment( _S3 : pixelInput_0) -> Fragment_0
+{
+    var light_vec_0 : vec3<f32> = normalize(light_var_0.light_dir_0.xyz);
+    var normal_vec_0 : vec3<f32> = normalize(_S3.normal_2);
+    var output_0 : Fragment_0;
+    output_0.color_2 = vec4<f32>(vec3<f32>(0.10000000149011612f, 0.10000000149011612f, 0.10000000149011612f) + vec3<f32>(max(dot(light_vec_0, normal_vec_0), 0.0f)) * _S3.color_3.xyz + vec3<f32>(0.40000000596046448f) * pow(vec3<f32>(max(dot(vec3<f32>((2.0f * dot(normal_vec_0, light_vec_0))) * normal_vec_0 - light_vec_0, normalize(view_var_0.view_dir_0.xyz)), 0.0f)), vec3<f32>(vec3<i32>(i32(50)))), _S3.color_3.w);
+    return output_0;
+}
@@ -1,23 +1,27 @@
 use cgmath as cg;
-use cgmath::{Matrix4, SquareMatrix, Vector3};
+use cgmath::{EuclideanSpace, Matrix4, SquareMatrix, Vector3};
 use console_error_panic_hook;
 use fs_embed::fs_embed;
-use instant::Instant;
-use poisson_renderer::input::Input;
+use poisson_renderer::input::{AxisBinding, AxisSettings, AxisSource, Input, VectorBinding, VectorSettings};
+use poisson_renderer::time::Time;
+use poisson_renderer::context::EngineContext;
 use poisson_renderer::math::utils::perspective;
-use poisson_renderer::render_backend::web::{CreateDrawletWgpu, EguiUiShow, WgpuPipeline, WgpuRenderBackend};
-use poisson_renderer::render_backend::RenderBackend;
+use poisson_renderer::render_backend::web::WgpuRenderBackend;
+use poisson_renderer::render_backend::{EguiUiShow, RenderBackend};
 use poisson_renderer::{init_logger, run_game, shader, PoissonGame};
 use std::error::Error;
 use std::f32::consts::PI;
 use std::sync::Arc;
 use std::task::Context;
+use poisson_renderer::render_backend::InstanceID;
 use poisson_renderer::render_backend::render_interface::drawlets::{DrawletHandle, PassHandle, PipelineHandle, PipelineTrait};
+use poisson_renderer::render_backend::render_interface::drawlets::instanced_mesh::{Instance, InstancedColoredMesh, InstancedColoredMeshData, InstancedLitMesh, InstancedLitMeshData};
+use poisson_renderer::render_backend::render_interface::drawlets::lit_colored_mesh::NormalColoredVertex;
 use poisson_renderer::render_backend::render_interface::Mesh;
 use rj::Own;
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::wasm_bindgen;
-use poisson_renderer::render_backend::render_interface::drawlets::colored_mesh::{ColoredMesh, ColoredMeshData, ColoredVertex};
+use poisson_renderer::render_backend::render_interface::drawlets::colored_mesh::ColoredVertex;
 
 #[cfg_attr(target_arch="wasm32", wasm_bindgen(start))]
 pub async fn run_wasm() {
@@ -30,8 +34,56 @@ pub fn run() ->  Result<(), impl Error> {
     run_game::<Orbits>()
 }
 
+// the shader only has directional lights, so the planets and moons share one from above the orbits
+const LIGHT_DIRECTION: Vector3<f32> = Vector3 { x: 1f32, y: 2f32, z: 1f32 };
+
+// every face gets its own vertices so it is lit evenly, the meshes are centered on the origin
+// so the normals are flipped to point away from it
+fn flat_shaded(vertices: &[ColoredVertex], indices: &[u32]) -> Vec<NormalColoredVertex> {
+    indices.chunks(3).flat_map(|face| {
+        let [a, b, c] = [face[0], face[1], face[2]].map(|i| Vector3::from(vertices[i as usize].pos));
+        let mut normal = (b - a).cross(c - a);
+        if cg::dot(normal, a + b + c) < 0f32 {
+            normal = -normal;
+        }
+        face.iter().map(move |i| {
+            let vertex = &vertices[*i as usize];
+            NormalColoredVertex {
+                pos: vertex.pos,
+                color: [vertex.color[0], vertex.color[1], vertex.color[2], 1f32],
+                normal: normal.into(),
+            }
+        })
+    }).collect()
+}
+
+// the sun glows on its own, everything else is lit by it
+#[derive(Clone)]
+enum BodyDrawlet {
+    Glowing(DrawletHandle<InstancedColoredMesh>),
+    Lit(DrawletHandle<InstancedLitMesh>),
+}
+
+impl BodyDrawlet {
+    fn add_instance(self: &mut Self, instance: Instance) -> Option<InstanceID> {
+        match self {
+            BodyDrawlet::Glowing(drawlet) => drawlet.add_instance(instance),
+            BodyDrawlet::Lit(drawlet) => drawlet.add_instance(instance),
+        }
+    }
+
+    fn update_instance(self: &mut Self, id: InstanceID, instance: Instance) {
+        match self {
+            BodyDrawlet::Glowing(drawlet) => drawlet.update_instance(id, instance),
+            BodyDrawlet::Lit(drawlet) => drawlet.update_instance(id, instance),
+        }
+    }
+}
+
 struct CelestialBody {
-    drawlet: DrawletHandle<ColoredMesh>,
+    // shared by every body with the same mesh
+    drawlet: BodyDrawlet,
+    instance: InstanceID,
     base_position: cg::Vector3<f32>,
     transform: Matrix4<f32>,
     spin_speed: f32,
@@ -45,22 +97,20 @@ struct CelestialBody {
 }
 
 impl CelestialBody {
-    fn new(pipeline: &mut PipelineHandle<ColoredMesh>,
-           mesh: &Arc<Mesh<ColoredVertex>>,
+    fn new(drawlet: &BodyDrawlet,
            spin_speed: f32,
            revolve_radius: f32,
            revolve_speed: f32,
            scale: f32
     ) -> Self
     {
-        let body_data = ColoredMeshData {
-            mvp_data: Matrix4::identity(),
-            mesh: mesh.clone(),
-        };
-        let drawlet = pipeline.create_drawlet(body_data);
+        let mut drawlet = drawlet.clone();
+        let instance = drawlet.add_instance(Instance::default())
+            .expect("mesh drawlet was removed");
 
         CelestialBody {
             drawlet,
+            instance,
             base_position: cg::Vector3 {x: 0f32, y: 0f32, z: 0f32 },
             transform: Matrix4::identity(),
             spin_speed,
@@ -74,29 +124,39 @@ impl CelestialBody {
         }
     }
 
-    fn set_mvp(self: &mut Self, mvp: cgmath::Matrix4<f32>) {
-        self.drawlet.set_mvp(mvp)
+    fn set_model(self: &mut Self, model: cgmath::Matrix4<f32>) {
+        self.drawlet.update_instance(self.instance, Instance::from_model(model))
     }
 
-    pub fn update(&mut self, renderer: &mut WgpuRenderBackend, view_proj: cgmath::Matrix4<f32>, dt: f32) {
+    pub fn simulate(&mut self, dt: f32) {
         self.spin_angle += dt * self.spin_speed;
         self.revolve_angle += dt * self.revolve_speed;
 
-        let rotation = Matrix4::<f32>::from_angle_y(cg::Rad(self.spin_angle));
+        for c in &mut self.children {
+            c.access().simulate(dt);
+        }
+    }
+
+    // `lookahead` is the time since the last simulation step, used to interpolate between steps
+    pub fn update(&mut self, renderer: &mut impl RenderBackend, lookahead: f32) {
+        let spin_angle = self.spin_angle + lookahead * self.spin_speed;
+        let revolve_angle = self.revolve_angle + lookahead * self.revolve_speed;
+
+        let rotation = Matrix4::<f32>::from_angle_y(cg::Rad(spin_angle));
         let translation = Vector3 {
-            x: self.revolve_radius *  self.revolve_angle.sin(),
+            x: self.revolve_radius *  revolve_angle.sin(),
             y: 0f32,
-            z: self.revolve_radius *  self.revolve_angle.cos()
+            z: self.revolve_radius *  revolve_angle.cos()
         };
         let scale = Matrix4::<f32>::from_scale(self.scale);
 
         let tform =  Matrix4::from_translation(self.base_position + translation)* rotation * scale;
 
-        self.set_mvp(view_proj * tform);
+        self.set_model(tform);
 
         for c in &mut self.children {
             c.access().base_position = self.base_position + translation;
-            c.access().update(renderer, view_proj, dt);
+            c.access().update(renderer, lookahead);
         }
     }
 
@@ -113,12 +173,17 @@ impl CelestialBody {
 
 pub struct Orbits {
     scene_render_pass: Option<PassHandle>,
-    colored_mesh_pipeline: Option<PipelineHandle<ColoredMesh>>,
+    instanced_mesh_pipeline: Option<PipelineHandle<InstancedColoredMesh>>,
+    instanced_lit_mesh_pipeline: Option<PipelineHandle<InstancedLitMesh>>,
+    // one drawlet per mesh, each body is an instance of one of them
+    glowing_drawlets: Vec<DrawletHandle<InstancedColoredMesh>>,
+    lit_drawlets: Vec<DrawletHandle<InstancedLitMesh>>,
     sun: Option<CelestialBody>,
-    last_time: Instant,
-    elapsed_time: f32,
     assets: fs_embed::Dir,
     egui_state: EguiState,
+    camera_yaw: f32,
+    camera_pitch: f32,
+    camera_distance: f32,
 }
 
 impl PoissonGame for Orbits {
@@ -129,21 +194,37 @@ impl PoissonGame for Orbits {
 
         Self {
             scene_render_pass: None,
-            colored_mesh_pipeline: None,
+            instanced_mesh_pipeline: None,
+            instanced_lit_mesh_pipeline: None,
+            glowing_drawlets: Vec::new(),
+            lit_drawlets: Vec::new(),
             sun: None,
-            last_time: Instant::now(),
-            elapsed_time: 0f32,
             assets: FILES.clone().auto_dynamic(),
             egui_state: EguiState {},
+            camera_yaw: 0f32,
+            camera_pitch: 0.245f32,
+            camera_distance: 8.25f32,
         }
     }
 
     fn pre_init(self: &mut Self, input: &mut Input) {
-        //input.set_mapping("up", vec![PhysicalKey::Code(KeyCode::KeyW)]);
+        // one finger orbits, two fingers or the wheel zoom
+        input.set_vector_binding("orbit", VectorBinding {
+            x: vec![AxisSource::TouchDragX],
+            y: vec![AxisSource::TouchDragY],
+            settings: VectorSettings { sensitivity: 0.01f32, normalize: false, ..Default::default() },
+        });
+        input.set_axis_binding("zoom", AxisBinding {
+            sources: vec![AxisSource::Pinch],
+            settings: AxisSettings::default(),
+        });
+        input.set_axis_binding("zoom_wheel", AxisBinding {
+            sources: vec![AxisSource::WheelY],
+            settings: AxisSettings { sensitivity: 0.1f32, ..Default::default() },
+        });
     }
 
-    fn init(self: &mut Self, _input: &mut Input, renderer: &mut Self::Ren) {
-        self.last_time = Instant::now();
+    fn init(self: &mut Self, _input: &mut Input, renderer: &mut Self::Ren, _context: &mut EngineContext) {
         let tetrahedron_indices = [0u32, 1, 2, 0, 2, 3, 0, 3, 1, 1, 2, 3];
         let tetrahedron_vertices = vec![
             ColoredVertex {pos: [ 1f32,  1f32,  1f32], color: [1f32, 1f32, 1f32]},
@@ -151,8 +232,9 @@ impl PoissonGame for Orbits {
             ColoredVertex {pos: [-1f32,  1f32, -1f32], color: [0f32, 1f32, 0f32]},
             ColoredVertex {pos: [ 1f32, -1f32, -1f32], color: [1f32, 0f32, 0f32]}
         ];
+        let tetrahedron_vertices = flat_shaded(&tetrahedron_vertices, &tetrahedron_indices);
         let tetrahedron_mesh = Arc::new(Mesh {
-            index: renderer.create_index_buffer(&tetrahedron_indices),
+            index: renderer.create_index_buffer(&(0..tetrahedron_vertices.len() as u32).collect::<Vec<_>>()),
             vertex: renderer.create_vertex_buffer(tetrahedron_vertices.as_slice())
         });
         
@@ -172,28 +254,56 @@ impl PoissonGame for Orbits {
             index: renderer.create_index_buffer(octahedron_indices.as_slice()),
             vertex: renderer.create_vertex_buffer(octahedron_vertices.as_slice())
         });
+        let lit_octahedron_vertices = flat_shaded(&octahedron_vertices, &octahedron_indices);
+        let lit_octahedron_mesh = Arc::new(Mesh {
+            index: renderer.create_index_buffer(&(0..lit_octahedron_vertices.len() as u32).collect::<Vec<_>>()),
+            vertex: renderer.create_vertex_buffer(lit_octahedron_vertices.as_slice())
+        });
 
-        let triangle_shader = self.assets.get_file(shader!("shaders/colored_mesh")).unwrap();
-        let triangle_shader_content = triangle_shader.read_str().unwrap();
+        let instanced_shader = self.assets.get_file(shader!("shaders/instanced_colored_mesh")).unwrap();
+        let instanced_shader_content = instanced_shader.read_str().unwrap();
+        let instanced_lit_shader = self.assets.get_file(shader!("shaders/instanced_lit_mesh")).unwrap();
+        let instanced_lit_shader_content = instanced_lit_shader.read_str().unwrap();
 
         let mut r_handle = renderer.create_render_pass();
 
-        let mut p_handle = r_handle.create_colored_mesh_pipeline(
-                "cs418_logo/assets/shaders/colored_mesh",
-                triangle_shader_content.as_str());
+        let mut p_handle = r_handle.create_pipeline::<InstancedColoredMesh>(
+                "cs418_orbits/assets/shaders/instanced_colored_mesh",
+                instanced_shader_content.as_str());
+
+        let mut lit_p_handle = r_handle.create_pipeline::<InstancedLitMesh>(
+                "cs418_orbits/assets/shaders/instanced_lit_mesh",
+                instanced_lit_shader_content.as_str());
+
+        let sun_drawlet = p_handle.create_drawlet(InstancedColoredMeshData {
+            view_proj: Matrix4::identity(),
+            mesh: octahedron_mesh,
+        });
+        let lit_data = |mesh| InstancedLitMeshData {
+            view_proj: Matrix4::identity(),
+            light_dir: LIGHT_DIRECTION.extend(0f32),
+            view_dir: cg::Vector4::new(0f32, 0f32, 1f32, 0f32),
+            mesh,
+        };
+        let octahedra_drawlet = lit_p_handle.create_drawlet(lit_data(lit_octahedron_mesh));
+        let tetrahedra_drawlet = lit_p_handle.create_drawlet(lit_data(tetrahedron_mesh));
+
+        let sun = BodyDrawlet::Glowing(sun_drawlet.clone());
+        let octahedra = BodyDrawlet::Lit(octahedra_drawlet.clone());
+        let tetrahedra = BodyDrawlet::Lit(tetrahedra_drawlet.clone());
 
         self.sun = Some(CelestialBody::new(
-            &mut p_handle, &octahedron_mesh,
+            &sun,
             PI, 0f32, 0f32, 1f32
         ));
 
         let earth = rj::Own::new(CelestialBody::new(
-                 &mut p_handle, &octahedron_mesh,
+                 &octahedra,
                 4f32*PI, 2f32, 0.2f32*PI, 0.2f32
         ));
         let moon = rj::Own::new(
             CelestialBody::new(
-                 &mut p_handle, &tetrahedron_mesh,
+                 &tetrahedra,
                 2f32*PI, 0.5f32, 2f32*PI, 0.08f32
             ));
         earth.access().add_child(moon);
@@ -201,18 +311,18 @@ impl PoissonGame for Orbits {
 
         let mars = rj::Own::new(
             CelestialBody::new(
-                &mut p_handle, &octahedron_mesh,
+                &octahedra,
                 4f32/2.2f32 * PI, 2f32 * 1.6f32, 0.2f32 / 1.9f32 *PI, 0.2f32 * 0.9f32
             )
         );
 
         let phobos = rj::Own::new(
             CelestialBody::new(
-                &mut p_handle, &tetrahedron_mesh,
+                &tetrahedra,
                 6f32 * PI, 0.4f32, 4f32 * PI, 0.1f32));
 
         let deimos = rj::Own::new(
-            CelestialBody::new(&mut p_handle, &tetrahedron_mesh,
+            CelestialBody::new(&tetrahedra,
                 4f32/2.0f32 * PI, 0.8f32, 4f32/2.0f32 * PI, 0.05f32)
         );
 
@@ -226,23 +336,48 @@ impl PoissonGame for Orbits {
         self.sun.as_mut().unwrap().add_child(earth);
         self.sun.as_mut().unwrap().add_child(mars);
 
+        self.glowing_drawlets = vec![sun_drawlet];
+        self.lit_drawlets = vec![octahedra_drawlet, tetrahedra_drawlet];
+        self.instanced_mesh_pipeline = Some(p_handle);
+        self.instanced_lit_mesh_pipeline = Some(lit_p_handle);
+        self.scene_render_pass = Some(r_handle);
     }
 
-    fn update(self: &mut Self, input: &mut Input, renderer: &mut Self::Ren) {
-        let delta_time = self.last_time.elapsed().as_secs_f32();
-        self.last_time = Instant::now();
-
-        self.elapsed_time += delta_time;
+    fn fixed_update(self: &mut Self, _input: &mut Input, time: &mut Time) {
+        if let Some(sun) = self.sun.as_mut() {
+            sun.simulate(time.fixed_timestep());
+        }
+    }
 
+    fn update(self: &mut Self, input: &mut Input, renderer: &mut Self::Ren, time: &mut Time, _context: &mut EngineContext) {
+        let orbit = input.vector("orbit");
+        self.camera_yaw -= orbit.x;
+        self.camera_pitch = (self.camera_pitch + orbit.y).clamp(-1.5f32, 1.5f32);
+        let zoom = input.axis("zoom") + input.axis("zoom_wheel");
+        self.camera_distance = (self.camera_distance / (1f32 + zoom).max(0.1f32)).clamp(2f32, 30f32);
+
+        let eye = cgmath::Point3::new(
+            self.camera_distance * self.camera_pitch.cos() * self.camera_yaw.sin(),
+            self.camera_distance * self.camera_pitch.sin(),
+            self.camera_distance * self.camera_pitch.cos() * self.camera_yaw.cos());
         let v = cgmath::Matrix4::look_at_rh(
-            cgmath::Point3::new(0.0, 2.0, 8.0),
+            eye,
             cgmath::Point3::new(0.0, 0.0, 0.0),
             cgmath::Vector3::new(0.0, 1.0, 0.0));
         let aspect_ratio = (renderer.get_width() as f32)/(renderer.get_height() as f32);
 
         let p = perspective(PI/12f32, aspect_ratio, 0.1, 100.0, Self::Ren::PERSPECTIVE_ALIGNMENT);
 
-        self.sun.as_mut().unwrap().update(renderer, p * v, delta_time);
+        for drawlet in &mut self.glowing_drawlets {
+            drawlet.set_view_proj(p * v);
+        }
+        for drawlet in &mut self.lit_drawlets {
+            drawlet.set_view_proj(p * v);
+            drawlet.set_view_direction(eye.to_vec());
+        }
+
+        let lookahead = time.alpha() * time.fixed_timestep();
+        self.sun.as_mut().unwrap().update(renderer, lookahead);
 
     }
 
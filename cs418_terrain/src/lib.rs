@@ -4,11 +4,12 @@ use std::cell::RefCell;
 use cgmath as cg;
 use console_error_panic_hook;
 use fs_embed::fs_embed;
-use instant::Instant;
-use poisson_renderer::input::Input;
+use poisson_renderer::input::{AxisBinding, AxisSettings, AxisSource, Input, InputButton};
+use poisson_renderer::time::Time;
+use poisson_renderer::context::EngineContext;
 use poisson_renderer::math::utils::perspective;
-use poisson_renderer::render_backend::web::{CreateDrawletWgpu, EguiUiShow, WgpuPipeline, WgpuRenderBackend};
-use poisson_renderer::render_backend::RenderBackend;
+use poisson_renderer::render_backend::web::WgpuRenderBackend;
+use poisson_renderer::render_backend::{EguiUiShow, RenderBackend};
 use poisson_renderer::{init_logger, render_backend, run_game, shader, PoissonGame};
 use std::error::Error;
 use std::f32::consts::PI;
@@ -31,7 +32,8 @@ cfg_if::cfg_if! {
 
 
 use poisson_renderer::render_backend::render_interface::drawlets::colored_mesh::{ColoredMesh, ColoredMeshData, ColoredVertex};
-use poisson_renderer::render_backend::render_interface::drawlets::lit_colored_mesh::{LitColoredMesh, LitColoredMeshData};
+use poisson_renderer::render_backend::render_interface::drawlets::lit_colored_mesh::{LitColoredMesh, LitColoredMeshData, NormalColoredVertex};
+use poisson_renderer::render_backend::render_interface::resources::BufferUsage;
 
 #[cfg_attr(target_arch="wasm32", wasm_bindgen(start))]
 pub async fn run_wasm() {
@@ -52,13 +54,16 @@ pub struct TerrainParams {
 pub struct Terrain {
     //document: Option<Document>,
     terrain_mesh: Option<DrawletHandle<LitColoredMesh>>,
+    // rewritten in place when new params are submitted, the drawlet keeps drawing it
+    terrain_buffers: Option<Arc<Mesh<NormalColoredVertex>>>,
     scene_render_pass: Option<PassHandle>,
     lit_colored_mesh_pipeline: Option<PipelineHandle<LitColoredMesh>>,
-    last_time: Instant,
-    elapsed_time: f32,
     assets: fs_embed::Dir,
     egui_state: EguiState,
     terrain_params: Rc<RefCell<Option<TerrainParams>>>,
+    camera_angle: f32,
+    camera_zoom: f32,
+    auto_rotate: bool,
 }
 
 impl PoissonGame for Terrain {
@@ -72,19 +77,34 @@ impl PoissonGame for Terrain {
             scene_render_pass: None,
             lit_colored_mesh_pipeline: None,
             terrain_mesh: None,
-            last_time: Instant::now(),
-            elapsed_time: 0f32,
+            terrain_buffers: None,
             assets: FILES.clone().auto_dynamic(),
             egui_state: EguiState {},
             terrain_params: Rc::new(RefCell::new(None)),
+            camera_angle: 0f32,
+            camera_zoom: 1f32,
+            auto_rotate: true,
         }
     }
 
     fn pre_init(self: &mut Self, input: &mut Input) {
-        //input.set_mapping("up", vec![PhysicalKey::Code(KeyCode::KeyW)]);
+        // dragging orbits, pinching or the wheel zooms, tapping pauses the automatic rotation
+        input.set_axis_binding("orbit", AxisBinding {
+            sources: vec![AxisSource::TouchDragX],
+            settings: AxisSettings { sensitivity: 0.01f32, ..Default::default() },
+        });
+        input.set_axis_binding("zoom", AxisBinding {
+            sources: vec![AxisSource::Pinch],
+            settings: AxisSettings::default(),
+        });
+        input.set_axis_binding("zoom_wheel", AxisBinding {
+            sources: vec![AxisSource::WheelY],
+            settings: AxisSettings { sensitivity: 0.1f32, ..Default::default() },
+        });
+        input.set_mapping("toggle_rotation", vec![InputButton::Tap]);
     }
 
-    fn init(self: &mut Self, _input: &mut Input, renderer: &mut Self::Ren) {
+    fn init(self: &mut Self, _input: &mut Input, renderer: &mut Self::Ren, _context: &mut EngineContext) {
         cfg_if::cfg_if! {
         if #[cfg(target_arch="wasm32")] {
             let document = window().unwrap().document().unwrap();
@@ -114,8 +134,6 @@ impl PoissonGame for Terrain {
             })))
         }}
 
-        self.last_time = Instant::now();
-
         let lit_colored_mesh_shader = self.assets.get_file(shader!("shaders/lit_colored_mesh")).unwrap();
         let lit_colored_mesh_shader_content = lit_colored_mesh_shader.read_str().unwrap();
 
@@ -129,30 +147,34 @@ impl PoissonGame for Terrain {
         self.lit_colored_mesh_pipeline = Some(p_handle);
     }
 
-    fn update(self: &mut Self, input: &mut Input, renderer: &mut Self::Ren) {
+    fn update(self: &mut Self, input: &mut Input, renderer: &mut Self::Ren, time: &mut Time, _context: &mut EngineContext) {
         let params_submitted = self.terrain_params.borrow().is_some();
         if params_submitted {
             {
                 let data = self.terrain_params.borrow();
                 let data = data.as_ref().unwrap();
                 let mesh_grid = mesh::mesh_grid(data.grid_size - 1, data.faults, true);
-                let vertex_buffer = renderer.create_vertex_buffer(mesh_grid.0.as_slice());
-                let index_buffer = renderer.create_index_buffer(mesh_grid.1.as_slice());
-                let lit_mesh_data = LitColoredMeshData {
-                    mvp_data: cg::Matrix4::identity(),
-                    light_dir: cg::Vector4 {x: 1f32, y: 0f32, z: 0f32, w: 0f32},
-                    view_dir: cg::Vector4 {x: 1f32, y: 0f32, z: 0f32, w: 0f32},
-                    mesh: Arc::new(Mesh {
+                if let Some(terrain_buffers) = &self.terrain_buffers {
+                    terrain_buffers.vertex.set_data(mesh_grid.0.as_slice());
+                    terrain_buffers.index.set_data(mesh_grid.1.as_slice());
+                } else {
+                    let vertex_buffer = renderer.create_vertex_buffer_with_usage(mesh_grid.0.as_slice(), BufferUsage::Dynamic);
+                    let index_buffer = renderer.create_index_buffer_with_usage(mesh_grid.1.as_slice(), BufferUsage::Dynamic);
+                    let terrain_buffers = Arc::new(Mesh {
                         index: index_buffer,
                         vertex: vertex_buffer,
-                    }),
-                };
-                if let Some(drawlet)= self.terrain_mesh.take() {
-                    self.lit_colored_mesh_pipeline.as_mut().unwrap().remove_drawlet(drawlet);
+                    });
+                    let lit_mesh_data = LitColoredMeshData {
+                        mvp_data: cg::Matrix4::identity(),
+                        light_dir: cg::Vector4 {x: 1f32, y: 0f32, z: 0f32, w: 0f32},
+                        view_dir: cg::Vector4 {x: 1f32, y: 0f32, z: 0f32, w: 0f32},
+                        mesh: terrain_buffers.clone(),
+                    };
+
+                    self.terrain_mesh = Some(self.lit_colored_mesh_pipeline.as_mut().unwrap().create_drawlet(lit_mesh_data));
+                    self.terrain_mesh.as_mut().unwrap().set_light_direction(cg::Vector3::<f32>::new(2f32, 2f32, 2f32));
+                    self.terrain_buffers = Some(terrain_buffers);
                 }
-
-                self.terrain_mesh = Some(self.lit_colored_mesh_pipeline.as_mut().unwrap().create_drawlet(lit_mesh_data));
-                self.terrain_mesh.as_mut().unwrap().set_light_direction(cg::Vector3::<f32>::new(2f32, 2f32, 2f32));
             }
             self.terrain_params.replace(None);
         }
@@ -160,12 +182,18 @@ impl PoissonGame for Terrain {
         if let Some(terrain_params) = self.terrain_params.borrow().as_ref() {
             log::info!("TerrainParams: {}, {}", terrain_params.faults, terrain_params.grid_size);
         }
-        let delta_time = self.last_time.elapsed().as_secs_f32();
-        self.last_time = Instant::now();
-
-        self.elapsed_time += delta_time;
+        if input.just_pressed("toggle_rotation") {
+            self.auto_rotate = !self.auto_rotate;
+        }
+        if self.auto_rotate {
+            self.camera_angle += time.delta();
+        }
+        self.camera_angle += input.axis("orbit");
+        let zoom = input.axis("zoom") + input.axis("zoom_wheel");
+        self.camera_zoom = (self.camera_zoom / (1f32 + zoom).max(0.1f32)).clamp(0.3f32, 3f32);
 
-        let camera_center = cgmath::Vector3::new(3f32 * self.elapsed_time.cos(), 3f32, 3f32 * self.elapsed_time.sin());
+        let camera_center = cgmath::Vector3::new(
+            3f32 * self.camera_angle.cos(), 3f32, 3f32 * self.camera_angle.sin()) * self.camera_zoom;
 
         let v = cgmath::Matrix4::look_at_rh(
             cgmath::Point3::from_vec(camera_center),
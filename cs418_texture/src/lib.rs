@@ -4,11 +4,12 @@ use std::cell::{Ref, RefCell};
 use cgmath as cg;
 use console_error_panic_hook;
 use fs_embed::fs_embed;
-use instant::Instant;
 use poisson_renderer::input::Input;
+use poisson_renderer::time::Time;
+use poisson_renderer::context::EngineContext;
 use poisson_renderer::math::utils::perspective;
-use poisson_renderer::render_backend::web::{CreateDrawletWgpu, EguiUiShow, WgpuPipeline, WgpuRenderBackend};
-use poisson_renderer::render_backend::RenderBackend;
+use poisson_renderer::render_backend::web::WgpuRenderBackend;
+use poisson_renderer::render_backend::{EguiUiShow, RenderBackend};
 use poisson_renderer::{init_logger, render_backend, run_game, shader, PoissonGame};
 use std::error::Error;
 use std::f32::consts::PI;
@@ -28,6 +29,7 @@ use web_sys::{CanvasRenderingContext2d, Document, Event, HtmlCanvasElement, Html
 
 use poisson_renderer::render_backend::render_interface::drawlets::{DrawletHandle, PassHandle, PipelineHandle, PipelineTrait};
 use poisson_renderer::render_backend::render_interface::Mesh;
+use poisson_renderer::render_backend::render_interface::resources::{BufferUsage, GpuTextureHandle};
 use rj::Own;
 
 cfg_if::cfg_if! {
@@ -41,7 +43,6 @@ cfg_if::cfg_if! {
 use poisson_renderer::render_backend::render_interface::drawlets::colored_mesh::{ColoredMesh, ColoredMeshData, ColoredVertex};
 use poisson_renderer::render_backend::render_interface::drawlets::lit_colored_mesh::{LitColoredMesh, LitColoredMeshData, NormalColoredVertex};
 use poisson_renderer::render_backend::render_interface::drawlets::textured_mesh::{TexturedMesh, TexturedMeshData, UvVertex};
-use poisson_renderer::render_backend::web::textured_mesh::TexturedMeshPipeline;
 use crate::TextureColor::{Color, Texture};
 
 #[cfg_attr(target_arch="wasm32", wasm_bindgen(start))]
@@ -103,11 +104,12 @@ enum ColoredOrTexturedMesh {
 pub struct Terrain {
     //document: Option<Document>,
     terrain_mesh: Option<ColoredOrTexturedMesh>,
+    textured_buffers: Option<Arc<Mesh<UvVertex>>>,
+    colored_buffers: Option<Arc<Mesh<NormalColoredVertex>>>,
+    texture: Option<GpuTextureHandle>,
     scene_render_pass: Option<PassHandle>,
     lit_colored_mesh_pipeline: Option<PipelineHandle<LitColoredMesh>>,
     textured_mesh_pipeline: Option<PipelineHandle<TexturedMesh>>,
-    last_time: Instant,
-    elapsed_time: f32,
     assets: fs_embed::Dir,
     egui_state: EguiState,
     terrain_params: Rc<RefCell<Option<TerrainParams>>>,
@@ -129,8 +131,9 @@ impl PoissonGame for Terrain {
             scene_render_pass: None,
             lit_colored_mesh_pipeline: None,
             terrain_mesh: None,
-            last_time: Instant::now(),
-            elapsed_time: 0f32,
+            textured_buffers: None,
+            colored_buffers: None,
+            texture: None,
             assets: FILES.clone().auto_dynamic(),
             egui_state: EguiState {},
             terrain_params: Rc::new(RefCell::new(None)),
@@ -147,7 +150,7 @@ impl PoissonGame for Terrain {
         //input.set_mapping("up", vec![PhysicalKey::Code(KeyCode::KeyW)]);
     }
 
-    fn init(self: &mut Self, _input: &mut Input, renderer: &mut Self::Ren) {
+    fn init(self: &mut Self, _input: &mut Input, renderer: &mut Self::Ren, _context: &mut EngineContext) {
         cfg_if::cfg_if! {
             if #[cfg(target_arch="wasm32")] {
                 let document = window().unwrap().document().unwrap();
@@ -240,8 +243,6 @@ impl PoissonGame for Terrain {
             }
         }
 
-        self.last_time = Instant::now();
-
         let lit_colored_mesh_shader = self.assets.get_file(shader!("shaders/lit_colored_mesh")).unwrap();
         let lit_colored_mesh_shader_content = lit_colored_mesh_shader.read_str().unwrap();
 
@@ -262,7 +263,23 @@ impl PoissonGame for Terrain {
         self.textured_mesh_pipeline = Some(textured_mesh_pipeline);
     }
 
-    fn update(self: &mut Self, input: &mut Input, renderer: &mut Self::Ren) {
+    fn update(self: &mut Self, input: &mut Input, renderer: &mut Self::Ren, time: &mut Time, _context: &mut EngineContext) {
+        // dragging an image onto the window textures the terrain with it
+        for file in input.take_dropped_files() {
+            if !matches!(file.extension().as_deref(), Some("png" | "jpg" | "jpeg")) {
+                log::info!("ignoring dropped file {}", file.name);
+                continue;
+            }
+            match image::load_from_memory(file.bytes.as_slice()) {
+                Ok(image) => {
+                    log::info!("loaded dropped texture {}", file.name);
+                    self.texture_color.replace(Texture(image.to_rgba8()));
+                    self.texture_color_updated.replace(true);
+                }
+                Err(err) => log::warn!("failed to decode dropped texture {}: {err}", file.name),
+            }
+        }
+
         let params_submitted = self.terrain_params.borrow().is_some();
         if params_submitted {
             {
@@ -280,72 +297,41 @@ impl PoissonGame for Terrain {
                 }
                 self.index_list = mesh_grid.1;
 
+                let color = match &*self.texture_color.borrow() {
+                    Color((r, g, b, a)) => [*r, *g, *b, *a],
+                    Texture(_) => [1.0; 4],
+                };
                 self.color_vertex_list = Vec::new();
                 for vertex in &mesh_grid.0 {
                     self.color_vertex_list.push(
                         NormalColoredVertex {
                             pos: vertex.pos,
-                            color: [1.0; 4],
+                            color,
                             normal: vertex.normal,
                         }
                     )
                 }
 
-                match &(*self.texture_color.borrow()) {
-                    Texture(tex) => {
-                        let vertex_buffer = renderer.create_vertex_buffer(self.texture_vertex_list.as_slice());
-                        let index_buffer = renderer.create_index_buffer(self.index_list.as_slice());
-                        let lit_mesh_data = TexturedMeshData {
-                            mvp_data: cg::Matrix4::identity(),
-                            // light_dir: cg::Vector4 {x: 1f32, y: 0f32, z: 0f32, w: 0f32},
-                            // view_dir: cg::Vector4 {x: 1f32, y: 0f32, z: 0f32, w: 0f32},
-                            mesh: Arc::new(Mesh {
-                                index: index_buffer,
-                                vertex: vertex_buffer,
-                            }),
-                            texture_data: DynamicImage::ImageRgba8(tex.clone()),
-                        };
-
-                        if let Some(drawlet)= self.terrain_mesh.take() {
-                            match drawlet {
-                                ColoredOrTexturedMesh::ColoredMesh(drawlet) => { self.lit_colored_mesh_pipeline.as_mut().unwrap().remove_drawlet(drawlet); }
-                                ColoredOrTexturedMesh::TexturedMesh(drawlet) => { self.textured_mesh_pipeline.as_mut().unwrap().remove_drawlet(drawlet); }
-                            }
-                        }
-                        self.terrain_mesh = Some(ColoredOrTexturedMesh::TexturedMesh(
-                            self.textured_mesh_pipeline.as_mut().unwrap().create_drawlet(lit_mesh_data)
-                        ));
-                    }
-                    Color((r, g, b, a)) => {
-                        log::info!("setting vertex color, {}, {}, {}, {}", r, g, b, a);
-                        for vertex in &mut self.color_vertex_list {
-                            vertex.color = [*r, *g, *b, *a];
-                        }
-
-                        let vertex_buffer = renderer.create_vertex_buffer(self.color_vertex_list.as_slice());
-                        let index_buffer = renderer.create_index_buffer(self.index_list.as_slice());
-                        let lit_mesh_data = LitColoredMeshData {
-                            mvp_data: cg::Matrix4::identity(),
-                            light_dir: cg::Vector4 {x: 1f32, y: 0f32, z: 0f32, w: 0f32},
-                            view_dir: cg::Vector4 {x: 1f32, y: 0f32, z: 0f32, w: 0f32},
-                            mesh: Arc::new(Mesh {
-                                index: index_buffer,
-                                vertex: vertex_buffer,
-                            }),
-                        };
-
-                        if let Some(drawlet)= self.terrain_mesh.take() {
-                            match drawlet {
-                                ColoredOrTexturedMesh::ColoredMesh(drawlet) => { self.lit_colored_mesh_pipeline.as_mut().unwrap().remove_drawlet(drawlet); }
-                                ColoredOrTexturedMesh::TexturedMesh(drawlet) => { self.textured_mesh_pipeline.as_mut().unwrap().remove_drawlet(drawlet); }
-                            }
-                        }
-                        self.terrain_mesh = Some(ColoredOrTexturedMesh::ColoredMesh(
-                            self.lit_colored_mesh_pipeline.as_mut().unwrap().create_drawlet(lit_mesh_data)
-                        ));
-                    }
+                // both meshes share the index buffer, the drawlets see the new grid without being recreated
+                if let (Some(textured), Some(colored)) = (&self.textured_buffers, &self.colored_buffers) {
+                    textured.index.set_data(self.index_list.as_slice());
+                    textured.vertex.set_data(self.texture_vertex_list.as_slice());
+                    colored.vertex.set_data(self.color_vertex_list.as_slice());
+                } else {
+                    let index_buffer = renderer.create_index_buffer_with_usage(self.index_list.as_slice(), BufferUsage::Dynamic);
+                    self.textured_buffers = Some(Arc::new(Mesh {
+                        index: index_buffer.clone(),
+                        vertex: renderer.create_vertex_buffer_with_usage(self.texture_vertex_list.as_slice(), BufferUsage::Dynamic),
+                    }));
+                    self.colored_buffers = Some(Arc::new(Mesh {
+                        index: index_buffer,
+                        vertex: renderer.create_vertex_buffer_with_usage(self.color_vertex_list.as_slice(), BufferUsage::Dynamic),
+                    }));
                 }
             }
+            if self.terrain_mesh.is_none() {
+                self.texture_color_updated.replace(true);
+            }
             self.terrain_params.replace(None);
         }
 
@@ -354,64 +340,59 @@ impl PoissonGame for Terrain {
                 Texture(tex) => {
                     log::info!("found image of size {}, {}", tex.width(), tex.height());
 
-                    let vertex_buffer = renderer.create_vertex_buffer(self.texture_vertex_list.as_slice());
-                    let index_buffer = renderer.create_index_buffer(self.index_list.as_slice());
-                    let lit_mesh_data = TexturedMeshData {
-                        mvp_data: cg::Matrix4::identity(),
-                        mesh: Arc::new(Mesh {
-                            index: index_buffer,
-                            vertex: vertex_buffer,
-                        }),
-                        texture_data: DynamicImage::ImageRgba8(tex.clone()),
+                    // an image of the same size is uploaded into the texture the drawlet already samples
+                    let texture = match &self.texture {
+                        Some(texture) if (texture.get_width(), texture.get_height()) == tex.dimensions() => {
+                            texture.update_region(0, 0, tex);
+                            texture.clone()
+                        }
+                        _ => renderer.create_texture(&DynamicImage::ImageRgba8(tex.clone())),
                     };
 
-                    if let Some(drawlet)= self.terrain_mesh.take() {
-                        match drawlet {
-                            ColoredOrTexturedMesh::ColoredMesh(drawlet) => { self.lit_colored_mesh_pipeline.as_mut().unwrap().remove_drawlet(drawlet); }
-                            ColoredOrTexturedMesh::TexturedMesh(drawlet) => { self.textured_mesh_pipeline.as_mut().unwrap().remove_drawlet(drawlet); }
+                    match &mut self.terrain_mesh {
+                        Some(ColoredOrTexturedMesh::TexturedMesh(terrain_mesh)) => {
+                            terrain_mesh.set_texture(&texture);
+                        }
+                        _ => {
+                            let textured_mesh_data = TexturedMeshData {
+                                mvp_data: cg::Matrix4::identity(),
+                                mesh: self.textured_buffers.clone().unwrap(),
+                                texture: texture.clone(),
+                            };
+
+                            self.terrain_mesh = Some(ColoredOrTexturedMesh::TexturedMesh(
+                                self.textured_mesh_pipeline.as_mut().unwrap().create_drawlet(textured_mesh_data)
+                            ));
                         }
                     }
-                    self.terrain_mesh = Some(ColoredOrTexturedMesh::TexturedMesh(
-                        self.textured_mesh_pipeline.as_mut().unwrap().create_drawlet(lit_mesh_data)
-                    ));
+                    self.texture = Some(texture);
                 }
                 Color((r, g, b, a)) => {
                     log::info!("loaded color {}, {}, {}, {}", r, g, b, a);
                     for vertex in &mut self.color_vertex_list {
                         vertex.color = [*r, *g, *b, *a];
                     }
-                    let vertex_buffer = renderer.create_vertex_buffer(self.color_vertex_list.as_slice());
-                    let index_buffer = renderer.create_index_buffer(self.index_list.as_slice());
-                    let lit_mesh_data = LitColoredMeshData {
-                        mvp_data: cg::Matrix4::identity(),
-                        light_dir: cg::Vector4 {x: 1f32, y: 0f32, z: 0f32, w: 0f32},
-                        view_dir: cg::Vector4 {x: 1f32, y: 0f32, z: 0f32, w: 0f32},
-                        mesh: Arc::new(Mesh {
-                            index: index_buffer,
-                            vertex: vertex_buffer,
-                        }),
-                    };
-                    if let Some(drawlet)= self.terrain_mesh.take() {
-                        match drawlet {
-                            ColoredOrTexturedMesh::ColoredMesh(drawlet) => { self.lit_colored_mesh_pipeline.as_mut().unwrap().remove_drawlet(drawlet); }
-                            ColoredOrTexturedMesh::TexturedMesh(drawlet) => { self.textured_mesh_pipeline.as_mut().unwrap().remove_drawlet(drawlet); }
-                        }
+                    let colored_buffers = self.colored_buffers.clone().unwrap();
+                    colored_buffers.vertex.write(0, self.color_vertex_list.as_slice());
+
+                    // a color change only rewrites the vertices, the drawlet is kept
+                    if !matches!(self.terrain_mesh, Some(ColoredOrTexturedMesh::ColoredMesh(_))) {
+                        let lit_mesh_data = LitColoredMeshData {
+                            mvp_data: cg::Matrix4::identity(),
+                            light_dir: cg::Vector4 {x: 1f32, y: 0f32, z: 0f32, w: 0f32},
+                            view_dir: cg::Vector4 {x: 1f32, y: 0f32, z: 0f32, w: 0f32},
+                            mesh: colored_buffers,
+                        };
+                        self.terrain_mesh = Some(ColoredOrTexturedMesh::ColoredMesh(
+                            self.lit_colored_mesh_pipeline.as_mut().unwrap().create_drawlet(lit_mesh_data)
+                        ));
                     }
-                    self.terrain_mesh = Some(ColoredOrTexturedMesh::ColoredMesh(
-                        self.lit_colored_mesh_pipeline.as_mut().unwrap().create_drawlet(lit_mesh_data)
-                    ));
                 }
-                _ => {}
             }
         }
         self.texture_color_updated.replace(false);
 
-        let delta_time = self.last_time.elapsed().as_secs_f32();
-        self.last_time = Instant::now();
-
-        self.elapsed_time += delta_time;
-
-        let camera_center = cgmath::Vector3::new(3f32 * self.elapsed_time.cos(), 3f32, 3f32 * self.elapsed_time.sin());
+        let camera_center = cgmath::Vector3::new(3f32 * time.elapsed().cos(), 3f32, 3f32 * time.elapsed().sin());
 
         let v = cgmath::Matrix4::look_at_rh(
             cgmath::Point3::from_vec(camera_center),
@@ -0,0 +1,183 @@
+use std::sync::Arc;
+use winit::window::Window;
+use crate::FullscreenMode;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CursorGrab {
+    None,
+    Confined,
+    // falls back to confined on platforms that can't lock the cursor
+    Locked,
+}
+
+pub(crate) enum EngineCommand {
+    Exit,
+    SetTitle(String),
+    SetCursorGrab(CursorGrab),
+    SetCursorVisible(bool),
+    SetFullscreen(FullscreenMode),
+    ToggleFullscreen,
+    SetImeAllowed(bool),
+    SetImeCursorArea { position: (f32, f32), size: (f32, f32) },
+}
+
+// handed to the game so it can drive the app, requests are queued and
+// applied by `PoissonEngine` once the current frame is done
+pub struct EngineContext {
+    window: Option<Arc<Window>>,
+    commands: Vec<EngineCommand>,
+    exit_requested: bool,
+    clipboard: Clipboard,
+}
+
+impl EngineContext {
+    pub(crate) fn new() -> Self {
+        Self {
+            window: None,
+            commands: Vec::new(),
+            exit_requested: false,
+            clipboard: Clipboard::new(),
+        }
+    }
+
+    // a context without a window, for driving games from tests
+    pub fn headless() -> Self {
+        Self::new()
+    }
+
+    pub(crate) fn set_window(self: &mut Self, window: Arc<Window>) {
+        self.window = Some(window);
+    }
+
+    pub(crate) fn take_commands(self: &mut Self) -> Vec<EngineCommand> {
+        std::mem::take(&mut self.commands)
+    }
+
+    pub fn exit(self: &mut Self) {
+        self.exit_requested = true;
+        self.commands.push(EngineCommand::Exit);
+    }
+
+    pub fn is_exit_requested(self: &Self) -> bool {
+        self.exit_requested
+    }
+
+    pub fn set_title(self: &mut Self, title: &str) {
+        self.commands.push(EngineCommand::SetTitle(title.to_string()));
+    }
+
+    pub fn set_cursor_grab(self: &mut Self, grab: CursorGrab) {
+        self.commands.push(EngineCommand::SetCursorGrab(grab));
+    }
+
+    pub fn set_cursor_visible(self: &mut Self, visible: bool) {
+        self.commands.push(EngineCommand::SetCursorVisible(visible));
+    }
+
+    pub fn request_fullscreen(self: &mut Self, mode: FullscreenMode) {
+        self.commands.push(EngineCommand::SetFullscreen(mode));
+    }
+
+    pub fn toggle_fullscreen(self: &mut Self) {
+        self.commands.push(EngineCommand::ToggleFullscreen);
+    }
+
+    // ime composition is off by default, turn it on while a text field has focus
+    pub fn set_ime_allowed(self: &mut Self, allowed: bool) {
+        self.commands.push(EngineCommand::SetImeAllowed(allowed));
+    }
+
+    // where the text cursor is in physical pixels, so the ime candidate window can sit next to it
+    pub fn set_ime_cursor_area(self: &mut Self, position: (f32, f32), size: (f32, f32)) {
+        self.commands.push(EngineCommand::SetImeCursorArea { position, size });
+    }
+
+    pub fn get_clipboard_text(self: &mut Self) -> Option<String> {
+        self.clipboard.get_text()
+    }
+
+    pub fn set_clipboard_text(self: &mut Self, text: &str) {
+        self.clipboard.set_text(text);
+    }
+
+    pub fn is_fullscreen(self: &Self) -> bool {
+        self.window.as_ref()
+            .map(|window| window.fullscreen().is_some())
+            .unwrap_or(false)
+    }
+
+    pub fn scale_factor(self: &Self) -> f64 {
+        self.window.as_ref()
+            .map(|window| window.scale_factor())
+            .unwrap_or(1f64)
+    }
+
+    pub fn inner_size(self: &Self) -> (u32, u32) {
+        self.window.as_ref()
+            .map(|window| {
+                let size = window.inner_size();
+                (size.width, size.height)
+            })
+            .unwrap_or((0, 0))
+    }
+}
+
+// the system clipboard on native, on the web writes go to the system clipboard
+// but reads only see what was written from here, since browsers only read it asynchronously
+struct Clipboard {
+    #[cfg(not(target_arch = "wasm32"))]
+    clipboard: Option<arboard::Clipboard>,
+    #[cfg(target_arch = "wasm32")]
+    last_text: Option<String>,
+}
+
+impl Clipboard {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn new() -> Self {
+        // created lazily, some platforms only allow it once a window exists
+        Self { clipboard: None }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn get_clipboard(self: &mut Self) -> Option<&mut arboard::Clipboard> {
+        if self.clipboard.is_none() {
+            match arboard::Clipboard::new() {
+                Ok(clipboard) => self.clipboard = Some(clipboard),
+                Err(err) => log::warn!("clipboard is unavailable: {err}"),
+            }
+        }
+        self.clipboard.as_mut()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn get_text(self: &mut Self) -> Option<String> {
+        self.get_clipboard()?.get_text().ok()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn set_text(self: &mut Self, text: &str) {
+        if let Some(clipboard) = self.get_clipboard() {
+            if let Err(err) = clipboard.set_text(text) {
+                log::warn!("failed to set clipboard text: {err}");
+            }
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn new() -> Self {
+        Self { last_text: None }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn get_text(self: &mut Self) -> Option<String> {
+        self.last_text.clone()
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn set_text(self: &mut Self, text: &str) {
+        self.last_text = Some(text.to_string());
+        if let Some(window) = web_sys::window() {
+            let _ = window.navigator().clipboard().write_text(text);
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use cgmath::{InnerSpace, Vector2};
+use serde::{Deserialize, Serialize};
+use crate::input::InputButton;
+use crate::input::gamepad::GamepadAxis;
+
+// something that produces a signed value each frame
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum AxisSource {
+    // +1 while any positive button is down, -1 for negative, both cancel out
+    Buttons { positive: Vec<InputButton>, negative: Vec<InputButton> },
+    // per-frame motion, in physical pixels for the cursor and lines for the wheel
+    MouseX,
+    MouseY,
+    RawMouseX,
+    RawMouseY,
+    WheelX,
+    WheelY,
+    // the largest deflection over all connected gamepads
+    Gamepad(GamepadAxis),
+    // per-frame touch gestures, see `Gestures`
+    TouchDragX,
+    TouchDragY,
+    TouchPanX,
+    TouchPanY,
+    Pinch,
+    Rotate,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AxisSettings {
+    // magnitudes below this read as 0. when every source is a button or gamepad axis the
+    // rest is rescaled to start at 0, motion sources aren't bounded so they pass through as is
+    pub dead_zone: f32,
+    pub sensitivity: f32,
+    pub inverted: bool,
+}
+
+impl Default for AxisSettings {
+    fn default() -> Self {
+        Self {
+            dead_zone: 0f32,
+            sensitivity: 1f32,
+            inverted: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VectorSettings {
+    // radial, applied to the length of the combined vector like `AxisSettings::dead_zone`
+    pub dead_zone: f32,
+    pub sensitivity: f32,
+    pub invert_x: bool,
+    pub invert_y: bool,
+    // clamps the length to 1 so diagonals aren't faster
+    pub normalize: bool,
+}
+
+impl Default for VectorSettings {
+    fn default() -> Self {
+        Self {
+            dead_zone: 0f32,
+            sensitivity: 1f32,
+            invert_x: false,
+            invert_y: false,
+            normalize: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AxisBinding {
+    pub sources: Vec<AxisSource>,
+    pub settings: AxisSettings,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VectorBinding {
+    pub x: Vec<AxisSource>,
+    pub y: Vec<AxisSource>,
+    pub settings: VectorSettings,
+}
+
+impl AxisSource {
+    pub fn buttons<T: Into<InputButton>>(positive: Vec<T>, negative: Vec<T>) -> Self {
+        AxisSource::Buttons {
+            positive: positive.into_iter().map(|button| button.into()).collect(),
+            negative: negative.into_iter().map(|button| button.into()).collect(),
+        }
+    }
+
+    pub(crate) fn get_buttons(self: &Self) -> impl Iterator<Item = &InputButton> {
+        let (positive, negative): (&[InputButton], &[InputButton]) = match self {
+            AxisSource::Buttons { positive, negative } => (positive, negative),
+            _ => (&[], &[]),
+        };
+        positive.iter().chain(negative.iter())
+    }
+
+    // whether a single frame's value stays within [-1, 1]
+    pub fn is_normalized(self: &Self) -> bool {
+        matches!(self, AxisSource::Buttons { .. } | AxisSource::Gamepad(_))
+    }
+}
+
+fn apply_dead_zone(magnitude: f32, dead_zone: f32, rescale: bool) -> f32 {
+    if magnitude <= dead_zone {
+        return 0f32;
+    }
+    if rescale && dead_zone < 1f32 {
+        (magnitude - dead_zone) / (1f32 - dead_zone)
+    } else {
+        magnitude
+    }
+}
+
+fn all_normalized<'a>(sources: impl IntoIterator<Item = &'a AxisSource>) -> bool {
+    sources.into_iter().all(|source| source.is_normalized())
+}
+
+impl AxisBinding {
+    pub(crate) fn evaluate(self: &Self, read_source: impl Fn(&AxisSource) -> f32) -> f32 {
+        let raw: f32 = self.sources.iter().map(|source| read_source(source)).sum();
+        let raw = if self.settings.inverted { -raw } else { raw };
+
+        let rescale = all_normalized(&self.sources);
+        raw.signum() * apply_dead_zone(raw.abs(), self.settings.dead_zone, rescale) * self.settings.sensitivity
+    }
+}
+
+impl VectorBinding {
+    pub(crate) fn evaluate(self: &Self, read_source: impl Fn(&AxisSource) -> f32) -> Vector2<f32> {
+        let mut raw = Vector2::new(
+            self.x.iter().map(|source| read_source(source)).sum::<f32>(),
+            self.y.iter().map(|source| read_source(source)).sum::<f32>(),
+        );
+        if self.settings.invert_x { raw.x = -raw.x; }
+        if self.settings.invert_y { raw.y = -raw.y; }
+
+        let length = raw.magnitude();
+        if length == 0f32 {
+            return raw;
+        }
+
+        let rescale = all_normalized(self.x.iter().chain(self.y.iter()));
+        let mut scaled_length = apply_dead_zone(length, self.settings.dead_zone, rescale);
+        if self.settings.normalize {
+            scaled_length = scaled_length.min(1f32);
+        }
+
+        raw * (scaled_length / length) * self.settings.sensitivity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(value: f32, expected: f32) {
+        assert!((value - expected).abs() < 1e-5f32, "{value} != {expected}");
+    }
+
+    fn stick_x(dead_zone: f32) -> AxisBinding {
+        AxisBinding {
+            sources: vec![AxisSource::Gamepad(GamepadAxis::LeftStickX)],
+            settings: AxisSettings { dead_zone, ..Default::default() },
+        }
+    }
+
+    #[test]
+    fn dead_zone_rescales_bounded_sources_to_start_at_zero() {
+        let binding = stick_x(0.2f32);
+        assert_eq!(binding.evaluate(|_| 0.1f32), 0f32);
+        assert_eq!(binding.evaluate(|_| -0.2f32), 0f32);
+        assert_close(binding.evaluate(|_| 0.6f32), 0.5f32);
+        assert_close(binding.evaluate(|_| -1f32), -1f32);
+    }
+
+    #[test]
+    fn dead_zone_leaves_mixed_sources_unscaled() {
+        let binding = AxisBinding {
+            sources: vec![AxisSource::Gamepad(GamepadAxis::LeftStickX), AxisSource::MouseX],
+            settings: AxisSettings { dead_zone: 0.2f32, ..Default::default() },
+        };
+        let read = |stick: f32, mouse: f32| binding.evaluate(|source| match source {
+            AxisSource::MouseX => mouse,
+            _ => stick,
+        });
+
+        assert_eq!(read(0.1f32, 0f32), 0f32);
+        // rescaling would turn these into 0.5 and 6.25
+        assert_close(read(0.6f32, 0f32), 0.6f32);
+        assert_close(read(0f32, 5f32), 5f32);
+    }
+
+    #[test]
+    fn inversion_and_sensitivity_apply_after_the_dead_zone() {
+        let mut binding = stick_x(0.2f32);
+        binding.settings.inverted = true;
+        binding.settings.sensitivity = 2f32;
+        assert_close(binding.evaluate(|_| 0.6f32), -1f32);
+        assert_eq!(binding.evaluate(|_| 0.1f32), 0f32);
+    }
+
+    #[test]
+    fn vector_dead_zone_is_radial() {
+        let binding = VectorBinding {
+            x: vec![AxisSource::Gamepad(GamepadAxis::LeftStickX)],
+            y: vec![AxisSource::Gamepad(GamepadAxis::LeftStickY)],
+            settings: VectorSettings { dead_zone: 0.5f32, invert_y: true, ..Default::default() },
+        };
+        let read = |x: f32, y: f32| binding.evaluate(|source| match source {
+            AxisSource::Gamepad(GamepadAxis::LeftStickX) => x,
+            _ => y,
+        });
+
+        assert_eq!(read(0.3f32, 0.4f32), Vector2::new(0f32, 0f32));
+        // a length of 0.75 is halfway out of the dead zone, the direction is kept
+        let value = read(0.45f32, 0.6f32);
+        assert_close(value.x, 0.3f32);
+        assert_close(value.y, -0.4f32);
+    }
+}
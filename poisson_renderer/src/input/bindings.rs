@@ -0,0 +1,278 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::{Display, Formatter};
+use serde::{Deserialize, Serialize};
+use crate::input::InputButton;
+use crate::input::axis::{AxisBinding, AxisSource, VectorBinding};
+
+// everything the player can remap, stored as json
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct InputBindings {
+    #[serde(default)]
+    pub actions: BTreeMap<String, Vec<InputButton>>,
+    #[serde(default)]
+    pub axes: BTreeMap<String, AxisBinding>,
+    #[serde(default)]
+    pub vectors: BTreeMap<String, VectorBinding>,
+}
+
+// one button in a binding, used to target rebinds
+#[derive(Clone, Debug, PartialEq)]
+pub enum BindingSlot {
+    Action { name: String, index: usize },
+    // `source` indexes the axis sources, which has to be `AxisSource::Buttons`
+    Axis { name: String, source: usize, positive: bool, index: usize },
+    Vector { name: String, y: bool, source: usize, positive: bool, index: usize },
+}
+
+#[derive(Clone, Debug)]
+pub struct RebindResult {
+    pub slot: BindingSlot,
+    pub button: InputButton,
+    // other bindings that already use `button`, nothing is unbound automatically
+    pub conflicts: Vec<String>,
+}
+
+#[derive(Debug)]
+pub enum BindingsError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+    Storage(String),
+}
+
+impl Display for BindingsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BindingsError::Io(err) => write!(f, "failed to access bindings file: {err}"),
+            BindingsError::Parse(err) => write!(f, "failed to parse bindings: {err}"),
+            BindingsError::Storage(err) => write!(f, "failed to access bindings storage: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for BindingsError {}
+
+impl From<std::io::Error> for BindingsError {
+    fn from(err: std::io::Error) -> Self {
+        BindingsError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for BindingsError {
+    fn from(err: serde_json::Error) -> Self {
+        BindingsError::Parse(err)
+    }
+}
+
+impl InputBindings {
+    pub fn to_json(self: &Self) -> Result<String, BindingsError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, BindingsError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub(crate) fn get_slot_mut(self: &mut Self, slot: &BindingSlot) -> Option<&mut Vec<InputButton>> {
+        let (source, positive) = match slot {
+            BindingSlot::Action { name, .. } => return self.actions.get_mut(name),
+            BindingSlot::Axis { name, source, positive, .. } =>
+                (self.axes.get_mut(name)?.sources.get_mut(*source)?, *positive),
+            BindingSlot::Vector { name, y, source, positive, .. } => {
+                let vector = self.vectors.get_mut(name)?;
+                let sources = if *y { &mut vector.y } else { &mut vector.x };
+                (sources.get_mut(*source)?, *positive)
+            }
+        };
+
+        match source {
+            AxisSource::Buttons { positive: buttons, .. } if positive => Some(buttons),
+            AxisSource::Buttons { negative: buttons, .. } => Some(buttons),
+            _ => None,
+        }
+    }
+
+    // names of every binding `button` is part of, axes and vectors included
+    pub fn get_users(self: &Self, button: InputButton) -> Vec<String> {
+        let uses = |sources: &Vec<AxisSource>| sources.iter()
+            .flat_map(|source| source.get_buttons())
+            .any(|b| *b == button);
+
+        let actions = self.actions.iter()
+            .filter(|(_, buttons)| buttons.contains(&button))
+            .map(|(name, _)| name.clone());
+        let axes = self.axes.iter()
+            .filter(|(_, axis)| uses(&axis.sources))
+            .map(|(name, _)| name.clone());
+        let vectors = self.vectors.iter()
+            .filter(|(_, vector)| uses(&vector.x) || uses(&vector.y))
+            .map(|(name, _)| name.clone());
+
+        actions.chain(axes).chain(vectors).collect()
+    }
+
+    // every button bound to more than one binding
+    pub fn find_conflicts(self: &Self) -> Vec<(InputButton, Vec<String>)> {
+        let mut buttons: Vec<InputButton> = self.actions.values().flatten().copied()
+            .chain(self.axes.values().flat_map(|axis| axis.sources.iter()).flat_map(|s| s.get_buttons()).copied())
+            .chain(self.vectors.values().flat_map(|v| v.x.iter().chain(v.y.iter())).flat_map(|s| s.get_buttons()).copied())
+            .collect();
+        let mut seen = HashSet::new();
+        buttons.retain(|button| seen.insert(*button));
+
+        buttons.into_iter()
+            .map(|button| (button, self.get_users(button)))
+            .filter(|(_, users)| users.len() > 1)
+            .collect()
+    }
+}
+
+// a file next to the executable's working directory on native, localStorage in the browser
+pub struct BindingsStore {
+    #[cfg(not(target_arch = "wasm32"))]
+    path: std::path::PathBuf,
+    #[cfg(target_arch = "wasm32")]
+    key: String,
+}
+
+impl BindingsStore {
+    pub fn new(name: &str) -> Self {
+        Self {
+            #[cfg(not(target_arch = "wasm32"))]
+            path: std::path::PathBuf::from(format!("{name}.bindings.json")),
+            #[cfg(target_arch = "wasm32")]
+            key: format!("poisson.bindings.{name}"),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_path(path: impl Into<std::path::PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    // `None` if nothing has been saved yet
+    pub fn load(self: &Self) -> Result<Option<InputBindings>, BindingsError> {
+        let json = match self.read()? {
+            Some(json) => json,
+            None => return Ok(None),
+        };
+        InputBindings::from_json(json.as_str()).map(Some)
+    }
+
+    pub fn save(self: &Self, bindings: &InputBindings) -> Result<(), BindingsError> {
+        self.write(bindings.to_json()?.as_str())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn read(self: &Self) -> Result<Option<String>, BindingsError> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(json) => Ok(Some(json)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn write(self: &Self, json: &str) -> Result<(), BindingsError> {
+        if let Some(parent) = self.path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        Ok(std::fs::write(&self.path, json)?)
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn get_storage() -> Result<web_sys::Storage, BindingsError> {
+        web_sys::window()
+            .ok_or_else(|| BindingsError::Storage("no window".to_string()))?
+            .local_storage()
+            .map_err(|err| BindingsError::Storage(format!("{err:?}")))?
+            .ok_or_else(|| BindingsError::Storage("localStorage is disabled".to_string()))
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn read(self: &Self) -> Result<Option<String>, BindingsError> {
+        Self::get_storage()?
+            .get_item(self.key.as_str())
+            .map_err(|err| BindingsError::Storage(format!("{err:?}")))
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn write(self: &Self, json: &str) -> Result<(), BindingsError> {
+        Self::get_storage()?
+            .set_item(self.key.as_str(), json)
+            .map_err(|err| BindingsError::Storage(format!("{err:?}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winit::event::MouseButton;
+    use winit::keyboard::{KeyCode, PhysicalKey};
+    use crate::input::axis::{AxisSettings, VectorSettings};
+    use crate::input::gamepad::{GamepadAxis, GamepadButton};
+
+    fn key(code: KeyCode) -> InputButton {
+        InputButton::Key(PhysicalKey::Code(code))
+    }
+
+    fn bindings() -> InputBindings {
+        let mut bindings = InputBindings::default();
+        bindings.actions.insert("jump".to_string(), vec![key(KeyCode::Space), InputButton::Gamepad(GamepadButton::South)]);
+        bindings.actions.insert("fire".to_string(), vec![InputButton::Mouse(MouseButton::Left), InputButton::Tap]);
+        bindings.axes.insert("steer".to_string(), AxisBinding {
+            sources: vec![
+                AxisSource::buttons(vec![key(KeyCode::KeyD)], vec![key(KeyCode::KeyA)]),
+                AxisSource::Gamepad(GamepadAxis::LeftStickX),
+            ],
+            settings: AxisSettings { dead_zone: 0.25f32, sensitivity: 2f32, inverted: true },
+        });
+        bindings.vectors.insert("move".to_string(), VectorBinding {
+            x: vec![AxisSource::buttons(vec![key(KeyCode::KeyD)], vec![key(KeyCode::KeyA)])],
+            y: vec![AxisSource::buttons(vec![key(KeyCode::KeyW)], vec![key(KeyCode::Space)])],
+            settings: VectorSettings { normalize: false, ..Default::default() },
+        });
+        bindings
+    }
+
+    #[test]
+    fn json_round_trip_keeps_every_binding() {
+        let bindings = bindings();
+        let json = bindings.to_json().unwrap();
+        assert_eq!(InputBindings::from_json(json.as_str()).unwrap(), bindings);
+
+        // missing tables fall back to empty ones
+        let partial = InputBindings::from_json(r#"{ "actions": { "jump": [] } }"#).unwrap();
+        assert_eq!(partial.actions["jump"], vec![]);
+        assert!(partial.axes.is_empty() && partial.vectors.is_empty());
+
+        assert!(matches!(InputBindings::from_json("{ \"actions\": 3 }"), Err(BindingsError::Parse(_))));
+    }
+
+    #[test]
+    fn conflicts_list_every_binding_sharing_a_button() {
+        let mut conflicts = bindings().find_conflicts();
+        conflicts.sort_by_key(|(button, _)| format!("{button:?}"));
+
+        assert_eq!(conflicts, vec![
+            (key(KeyCode::KeyA), vec!["steer".to_string(), "move".to_string()]),
+            (key(KeyCode::KeyD), vec!["steer".to_string(), "move".to_string()]),
+            (key(KeyCode::Space), vec!["jump".to_string(), "move".to_string()]),
+        ]);
+        assert!(InputBindings::default().find_conflicts().is_empty());
+    }
+
+    #[test]
+    fn slots_only_name_button_lists() {
+        let mut bindings = bindings();
+        let steer = |source, positive| BindingSlot::Axis { name: "steer".to_string(), source, positive, index: 0 };
+
+        assert_eq!(bindings.get_slot_mut(&steer(0, true)).cloned(), Some(vec![key(KeyCode::KeyD)]));
+        assert_eq!(bindings.get_slot_mut(&steer(0, false)).cloned(), Some(vec![key(KeyCode::KeyA)]));
+        assert_eq!(bindings.get_slot_mut(&steer(1, true)), None);
+        assert_eq!(bindings.get_slot_mut(&steer(2, true)), None);
+
+        let down = BindingSlot::Vector { name: "move".to_string(), y: true, source: 0, positive: false, index: 0 };
+        assert_eq!(bindings.get_slot_mut(&down).cloned(), Some(vec![key(KeyCode::Space)]));
+        assert_eq!(bindings.get_slot_mut(&BindingSlot::Action { name: "crouch".to_string(), index: 0 }), None);
+    }
+}
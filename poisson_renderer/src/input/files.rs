@@ -0,0 +1,181 @@
+use serde::{Deserialize, Serialize};
+
+// a file dragged onto the window, read completely so it looks the same on native and the web
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct DroppedFile {
+    // just the file name, the browser never reveals the full path
+    pub name: String,
+    pub bytes: Vec<u8>,
+}
+
+impl DroppedFile {
+    // lowercase and without the dot, e.g. "png"
+    pub fn extension(self: &Self) -> Option<String> {
+        std::path::Path::new(self.name.as_str())
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+    }
+}
+
+// the bytes are left out, a texture would flood the log
+impl std::fmt::Debug for DroppedFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DroppedFile")
+            .field("name", &self.name)
+            .field("len", &self.bytes.len())
+            .finish()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn get_file_name(path: &std::path::Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+// dropped files are read on their own thread so a large file doesn't stall the event loop,
+// they show up once they are read like on the web
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod native {
+    use std::path::PathBuf;
+    use std::sync::mpsc::{channel, Receiver, Sender};
+    use crate::input::InputEvent;
+    use crate::input::files::{get_file_name, DroppedFile};
+
+    pub(crate) struct NativeFileDrop {
+        sender: Sender<InputEvent>,
+        receiver: Receiver<InputEvent>,
+    }
+
+    impl NativeFileDrop {
+        pub(crate) fn new() -> Self {
+            let (sender, receiver) = channel();
+            Self { sender, receiver }
+        }
+
+        pub(crate) fn read(self: &Self, path: PathBuf) {
+            let sender = self.sender.clone();
+            let spawned = std::thread::Builder::new()
+                .name("dropped file reader".to_string())
+                .spawn(move || {
+                    let event = match std::fs::read(&path) {
+                        Ok(bytes) => InputEvent::FileDropped(DroppedFile { name: get_file_name(&path), bytes }),
+                        Err(err) => {
+                            log::warn!("failed to read dropped file {}: {err}", path.display());
+                            InputEvent::FileHoverCancelled
+                        }
+                    };
+                    let _ = sender.send(event);
+                });
+            if let Err(err) = spawned {
+                log::warn!("failed to start reading a dropped file: {err}");
+            }
+        }
+
+        pub(crate) fn poll(self: &mut Self, events: &mut Vec<InputEvent>) {
+            events.extend(self.receiver.try_iter());
+        }
+    }
+}
+
+// winit doesn't report drops on the web, so the canvas gets html drag and drop listeners,
+// files are read asynchronously and show up once the browser is done reading them
+#[cfg(target_arch = "wasm32")]
+pub(crate) mod web {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen::closure::Closure;
+    use web_sys::{DragEvent, HtmlCanvasElement};
+    use crate::input::InputEvent;
+    use crate::input::files::DroppedFile;
+
+    pub(crate) struct WebFileDrop {
+        canvas: HtmlCanvasElement,
+        events: Rc<RefCell<Vec<InputEvent>>>,
+        listeners: Vec<(&'static str, Closure<dyn FnMut(DragEvent)>)>,
+    }
+
+    fn has_files(event: &DragEvent) -> bool {
+        event.data_transfer()
+            .map(|data| data.types().iter().any(|kind| kind.as_string().as_deref() == Some("Files")))
+            .unwrap_or(false)
+    }
+
+    impl WebFileDrop {
+        pub(crate) fn new(canvas: HtmlCanvasElement) -> Self {
+            let events = Rc::new(RefCell::new(Vec::new()));
+            let mut listeners: Vec<(&'static str, Closure<dyn FnMut(DragEvent)>)> = Vec::new();
+
+            // file names are hidden until the drop, so hovering reports one unnamed file per item
+            let hover_events = events.clone();
+            listeners.push(("dragenter", Closure::new(move |event: DragEvent| {
+                if !has_files(&event) {
+                    return;
+                }
+                event.prevent_default();
+                let count = event.data_transfer().map(|data| data.items().length()).unwrap_or(0);
+                let mut events = hover_events.borrow_mut();
+                events.push(InputEvent::FileHoverCancelled);
+                for _ in 0..count.max(1) {
+                    events.push(InputEvent::FileHovered(String::new()));
+                }
+            })));
+
+            // without this the browser opens the file instead of dropping it on the canvas
+            listeners.push(("dragover", Closure::new(move |event: DragEvent| {
+                if has_files(&event) {
+                    event.prevent_default();
+                }
+            })));
+
+            let leave_events = events.clone();
+            listeners.push(("dragleave", Closure::new(move |_event: DragEvent| {
+                leave_events.borrow_mut().push(InputEvent::FileHoverCancelled);
+            })));
+
+            let drop_events = events.clone();
+            listeners.push(("drop", Closure::new(move |event: DragEvent| {
+                let Some(files) = event.data_transfer().and_then(|data| data.files()) else { return; };
+                event.prevent_default();
+                drop_events.borrow_mut().push(InputEvent::FileHoverCancelled);
+
+                for index in 0..files.length() {
+                    let Some(file) = files.get(index) else { continue; };
+                    let events = drop_events.clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        let name = file.name();
+                        match wasm_bindgen_futures::JsFuture::from(file.array_buffer()).await {
+                            Ok(buffer) => {
+                                let bytes = js_sys::Uint8Array::new(&buffer).to_vec();
+                                events.borrow_mut().push(InputEvent::FileDropped(DroppedFile { name, bytes }));
+                            }
+                            Err(err) => log::warn!("failed to read dropped file {name}: {err:?}"),
+                        }
+                    });
+                }
+            })));
+
+            for (kind, listener) in listeners.iter() {
+                if let Err(err) = canvas.add_event_listener_with_callback(kind, listener.as_ref().unchecked_ref()) {
+                    log::warn!("failed to listen for {kind} on the canvas: {err:?}");
+                }
+            }
+
+            Self { canvas, events, listeners }
+        }
+
+        pub(crate) fn poll(self: &mut Self, events: &mut Vec<InputEvent>) {
+            events.append(&mut self.events.borrow_mut());
+        }
+    }
+
+    impl Drop for WebFileDrop {
+        fn drop(&mut self) {
+            for (kind, listener) in self.listeners.iter() {
+                let _ = self.canvas.remove_event_listener_with_callback(kind, listener.as_ref().unchecked_ref());
+            }
+        }
+    }
+}
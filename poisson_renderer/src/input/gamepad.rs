@@ -0,0 +1,201 @@
+use std::sync::Arc;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+#[derive(Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct GamepadId(pub usize);
+
+// named by position so the same binding works for every controller layout
+#[derive(Hash, PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum GamepadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftBumper,
+    RightBumper,
+    // digital versions of the triggers, the analog value is a `GamepadAxis`
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    Mode,
+    LeftStick,
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+// sticks are in [-1, 1] with y pointing up, triggers in [0, 1]
+#[derive(Hash, PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum GamepadEvent {
+    Connected { id: GamepadId, name: String },
+    Disconnected { id: GamepadId },
+    Button { id: GamepadId, button: GamepadButton, pressed: bool },
+    Axis { id: GamepadId, axis: GamepadAxis, value: f32 },
+}
+
+// polled by `Input` once per frame
+pub trait GamepadBackend {
+    fn poll(self: &mut Self, events: &mut Vec<GamepadEvent>);
+}
+
+// feeds scripted events into `Input`, keep a clone to drive it after handing it over
+#[derive(Clone, Default)]
+pub struct MockGamepadBackend {
+    queue: Arc<Mutex<Vec<GamepadEvent>>>,
+    next_id: Arc<Mutex<usize>>,
+}
+
+impl MockGamepadBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn connect(self: &Self, name: &str) -> GamepadId {
+        let id = {
+            let mut next_id = self.next_id.lock();
+            *next_id += 1;
+            GamepadId(*next_id - 1)
+        };
+        self.queue.lock().push(GamepadEvent::Connected { id, name: name.to_string() });
+        id
+    }
+
+    pub fn disconnect(self: &Self, id: GamepadId) {
+        self.queue.lock().push(GamepadEvent::Disconnected { id });
+    }
+
+    pub fn press(self: &Self, id: GamepadId, button: GamepadButton) {
+        self.queue.lock().push(GamepadEvent::Button { id, button, pressed: true });
+    }
+
+    pub fn release(self: &Self, id: GamepadId, button: GamepadButton) {
+        self.queue.lock().push(GamepadEvent::Button { id, button, pressed: false });
+    }
+
+    pub fn set_axis(self: &Self, id: GamepadId, axis: GamepadAxis, value: f32) {
+        self.queue.lock().push(GamepadEvent::Axis { id, axis, value });
+    }
+}
+
+impl GamepadBackend for MockGamepadBackend {
+    fn poll(self: &mut Self, events: &mut Vec<GamepadEvent>) {
+        events.append(&mut self.queue.lock());
+    }
+}
+
+// gilrs reads the os apis on native and the browser Gamepad API on wasm
+pub struct GilrsGamepadBackend {
+    gilrs: gilrs::Gilrs,
+    reported_initial: bool,
+}
+
+impl GilrsGamepadBackend {
+    pub fn new() -> Option<Self> {
+        match gilrs::Gilrs::new() {
+            Ok(gilrs) => Some(Self { gilrs, reported_initial: false }),
+            Err(err) => {
+                log::warn!("gamepads are unavailable: {err}");
+                None
+            }
+        }
+    }
+
+    fn to_button(button: gilrs::Button) -> Option<GamepadButton> {
+        use gilrs::Button;
+        Some(match button {
+            Button::South => GamepadButton::South,
+            Button::East => GamepadButton::East,
+            Button::North => GamepadButton::North,
+            Button::West => GamepadButton::West,
+            Button::LeftTrigger => GamepadButton::LeftBumper,
+            Button::RightTrigger => GamepadButton::RightBumper,
+            Button::LeftTrigger2 => GamepadButton::LeftTrigger,
+            Button::RightTrigger2 => GamepadButton::RightTrigger,
+            Button::Select => GamepadButton::Select,
+            Button::Start => GamepadButton::Start,
+            Button::Mode => GamepadButton::Mode,
+            Button::LeftThumb => GamepadButton::LeftStick,
+            Button::RightThumb => GamepadButton::RightStick,
+            Button::DPadUp => GamepadButton::DPadUp,
+            Button::DPadDown => GamepadButton::DPadDown,
+            Button::DPadLeft => GamepadButton::DPadLeft,
+            Button::DPadRight => GamepadButton::DPadRight,
+            _ => return None,
+        })
+    }
+
+    fn to_axis(axis: gilrs::Axis) -> Option<GamepadAxis> {
+        use gilrs::Axis;
+        Some(match axis {
+            Axis::LeftStickX => GamepadAxis::LeftStickX,
+            Axis::LeftStickY => GamepadAxis::LeftStickY,
+            Axis::RightStickX => GamepadAxis::RightStickX,
+            Axis::RightStickY => GamepadAxis::RightStickY,
+            _ => return None,
+        })
+    }
+}
+
+impl GamepadBackend for GilrsGamepadBackend {
+    fn poll(self: &mut Self, events: &mut Vec<GamepadEvent>) {
+        // pads plugged in before startup never send a connected event
+        if !self.reported_initial {
+            for (id, gamepad) in self.gilrs.gamepads() {
+                events.push(GamepadEvent::Connected { id: GamepadId(id.into()), name: gamepad.name().to_string() });
+            }
+            self.reported_initial = true;
+        }
+
+        while let Some(gilrs::Event { id, event, .. }) = self.gilrs.next_event() {
+            let id_value = GamepadId(id.into());
+            match event {
+                gilrs::EventType::Connected => {
+                    let name = self.gilrs.gamepad(id).name().to_string();
+                    events.push(GamepadEvent::Connected { id: id_value, name });
+                }
+                gilrs::EventType::Disconnected => {
+                    events.push(GamepadEvent::Disconnected { id: id_value });
+                }
+                gilrs::EventType::ButtonPressed(button, _) => {
+                    if let Some(button) = Self::to_button(button) {
+                        events.push(GamepadEvent::Button { id: id_value, button, pressed: true });
+                    }
+                }
+                gilrs::EventType::ButtonReleased(button, _) => {
+                    if let Some(button) = Self::to_button(button) {
+                        events.push(GamepadEvent::Button { id: id_value, button, pressed: false });
+                    }
+                }
+                // analog triggers come in as button changes
+                gilrs::EventType::ButtonChanged(button, value, _) => {
+                    let axis = match button {
+                        gilrs::Button::LeftTrigger2 => GamepadAxis::LeftTrigger,
+                        gilrs::Button::RightTrigger2 => GamepadAxis::RightTrigger,
+                        _ => continue,
+                    };
+                    events.push(GamepadEvent::Axis { id: id_value, axis, value });
+                }
+                gilrs::EventType::AxisChanged(axis, value, _) => {
+                    if let Some(axis) = Self::to_axis(axis) {
+                        events.push(GamepadEvent::Axis { id: id_value, axis, value });
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use std::ops::BitOr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::input::recording::InputEvent;
+
+pub trait ProcessEvent {
+    fn process_event(self: &mut Self, event: &InputEvent);
+}
+
+impl<F: FnMut(&InputEvent)> ProcessEvent for F {
+    fn process_event(self: &mut Self, event: &InputEvent) {
+        self(event)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EventCategories(u32);
+
+impl EventCategories {
+    pub const NONE: Self = Self(0);
+    pub const KEYBOARD: Self = Self(1 << 0);
+    pub const MOUSE: Self = Self(1 << 1);
+    pub const FOCUS: Self = Self(1 << 2);
+    pub const RESIZE: Self = Self(1 << 3);
+    // ime events and text typed with key presses
+    pub const TEXT: Self = Self(1 << 4);
+    pub const OTHER: Self = Self(1 << 5);
+    pub const TOUCH: Self = Self(1 << 6);
+    pub const FILE_DROP: Self = Self(1 << 7);
+    pub const GAMEPAD: Self = Self(1 << 8);
+    pub const ALL: Self = Self(u32::MAX);
+
+    pub fn contains(self: Self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn intersects(self: Self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    pub fn of(event: &InputEvent) -> Self {
+        match event {
+            InputEvent::Key { .. } => Self::KEYBOARD,
+            InputEvent::Text(_) | InputEvent::ImeEnabled(_) | InputEvent::ImePreedit { .. }
+            | InputEvent::ImeCommit(_) => Self::TEXT,
+            InputEvent::MouseButton { .. } | InputEvent::CursorMoved { .. } | InputEvent::CursorLeft
+            | InputEvent::WheelLines { .. } | InputEvent::WheelPixels { .. } | InputEvent::RawMouseMotion { .. } => Self::MOUSE,
+            InputEvent::Touch { .. } => Self::TOUCH,
+            InputEvent::FileHovered(_) | InputEvent::FileHoverCancelled | InputEvent::FileDropped(_) => Self::FILE_DROP,
+            InputEvent::Focused(_) => Self::FOCUS,
+            InputEvent::Resized { .. } | InputEvent::ScaleFactorChanged(_) => Self::RESIZE,
+            InputEvent::Gamepad(_) => Self::GAMEPAD,
+            InputEvent::UiCapture { .. } => Self::OTHER,
+        }
+    }
+}
+
+impl BitOr for EventCategories {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+// the listener stays subscribed for as long as this is alive
+#[must_use = "dropping the subscription unsubscribes the listener right away"]
+pub struct Subscription {
+    detached: Arc<AtomicBool>,
+}
+
+impl Subscription {
+    pub fn unsubscribe(self: Self) {}
+
+    // keeps the listener subscribed for the lifetime of the `Input`
+    pub fn detach(self: Self) {
+        self.detached.store(true, Ordering::Relaxed);
+    }
+}
+
+pub(crate) struct Listener {
+    categories: EventCategories,
+    // shared with the subscription, the listener goes once it is the only owner left
+    detached: Arc<AtomicBool>,
+    listener: Box<dyn ProcessEvent>,
+}
+
+impl Listener {
+    fn is_subscribed(self: &Self) -> bool {
+        Arc::strong_count(&self.detached) > 1 || self.detached.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Listeners {
+    listeners: Vec<Listener>,
+}
+
+impl Listeners {
+    pub(crate) fn subscribe(self: &mut Self, categories: EventCategories, listener: Box<dyn ProcessEvent>) -> Subscription {
+        let detached = Arc::new(AtomicBool::new(false));
+        self.listeners.push(Listener {
+            categories,
+            detached: detached.clone(),
+            listener,
+        });
+        Subscription { detached }
+    }
+
+    pub(crate) fn dispatch(self: &mut Self, event: &InputEvent) {
+        self.listeners.retain(|listener| listener.is_subscribed());
+
+        let categories = EventCategories::of(event);
+        for listener in self.listeners.iter_mut().filter(|listener| listener.categories.intersects(categories)) {
+            listener.listener.process_event(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use winit::event::MouseButton;
+    use winit::keyboard::{KeyCode, PhysicalKey};
+
+    type Received = Rc<RefCell<Vec<InputEvent>>>;
+
+    fn subscribe(listeners: &mut Listeners, categories: EventCategories) -> (Subscription, Received) {
+        let received = Received::default();
+        let sink = received.clone();
+        let subscription = listeners.subscribe(categories, Box::new(move |event: &InputEvent| {
+            sink.borrow_mut().push(event.clone());
+        }));
+        (subscription, received)
+    }
+
+    fn events() -> Vec<InputEvent> {
+        vec![
+            InputEvent::Key { key: PhysicalKey::Code(KeyCode::KeyA), pressed: true, repeat: false },
+            InputEvent::Text("a".to_string()),
+            InputEvent::MouseButton { button: MouseButton::Left, pressed: true },
+            InputEvent::CursorMoved { x: 1f32, y: 2f32 },
+            InputEvent::Focused(false),
+            InputEvent::Resized { width: 10, height: 20 },
+        ]
+    }
+
+    #[test]
+    fn listeners_only_see_their_categories() {
+        let mut listeners = Listeners::default();
+        let (_keyboard, keyboard) = subscribe(&mut listeners, EventCategories::KEYBOARD | EventCategories::TEXT);
+        let (_mouse, mouse) = subscribe(&mut listeners, EventCategories::MOUSE);
+        let (_all, all) = subscribe(&mut listeners, EventCategories::ALL);
+        let (_none, none) = subscribe(&mut listeners, EventCategories::NONE);
+
+        let events = events();
+        for event in events.iter() {
+            listeners.dispatch(event);
+        }
+
+        assert_eq!(*keyboard.borrow(), events[0..2]);
+        assert_eq!(*mouse.borrow(), events[2..4]);
+        assert_eq!(*all.borrow(), events);
+        assert!(none.borrow().is_empty());
+    }
+
+    #[test]
+    fn dropped_subscriptions_unsubscribe_and_detached_ones_stay() {
+        let mut listeners = Listeners::default();
+        let (dropped, dropped_received) = subscribe(&mut listeners, EventCategories::ALL);
+        let (detached, detached_received) = subscribe(&mut listeners, EventCategories::ALL);
+        let (unsubscribed, unsubscribed_received) = subscribe(&mut listeners, EventCategories::ALL);
+
+        drop(dropped);
+        detached.detach();
+        unsubscribed.unsubscribe();
+        listeners.dispatch(&InputEvent::Focused(true));
+        listeners.dispatch(&InputEvent::Focused(false));
+
+        assert!(dropped_received.borrow().is_empty());
+        assert!(unsubscribed_received.borrow().is_empty());
+        assert_eq!(detached_received.borrow().len(), 2);
+        assert_eq!(listeners.listeners.len(), 1);
+
+        // nothing else holds on to the detached listener
+        drop(listeners);
+        assert_eq!(Rc::strong_count(&detached_received), 1);
+    }
+}
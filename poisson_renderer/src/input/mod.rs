@@ -0,0 +1,1266 @@
+pub mod axis;
+pub mod gamepad;
+pub mod bindings;
+pub mod recording;
+pub mod listeners;
+pub mod touch;
+pub mod files;
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use cgmath::Vector2;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use winit::event::{DeviceEvent, WindowEvent};
+pub use winit::event::MouseButton;
+pub use winit::keyboard::PhysicalKey;
+pub use winit::keyboard::KeyCode;
+pub use crate::input::axis::{AxisBinding, AxisSettings, AxisSource, VectorBinding, VectorSettings};
+pub use crate::input::gamepad::{GamepadAxis, GamepadBackend, GamepadButton, GamepadEvent, GamepadId, MockGamepadBackend};
+pub use crate::input::bindings::{BindingSlot, BindingsError, BindingsStore, InputBindings, RebindResult};
+pub use crate::input::recording::{InputEvent, InputRecording, RecordedFrame, RecordingError};
+use crate::input::recording::{InputRecorder, InputReplay};
+pub use crate::input::listeners::{EventCategories, ProcessEvent, Subscription};
+use crate::input::listeners::Listeners;
+pub use crate::input::touch::{Gestures, Touch, TouchState};
+use crate::input::touch::TouchTracker;
+pub use crate::input::files::DroppedFile;
+
+// anything that can be bound to a mapping name
+#[derive(Hash, PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum InputButton {
+    Key(PhysicalKey),
+    Mouse(MouseButton),
+    // pressed while it is held on any connected gamepad
+    Gamepad(GamepadButton),
+    // pressed and released within the frame a tap is recognized
+    Tap,
+}
+
+impl From<PhysicalKey> for InputButton {
+    fn from(key: PhysicalKey) -> Self {
+        InputButton::Key(key)
+    }
+}
+
+impl From<KeyCode> for InputButton {
+    fn from(key: KeyCode) -> Self {
+        InputButton::Key(PhysicalKey::Code(key))
+    }
+}
+
+impl From<MouseButton> for InputButton {
+    fn from(button: MouseButton) -> Self {
+        InputButton::Mouse(button)
+    }
+}
+
+impl From<GamepadButton> for InputButton {
+    fn from(button: GamepadButton) -> Self {
+        InputButton::Gamepad(button)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputError {
+    UnmappedAction(String),
+    UnmappedAxis(String),
+    UnmappedVector(String),
+}
+
+impl Display for InputError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InputError::UnmappedAction(name) => write!(f, "no input mapping named \"{name}\""),
+            InputError::UnmappedAxis(name) => write!(f, "no axis named \"{name}\""),
+            InputError::UnmappedVector(name) => write!(f, "no vector named \"{name}\""),
+        }
+    }
+}
+
+impl std::error::Error for InputError {}
+
+// live state of a button, the edge counters are reset whenever the frame advances
+#[derive(Clone, Copy, Default)]
+struct ButtonState {
+    down: bool,
+    presses: u32,
+    repeats: u32,
+}
+
+// state of a mapped action as of the current frame
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct ActionState {
+    pressed: bool,
+    just_pressed: bool,
+    just_released: bool,
+    held_duration: f32,
+    repeat_count: u32,
+}
+
+impl ActionState {
+    pub fn is_pressed(self: &Self) -> bool {
+        self.pressed
+    }
+
+    pub fn just_pressed(self: &Self) -> bool {
+        self.just_pressed
+    }
+
+    pub fn just_released(self: &Self) -> bool {
+        self.just_released
+    }
+
+    // seconds of unscaled time since the action went down, 0 while released
+    pub fn held_duration(self: &Self) -> f32 {
+        self.held_duration
+    }
+
+    // key repeats sent by the os since the last frame
+    pub fn repeat_count(self: &Self) -> u32 {
+        self.repeat_count
+    }
+}
+
+// the edges of an action that no fixed step has seen yet
+#[derive(Clone, Copy, Default)]
+struct FixedStepEdges {
+    pressed: bool,
+    released: bool,
+}
+
+// what the ui (egui) currently wants for itself, hidden from the game's bindings
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+struct UiCapture {
+    pointer: bool,
+    keyboard: bool,
+}
+
+impl UiCapture {
+    fn blocks(self: &Self, button: &InputButton) -> bool {
+        match button {
+            InputButton::Key(_) => self.keyboard,
+            InputButton::Mouse(_) | InputButton::Tap => self.pointer,
+            InputButton::Gamepad(_) => false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct MouseFrame {
+    delta: (f32, f32),
+    raw_delta: (f32, f32),
+    wheel_lines: (f32, f32),
+    wheel_pixels: (f32, f32),
+}
+
+struct GamepadState {
+    name: String,
+    buttons: HashSet<GamepadButton>,
+    axes: HashMap<GamepadAxis, f32>,
+}
+
+pub struct Input {
+    key_state: HashMap<InputButton, ButtonState>,
+    bindings: InputBindings,
+    actions: HashMap<String, ActionState>,
+    // collected every frame until a fixed step takes them, so each edge reaches exactly one step
+    pending_fixed_edges: HashMap<String, FixedStepEdges>,
+    // `Some` while a fixed step runs, the edges it reports instead of the frame's
+    fixed_step_edges: Option<HashMap<String, FixedStepEdges>>,
+    axis_values: HashMap<String, f32>,
+    vector_values: HashMap<String, Vector2<f32>>,
+    // physical pixels, relative to the top left of the window
+    mouse_position: (f32, f32),
+    scale_factor: f64,
+    cursor_inside: bool,
+    // accumulated from events, becomes `mouse_frame` when the frame advances
+    pending_mouse: MouseFrame,
+    mouse_frame: MouseFrame,
+    ui_capture: UiCapture,
+    // committed text since the last frame, becomes `text` when the frame advances
+    pending_text: String,
+    text: String,
+    ime_enabled: bool,
+    ime_preedit: Option<(String, Option<(usize, usize)>)>,
+    touch: TouchTracker,
+    gestures: Gestures,
+    hovered_files: Vec<String>,
+    // files finish dropping between frames, `dropped_files` is what the game sees this frame
+    pending_dropped_files: Vec<DroppedFile>,
+    dropped_files: Vec<DroppedFile>,
+    #[cfg(target_arch = "wasm32")]
+    web_file_drop: Option<crate::input::files::web::WebFileDrop>,
+    #[cfg(not(target_arch = "wasm32"))]
+    native_file_drop: crate::input::files::native::NativeFileDrop,
+    gamepad_backend: Option<Box<dyn GamepadBackend>>,
+    gamepads: BTreeMap<GamepadId, GamepadState>,
+    // connects and disconnects seen when the frame last advanced
+    gamepad_events: Vec<GamepadEvent>,
+    recorder: Option<InputRecorder>,
+    replay: Option<InputReplay>,
+    rebind: Option<BindingSlot>,
+    captured_button: Option<InputButton>,
+    rebind_result: Option<RebindResult>,
+    event_listeners: Listeners,
+}
+
+impl Input {
+    pub fn new() -> Self {
+        Self {
+            key_state: HashMap::new(),
+            bindings: InputBindings::default(),
+            actions: HashMap::new(),
+            pending_fixed_edges: HashMap::new(),
+            fixed_step_edges: None,
+            axis_values: HashMap::new(),
+            vector_values: HashMap::new(),
+            mouse_position: (0f32, 0f32),
+            scale_factor: 1f64,
+            cursor_inside: false,
+            pending_mouse: MouseFrame::default(),
+            mouse_frame: MouseFrame::default(),
+            ui_capture: UiCapture::default(),
+            pending_text: String::new(),
+            text: String::new(),
+            ime_enabled: false,
+            ime_preedit: None,
+            touch: TouchTracker::default(),
+            gestures: Gestures::default(),
+            hovered_files: Vec::new(),
+            pending_dropped_files: Vec::new(),
+            dropped_files: Vec::new(),
+            #[cfg(target_arch = "wasm32")]
+            web_file_drop: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            native_file_drop: crate::input::files::native::NativeFileDrop::new(),
+            gamepad_backend: None,
+            gamepads: BTreeMap::new(),
+            gamepad_events: Vec::new(),
+            recorder: None,
+            replay: None,
+            rebind: None,
+            captured_button: None,
+            rebind_result: None,
+            event_listeners: Listeners::default(),
+        }
+    }
+
+    pub fn process_event(self: &mut Self, event: &WindowEvent) {
+        // live input is ignored while a recording is replayed
+        if self.replay.is_some() {
+            return;
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let WindowEvent::DroppedFile(path) = event {
+            self.native_file_drop.read(path.clone());
+            return;
+        }
+        let mut input_events = Vec::new();
+        InputEvent::from_window_event(event, &mut input_events);
+        for input_event in input_events {
+            self.handle_event(input_event);
+        }
+    }
+
+    // `listener` is called with every input event in `categories` until the subscription is dropped,
+    // during a replay it sees the replayed events instead of live ones
+    pub fn subscribe<L: ProcessEvent + 'static>(self: &mut Self, categories: EventCategories, listener: L) -> Subscription {
+        self.event_listeners.subscribe(categories, Box::new(listener))
+    }
+
+    // while the ui wants the pointer or keyboard, mouse or key bindings read as released,
+    // the engine sets this from egui before every frame
+    pub fn set_ui_capture(self: &mut Self, pointer: bool, keyboard: bool) {
+        let capture = UiCapture { pointer, keyboard };
+        if self.replay.is_some() || capture == self.ui_capture {
+            return;
+        }
+        self.handle_event(InputEvent::UiCapture { pointer, keyboard });
+    }
+
+    pub fn is_pointer_over_ui(self: &Self) -> bool {
+        self.ui_capture.pointer
+    }
+
+    pub fn is_ui_typing(self: &Self) -> bool {
+        self.ui_capture.keyboard
+    }
+
+    // raw motion keeps arriving when the cursor is locked and can't move anymore
+    pub fn process_device_event(self: &mut Self, event: &DeviceEvent) {
+        if self.replay.is_some() {
+            return;
+        }
+        match event {
+            DeviceEvent::MouseMotion { delta: (x, y) } => {
+                self.handle_event(InputEvent::RawMouseMotion { x: *x as f32, y: *y as f32 });
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_event(self: &mut Self, event: InputEvent) {
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.push(event.clone());
+        }
+        self.apply_event(event);
+    }
+
+    fn apply_event(self: &mut Self, event: InputEvent) {
+        self.event_listeners.dispatch(&event);
+
+        match event {
+            InputEvent::Key { key, pressed, repeat } => {
+                if pressed && !repeat {
+                    self.capture_button(InputButton::Key(key));
+                }
+                if let Some(button) = self.key_state.get_mut(&InputButton::Key(key)) {
+                    button.update(pressed, repeat);
+                }
+            }
+            InputEvent::MouseButton { button, pressed } => {
+                if pressed {
+                    self.capture_button(InputButton::Mouse(button));
+                }
+                // tracked even when unmapped so `is_mouse_button_pressed` works for any button
+                self.key_state.entry(InputButton::Mouse(button)).or_default()
+                    .update(pressed, false);
+            }
+            InputEvent::CursorMoved { x, y } => {
+                // the first position after entering would be a jump, not a movement
+                if self.cursor_inside {
+                    self.pending_mouse.delta.0 += x - self.mouse_position.0;
+                    self.pending_mouse.delta.1 += y - self.mouse_position.1;
+                }
+                self.mouse_position = (x, y);
+                self.cursor_inside = true;
+            }
+            InputEvent::CursorLeft => {
+                self.cursor_inside = false;
+            }
+            InputEvent::WheelLines { x, y } => {
+                self.pending_mouse.wheel_lines.0 += x;
+                self.pending_mouse.wheel_lines.1 += y;
+            }
+            InputEvent::WheelPixels { x, y } => {
+                self.pending_mouse.wheel_pixels.0 += x;
+                self.pending_mouse.wheel_pixels.1 += y;
+            }
+            InputEvent::ScaleFactorChanged(scale_factor) => {
+                self.scale_factor = scale_factor;
+            }
+            InputEvent::RawMouseMotion { x, y } => {
+                self.pending_mouse.raw_delta.0 += x;
+                self.pending_mouse.raw_delta.1 += y;
+            }
+            InputEvent::Gamepad(event) => self.apply_gamepad_event(event),
+            InputEvent::UiCapture { pointer, keyboard } => {
+                self.ui_capture = UiCapture { pointer, keyboard };
+            }
+            // with the ime on, text arrives as commits and key text would be doubled
+            InputEvent::Text(text) if !self.ime_enabled => {
+                self.pending_text.push_str(text.as_str());
+            }
+            InputEvent::Text(_) => {}
+            InputEvent::ImeCommit(text) => {
+                self.pending_text.push_str(text.as_str());
+            }
+            InputEvent::ImeEnabled(enabled) => {
+                self.ime_enabled = enabled;
+                if !enabled {
+                    self.ime_preedit = None;
+                }
+            }
+            InputEvent::Touch { id, state, x, y } => {
+                self.touch.process(id, state, (x, y), self.scale_factor);
+            }
+            InputEvent::FileHovered(name) => {
+                self.hovered_files.push(name);
+            }
+            InputEvent::FileHoverCancelled => {
+                self.hovered_files.clear();
+            }
+            InputEvent::FileDropped(file) => {
+                self.hovered_files.clear();
+                self.pending_dropped_files.push(file);
+            }
+            InputEvent::ImePreedit { text, cursor } => {
+                // an empty preedit means the composition was cleared
+                self.ime_preedit = (!text.is_empty()).then_some((text, cursor));
+            }
+            InputEvent::Focused(_) | InputEvent::Resized { .. } => {}
+        }
+    }
+
+    // a replay keeps the scale factor it was recorded with
+    pub(crate) fn set_scale_factor(self: &mut Self, scale_factor: f64) {
+        if self.replay.is_none() {
+            self.scale_factor = scale_factor;
+        }
+    }
+
+    // takes the snapshot every query reads from, called once per frame before the game sees input,
+    // so a press and release between two frames still shows up as both edges
+    pub fn advance_frame(self: &mut Self, delta: f32) {
+        self.gamepad_events.clear();
+        self.poll_gamepads();
+        self.poll_file_drop();
+
+        if let Some(replay) = self.replay.as_mut() {
+            let frame = replay.next_frame();
+            if replay.is_finished() {
+                log::info!("input replay finished");
+                self.replay = None;
+            }
+            for event in frame.map(|frame| frame.events).unwrap_or_default() {
+                self.apply_event(event);
+            }
+        }
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.end_frame(delta);
+        }
+
+        self.finish_rebind();
+
+        self.gestures = self.touch.advance_frame(delta);
+        if self.ui_capture.pointer {
+            self.gestures = Gestures::default();
+        }
+        if !self.gestures.taps.is_empty() {
+            let tap = self.key_state.entry(InputButton::Tap).or_default();
+            tap.update(true, false);
+            tap.update(false, false);
+        }
+
+        for (name, keys) in self.bindings.actions.iter() {
+            let action = self.actions.entry(name.clone()).or_default();
+            let was_pressed = action.pressed;
+
+            let mut pressed = false;
+            let mut tapped = false;
+            let mut repeat_count = 0;
+            for key in keys.iter().filter(|key| !self.ui_capture.blocks(key)) {
+                let button = &self.key_state[key];
+                pressed |= button.down;
+                tapped |= button.presses > 0;
+                repeat_count += button.repeats;
+            }
+
+            action.just_pressed = !was_pressed && (pressed || tapped);
+            action.just_released = (was_pressed || tapped) && !pressed;
+            action.held_duration = match (pressed, was_pressed) {
+                (true, true) => action.held_duration + delta,
+                _ => 0f32,
+            };
+            action.repeat_count = repeat_count;
+            action.pressed = pressed;
+
+            let edges = self.pending_fixed_edges.entry(name.clone()).or_default();
+            edges.pressed |= action.just_pressed;
+            edges.released |= action.just_released;
+        }
+
+        for button in self.key_state.values_mut() {
+            button.presses = 0;
+            button.repeats = 0;
+        }
+
+        self.mouse_frame = std::mem::take(&mut self.pending_mouse);
+        if self.ui_capture.pointer {
+            self.mouse_frame = MouseFrame::default();
+        }
+        self.dropped_files = std::mem::take(&mut self.pending_dropped_files);
+        self.text = std::mem::take(&mut self.pending_text);
+        if self.ui_capture.keyboard {
+            self.text.clear();
+        }
+
+        // buttons are read live here, their edges don't matter for axes
+        self.axis_values = self.bindings.axes.iter()
+            .map(|(name, binding)| (name.clone(), binding.evaluate(|source| self.read_axis_source(source))))
+            .collect();
+        self.vector_values = self.bindings.vectors.iter()
+            .map(|(name, binding)| (name.clone(), binding.evaluate(|source| self.read_axis_source(source))))
+            .collect();
+    }
+
+    // until `end_fixed_step`, `just_pressed` and `just_released` report the edges since the
+    // previous fixed step instead of the frame's, the engine calls these around `fixed_update`
+    pub(crate) fn begin_fixed_step(self: &mut Self) {
+        self.fixed_step_edges = Some(std::mem::take(&mut self.pending_fixed_edges));
+    }
+
+    pub(crate) fn end_fixed_step(self: &mut Self) {
+        self.fixed_step_edges = None;
+    }
+
+    fn read_axis_source(self: &Self, source: &AxisSource) -> f32 {
+        let is_down = |buttons: &Vec<InputButton>| buttons.iter()
+            .filter(|button| !self.ui_capture.blocks(button))
+            .any(|button| self.key_state.get(button).map(|b| b.down).unwrap_or(false));
+
+        match source {
+            AxisSource::Buttons { positive, negative } => {
+                let mut value = 0f32;
+                if is_down(positive) { value += 1f32; }
+                if is_down(negative) { value -= 1f32; }
+                value
+            }
+            AxisSource::MouseX => self.mouse_frame.delta.0,
+            AxisSource::MouseY => self.mouse_frame.delta.1,
+            AxisSource::RawMouseX => self.mouse_frame.raw_delta.0,
+            AxisSource::RawMouseY => self.mouse_frame.raw_delta.1,
+            AxisSource::WheelX => self.mouse_frame.wheel_lines.0,
+            AxisSource::WheelY => self.mouse_frame.wheel_lines.1,
+            AxisSource::TouchDragX => self.gestures.drag.0,
+            AxisSource::TouchDragY => self.gestures.drag.1,
+            AxisSource::TouchPanX => self.gestures.pan.0,
+            AxisSource::TouchPanY => self.gestures.pan.1,
+            AxisSource::Pinch => self.gestures.pinch,
+            AxisSource::Rotate => self.gestures.rotation,
+            AxisSource::Gamepad(axis) => self.gamepads.values()
+                .map(|gamepad| gamepad.axes.get(axis).copied().unwrap_or(0f32))
+                .fold(0f32, |a, b| if b.abs() > a.abs() { b } else { a }),
+        }
+    }
+
+    pub fn set_gamepad_backend(self: &mut Self, backend: Box<dyn GamepadBackend>) {
+        self.gamepad_backend = Some(backend);
+    }
+
+    fn poll_gamepads(self: &mut Self) {
+        let mut events = Vec::new();
+        if let Some(backend) = self.gamepad_backend.as_mut() {
+            backend.poll(&mut events);
+        }
+
+        // still polled during a replay so nothing piles up in the backend
+        if self.replay.is_some() {
+            return;
+        }
+        for event in events {
+            self.handle_event(InputEvent::Gamepad(event));
+        }
+    }
+
+    // the canvas gets its own drag and drop listeners, the engine calls this once it exists
+    #[cfg(target_arch = "wasm32")]
+    pub(crate) fn set_drop_target(self: &mut Self, canvas: web_sys::HtmlCanvasElement) {
+        self.web_file_drop = Some(crate::input::files::web::WebFileDrop::new(canvas));
+    }
+
+    // dropped files are read in the background on both platforms and arrive here
+    fn poll_file_drop(self: &mut Self) {
+        let mut events = Vec::new();
+        #[cfg(target_arch = "wasm32")]
+        if let Some(web_file_drop) = self.web_file_drop.as_mut() {
+            web_file_drop.poll(&mut events);
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        self.native_file_drop.poll(&mut events);
+        if self.replay.is_some() {
+            return;
+        }
+        for event in events {
+            self.handle_event(event);
+        }
+    }
+
+    fn apply_gamepad_event(self: &mut Self, event: GamepadEvent) {
+        match &event {
+            GamepadEvent::Connected { id, name } => {
+                log::info!("gamepad {} connected: {name}", id.0);
+                self.gamepads.insert(*id, GamepadState {
+                    name: name.clone(),
+                    buttons: HashSet::new(),
+                    axes: HashMap::new(),
+                });
+                self.gamepad_events.push(event);
+            }
+            GamepadEvent::Disconnected { id } => {
+                log::info!("gamepad {} disconnected", id.0);
+                if let Some(gamepad) = self.gamepads.remove(id) {
+                    for button in gamepad.buttons {
+                        self.update_gamepad_button(button);
+                    }
+                }
+                self.gamepad_events.push(event);
+            }
+            GamepadEvent::Button { id, button, pressed } => {
+                let Some(gamepad) = self.gamepads.get_mut(id) else { return; };
+                if *pressed {
+                    gamepad.buttons.insert(*button);
+                    self.capture_button(InputButton::Gamepad(*button));
+                } else {
+                    gamepad.buttons.remove(button);
+                }
+                self.update_gamepad_button(*button);
+            }
+            GamepadEvent::Axis { id, axis, value } => {
+                if let Some(gamepad) = self.gamepads.get_mut(id) {
+                    gamepad.axes.insert(*axis, *value);
+                }
+            }
+        }
+    }
+
+    fn update_gamepad_button(self: &mut Self, button: GamepadButton) {
+        let down = self.gamepads.values().any(|gamepad| gamepad.buttons.contains(&button));
+        self.key_state.entry(InputButton::Gamepad(button)).or_default().update(down, false);
+    }
+
+    // only `Connected` and `Disconnected` events, the rest is folded into the button and axis state
+    pub fn gamepad_events(self: &Self) -> &[GamepadEvent] {
+        self.gamepad_events.as_slice()
+    }
+
+    pub fn connected_gamepads(self: &Self) -> impl Iterator<Item = (GamepadId, &str)> {
+        self.gamepads.iter().map(|(id, gamepad)| (*id, gamepad.name.as_str()))
+    }
+
+    pub fn is_gamepad_button_pressed(self: &Self, id: GamepadId, button: GamepadButton) -> bool {
+        self.gamepads.get(&id)
+            .map(|gamepad| gamepad.buttons.contains(&button))
+            .unwrap_or(false)
+    }
+
+    pub fn gamepad_axis(self: &Self, id: GamepadId, axis: GamepadAxis) -> f32 {
+        self.gamepads.get(&id)
+            .and_then(|gamepad| gamepad.axes.get(&axis).copied())
+            .unwrap_or(0f32)
+    }
+
+    // records everything that reaches `Input` from now on, start it before the first frame
+    // so no key is already held when the replay begins, and after the window's scale factor
+    // is known so the replay starts from the same one
+    pub fn start_recording(self: &mut Self) {
+        self.recorder = Some(InputRecorder::new(self.scale_factor, self.mouse_position));
+    }
+
+    pub fn stop_recording(self: &mut Self) -> Option<InputRecording> {
+        self.recorder.take().map(|recorder| recorder.finish())
+    }
+
+    pub fn is_recording(self: &Self) -> bool {
+        self.recorder.is_some()
+    }
+
+    // replaces live input with the recording until it runs out
+    pub fn start_replay(self: &mut Self, recording: InputRecording) {
+        self.scale_factor = recording.scale_factor;
+        self.mouse_position = recording.mouse_position;
+        self.replay = Some(InputReplay::new(recording));
+    }
+
+    pub fn stop_replay(self: &mut Self) {
+        self.replay = None;
+    }
+
+    pub fn is_replaying(self: &Self) -> bool {
+        self.replay.is_some()
+    }
+
+    // the recorded length of the next frame, drive the clock with this so the replay is exact
+    pub fn get_replay_delta(self: &Self) -> Option<f32> {
+        self.replay.as_ref().and_then(|replay| replay.peek_delta())
+    }
+
+    fn capture_button(self: &mut Self, button: InputButton) {
+        if self.rebind.is_some() && self.captured_button.is_none() {
+            self.captured_button = Some(button);
+        }
+    }
+
+    // the next key, mouse or gamepad button pressed replaces the button at `slot`,
+    // or is appended if the slot index is past the end
+    pub fn start_rebind(self: &mut Self, slot: BindingSlot) {
+        self.rebind = Some(slot);
+        self.captured_button = None;
+        self.rebind_result = None;
+    }
+
+    pub fn cancel_rebind(self: &mut Self) {
+        self.rebind = None;
+        self.captured_button = None;
+    }
+
+    pub fn is_rebinding(self: &Self) -> bool {
+        self.rebind.is_some()
+    }
+
+    // set once the frame after the button was pressed, check `conflicts` to warn the player
+    pub fn take_rebind_result(self: &mut Self) -> Option<RebindResult> {
+        self.rebind_result.take()
+    }
+
+    fn finish_rebind(self: &mut Self) {
+        let Some(button) = self.captured_button.take() else { return; };
+        let Some(slot) = self.rebind.take() else { return; };
+
+        let (name, index) = match &slot {
+            BindingSlot::Action { name, index } => (name.clone(), *index),
+            BindingSlot::Axis { name, index, .. } => (name.clone(), *index),
+            BindingSlot::Vector { name, index, .. } => (name.clone(), *index),
+        };
+
+        let conflicts: Vec<String> = self.bindings.get_users(button).into_iter()
+            .filter(|user| *user != name)
+            .collect();
+
+        let Some(buttons) = self.bindings.get_slot_mut(&slot) else {
+            log::warn!("can't rebind {:?}, it doesn't name a button list", slot);
+            return;
+        };
+        if index < buttons.len() {
+            buttons[index] = button;
+        } else {
+            buttons.push(button);
+        }
+
+        // the button is still held, it only counts once it is pressed again
+        self.key_state.entry(button).or_default();
+
+        self.rebind_result = Some(RebindResult { slot, button, conflicts });
+    }
+
+    pub fn get_bindings(self: &Self) -> &InputBindings {
+        &self.bindings
+    }
+
+    // bindings named in `bindings` replace the current ones, everything else is kept,
+    // so actions added after the bindings were saved still get their defaults
+    pub fn apply_bindings(self: &mut Self, bindings: InputBindings) {
+        for (name, buttons) in bindings.actions {
+            self.set_mapping(name.as_str(), buttons);
+        }
+        for (name, binding) in bindings.axes {
+            self.set_axis_binding(name.as_str(), binding);
+        }
+        for (name, binding) in bindings.vectors {
+            self.set_vector_binding(name.as_str(), binding);
+        }
+    }
+
+    // returns whether anything was stored
+    pub fn load_bindings(self: &mut Self, store: &BindingsStore) -> Result<bool, BindingsError> {
+        match store.load()? {
+            Some(bindings) => {
+                self.apply_bindings(bindings);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    pub fn save_bindings(self: &Self, store: &BindingsStore) -> Result<(), BindingsError> {
+        store.save(&self.bindings)
+    }
+
+    pub fn find_conflicts(self: &Self) -> Vec<(InputButton, Vec<String>)> {
+        self.bindings.find_conflicts()
+    }
+
+    fn track_buttons<'a>(self: &mut Self, sources: impl Iterator<Item = &'a AxisSource>) {
+        for button in sources.flat_map(|source| source.get_buttons()) {
+            self.key_state.entry(*button).or_default();
+        }
+    }
+
+    pub fn set_axis<T: Into<InputButton>>(self: &mut Self, name: &str, positive: Vec<T>, negative: Vec<T>) {
+        self.set_axis_binding(name, AxisBinding {
+            sources: vec![AxisSource::buttons(positive, negative)],
+            settings: AxisSettings::default(),
+        });
+    }
+
+    pub fn set_axis_binding(self: &mut Self, name: &str, binding: AxisBinding) {
+        self.track_buttons(binding.sources.iter());
+        self.bindings.axes.insert(name.to_string(), binding);
+    }
+
+    pub fn set_axis_settings(self: &mut Self, name: &str, settings: AxisSettings) -> Result<(), InputError> {
+        let binding = self.bindings.axes.get_mut(name)
+            .ok_or_else(|| InputError::UnmappedAxis(name.to_string()))?;
+        binding.settings = settings;
+        Ok(())
+    }
+
+    // y points up/forward, x to the right
+    pub fn set_vector<T: Into<InputButton>>(
+        self: &mut Self, name: &str, up: Vec<T>, down: Vec<T>, left: Vec<T>, right: Vec<T>
+    ) {
+        self.set_vector_binding(name, VectorBinding {
+            x: vec![AxisSource::buttons(right, left)],
+            y: vec![AxisSource::buttons(up, down)],
+            settings: VectorSettings::default(),
+        });
+    }
+
+    pub fn set_vector_binding(self: &mut Self, name: &str, binding: VectorBinding) {
+        self.track_buttons(binding.x.iter().chain(binding.y.iter()));
+        self.bindings.vectors.insert(name.to_string(), binding);
+    }
+
+    pub fn set_vector_settings(self: &mut Self, name: &str, settings: VectorSettings) -> Result<(), InputError> {
+        let binding = self.bindings.vectors.get_mut(name)
+            .ok_or_else(|| InputError::UnmappedVector(name.to_string()))?;
+        binding.settings = settings;
+        Ok(())
+    }
+
+    pub fn try_axis(self: &Self, name: &str) -> Result<f32, InputError> {
+        if !self.bindings.axes.contains_key(name) {
+            return Err(InputError::UnmappedAxis(name.to_string()));
+        }
+        Ok(self.axis_values.get(name).copied().unwrap_or(0f32))
+    }
+
+    pub fn try_vector(self: &Self, name: &str) -> Result<Vector2<f32>, InputError> {
+        if !self.bindings.vectors.contains_key(name) {
+            return Err(InputError::UnmappedVector(name.to_string()));
+        }
+        Ok(self.vector_values.get(name).copied().unwrap_or(Vector2::new(0f32, 0f32)))
+    }
+
+    pub fn axis(self: &Self, name: &str) -> f32 {
+        self.try_axis(name).unwrap_or(0f32)
+    }
+
+    pub fn vector(self: &Self, name: &str) -> Vector2<f32> {
+        self.try_vector(name).unwrap_or(Vector2::new(0f32, 0f32))
+    }
+
+    pub fn mouse_position(self: &Self) -> (f32, f32) {
+        self.mouse_position
+    }
+
+    pub fn mouse_position_logical(self: &Self) -> (f32, f32) {
+        let scale = self.scale_factor as f32;
+        (self.mouse_position.0 / scale, self.mouse_position.1 / scale)
+    }
+
+    pub fn is_cursor_inside(self: &Self) -> bool {
+        self.cursor_inside
+    }
+
+    // cursor movement this frame in physical pixels, stops at the window edges
+    pub fn mouse_delta(self: &Self) -> (f32, f32) {
+        self.mouse_frame.delta
+    }
+
+    // unaccelerated device motion this frame, use this for mouse look with a grabbed cursor
+    pub fn raw_mouse_delta(self: &Self) -> (f32, f32) {
+        self.mouse_frame.raw_delta
+    }
+
+    // wheel movement this frame in lines, as reported by most mice
+    pub fn wheel_delta(self: &Self) -> (f32, f32) {
+        self.mouse_frame.wheel_lines
+    }
+
+    // wheel movement this frame in pixels, as reported by touchpads
+    pub fn wheel_delta_pixels(self: &Self) -> (f32, f32) {
+        self.mouse_frame.wheel_pixels
+    }
+
+    // text typed or committed through the ime this frame, without control characters,
+    // so editing keys like backspace and enter have to be bound as actions
+    pub fn get_text(self: &Self) -> &str {
+        self.text.as_str()
+    }
+
+    pub fn is_ime_enabled(self: &Self) -> bool {
+        self.ime_enabled
+    }
+
+    // the text being composed and the byte range of its cursor, show it but don't commit it
+    pub fn get_ime_preedit(self: &Self) -> Option<(&str, Option<(usize, usize)>)> {
+        if self.ui_capture.keyboard {
+            return None;
+        }
+        self.ime_preedit.as_ref().map(|(text, cursor)| (text.as_str(), *cursor))
+    }
+
+    // fingers currently down, in physical pixels
+    pub fn get_touches(self: &Self) -> impl Iterator<Item = &Touch> {
+        self.touch.get_touches()
+    }
+
+    pub fn get_gestures(self: &Self) -> &Gestures {
+        &self.gestures
+    }
+
+    // names of the files dragged over the window right now, empty strings in the browser,
+    // which only reveals names on the drop
+    pub fn get_hovered_files(self: &Self) -> &[String] {
+        self.hovered_files.as_slice()
+    }
+
+    pub fn is_file_hovered(self: &Self) -> bool {
+        !self.hovered_files.is_empty()
+    }
+
+    // files dropped onto the window this frame
+    pub fn get_dropped_files(self: &Self) -> &[DroppedFile] {
+        self.dropped_files.as_slice()
+    }
+
+    // moves this frame's dropped files out, so their bytes can be kept without a copy
+    pub fn take_dropped_files(self: &mut Self) -> Vec<DroppedFile> {
+        std::mem::take(&mut self.dropped_files)
+    }
+
+    pub fn is_mouse_button_pressed(self: &Self, button: MouseButton) -> bool {
+        if self.ui_capture.pointer {
+            return false;
+        }
+        self.key_state.get(&InputButton::Mouse(button))
+            .map(|button| button.down)
+            .unwrap_or(false)
+    }
+
+    pub fn set_mapping<T: Into<InputButton>>(self: &mut Self, name: &str, keys: Vec<T>) {
+        let keys: Vec<InputButton> = keys.into_iter().map(|key| key.into()).collect();
+        for key in keys.iter() {
+            if !self.key_state.contains_key(&key) {
+                self.key_state.insert(*key, ButtonState::default());
+            }
+        }
+        self.bindings.actions.insert(name.to_string(), keys);
+    }
+
+    pub fn is_mapped(self: &Self, name: &str) -> bool {
+        self.bindings.actions.contains_key(name)
+    }
+
+    // an action that was mapped after the last frame advanced reads as released
+    pub fn action(self: &Self, name: &str) -> Result<ActionState, InputError> {
+        if !self.bindings.actions.contains_key(name) {
+            return Err(InputError::UnmappedAction(name.to_string()));
+        }
+        let mut action = self.actions.get(name).copied().unwrap_or_default();
+        if let Some(fixed_step_edges) = self.fixed_step_edges.as_ref() {
+            let edges = fixed_step_edges.get(name).copied().unwrap_or_default();
+            action.just_pressed = edges.pressed;
+            action.just_released = edges.released;
+        }
+        Ok(action)
+    }
+
+    // the queries below treat unmapped names as never pressed, use `action` to tell the difference
+
+    pub fn is_pressed(self: &Self, name: &str) -> bool {
+        self.action(name).map(|action| action.is_pressed()).unwrap_or(false)
+    }
+
+    pub fn just_pressed(self: &Self, name: &str) -> bool {
+        self.action(name).map(|action| action.just_pressed()).unwrap_or(false)
+    }
+
+    pub fn just_released(self: &Self, name: &str) -> bool {
+        self.action(name).map(|action| action.just_released()).unwrap_or(false)
+    }
+
+    pub fn held_duration(self: &Self, name: &str) -> f32 {
+        self.action(name).map(|action| action.held_duration()).unwrap_or(0f32)
+    }
+
+    pub fn repeat_count(self: &Self, name: &str) -> u32 {
+        self.action(name).map(|action| action.repeat_count()).unwrap_or(0)
+    }
+}
+
+impl ButtonState {
+    fn update(self: &mut Self, down: bool, repeat: bool) {
+        if down {
+            if repeat {
+                self.repeats += 1;
+            } else if !self.down {
+                self.presses += 1;
+            }
+        }
+        self.down = down;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::InnerSpace;
+
+    fn key(code: KeyCode, pressed: bool) -> InputEvent {
+        InputEvent::Key { key: PhysicalKey::Code(code), pressed, repeat: false }
+    }
+
+    fn mouse(button: MouseButton, pressed: bool) -> InputEvent {
+        InputEvent::MouseButton { button, pressed }
+    }
+
+    fn bound_input() -> Input {
+        let mut input = Input::new();
+        input.set_mapping("jump", vec![KeyCode::Space]);
+        input.set_mapping("fire", vec![MouseButton::Left]);
+        input
+    }
+
+    fn action_states(input: &Input) -> (ActionState, ActionState) {
+        (input.action("jump").unwrap(), input.action("fire").unwrap())
+    }
+
+    #[test]
+    fn replay_reproduces_the_recorded_action_states() {
+        let mut input = bound_input();
+        input.set_scale_factor(2f64);
+        input.start_recording();
+
+        let frames = vec![
+            (0.016f32, vec![key(KeyCode::Space, true)]),
+            (0.020f32, vec![]),
+            (0.016f32, vec![key(KeyCode::Space, false), mouse(MouseButton::Left, true)]),
+            // pressed and released between two frames
+            (0.033f32, vec![mouse(MouseButton::Left, false), key(KeyCode::Space, true), key(KeyCode::Space, false)]),
+            (0.016f32, vec![InputEvent::CursorMoved { x: 10f32, y: 20f32 }]),
+        ];
+        let mut recorded_states = Vec::new();
+        for (delta, events) in frames {
+            for event in events {
+                input.handle_event(event);
+            }
+            input.advance_frame(delta);
+            recorded_states.push(action_states(&input));
+        }
+
+        let recording = input.stop_recording().unwrap();
+        let recording = InputRecording::from_bytes(recording.to_bytes().as_slice()).unwrap();
+        assert_eq!(recording.scale_factor, 2f64);
+        assert_eq!(recording.frame_count(), recorded_states.len());
+
+        let mut replayed = bound_input();
+        replayed.start_replay(recording);
+        // the engine sets the window's scale factor after the replay started
+        replayed.set_scale_factor(1f64);
+
+        let mut replayed_states = Vec::new();
+        while let Some(delta) = replayed.get_replay_delta() {
+            replayed.advance_frame(delta);
+            replayed_states.push(action_states(&replayed));
+        }
+
+        assert_eq!(replayed_states, recorded_states);
+        assert!(!replayed.is_replaying());
+        assert_eq!(replayed.mouse_position_logical(), (5f32, 10f32));
+    }
+
+    #[test]
+    fn fixed_steps_see_each_edge_once() {
+        let mut input = bound_input();
+        let fixed_step = |input: &mut Input| {
+            input.begin_fixed_step();
+            let jump = input.action("jump").unwrap();
+            input.end_fixed_step();
+            (jump.just_pressed(), jump.just_released())
+        };
+
+        // a frame that runs two steps only reports the press to the first
+        input.handle_event(key(KeyCode::Space, true));
+        input.advance_frame(0.033f32);
+        assert_eq!(fixed_step(&mut input), (true, false));
+        assert_eq!(fixed_step(&mut input), (false, false));
+        assert!(input.just_pressed("jump"));
+
+        // a frame without a step hands its release to the next frame's step
+        input.handle_event(key(KeyCode::Space, false));
+        input.advance_frame(0.008f32);
+        input.advance_frame(0.008f32);
+        assert!(!input.just_released("jump"));
+        assert_eq!(fixed_step(&mut input), (false, true));
+        assert_eq!(fixed_step(&mut input), (false, false));
+    }
+
+    #[test]
+    fn edges_last_for_the_frame_they_happened_in() {
+        let mut input = bound_input();
+
+        input.handle_event(key(KeyCode::Space, true));
+        input.advance_frame(0.1f32);
+        let jump = input.action("jump").unwrap();
+        assert!(jump.is_pressed() && jump.just_pressed() && !jump.just_released());
+        assert_eq!(jump.held_duration(), 0f32);
+
+        input.advance_frame(0.1f32);
+        input.advance_frame(0.25f32);
+        let jump = input.action("jump").unwrap();
+        assert!(jump.is_pressed() && !jump.just_pressed());
+        assert!((jump.held_duration() - 0.35f32).abs() < 1e-6f32);
+
+        input.handle_event(key(KeyCode::Space, false));
+        input.advance_frame(0.1f32);
+        let jump = input.action("jump").unwrap();
+        assert!(!jump.is_pressed() && !jump.just_pressed() && jump.just_released());
+        assert_eq!(jump.held_duration(), 0f32);
+
+        input.advance_frame(0.1f32);
+        assert_eq!(input.action("jump").unwrap(), ActionState::default());
+    }
+
+    #[test]
+    fn a_press_between_two_frames_shows_both_edges() {
+        let mut input = bound_input();
+        input.handle_event(mouse(MouseButton::Left, true));
+        input.handle_event(mouse(MouseButton::Left, false));
+        input.advance_frame(0.016f32);
+
+        let fire = input.action("fire").unwrap();
+        assert!(fire.just_pressed() && fire.just_released() && !fire.is_pressed());
+
+        input.advance_frame(0.016f32);
+        assert!(!input.just_pressed("fire") && !input.just_released("fire"));
+    }
+
+    #[test]
+    fn repeats_are_counted_per_frame() {
+        let mut input = bound_input();
+        let repeat = InputEvent::Key { key: PhysicalKey::Code(KeyCode::Space), pressed: true, repeat: true };
+
+        input.handle_event(key(KeyCode::Space, true));
+        input.handle_event(repeat.clone());
+        input.handle_event(repeat.clone());
+        input.advance_frame(0.016f32);
+        assert_eq!(input.repeat_count("jump"), 2);
+        assert!(input.just_pressed("jump"));
+
+        input.handle_event(repeat);
+        input.advance_frame(0.016f32);
+        assert_eq!(input.repeat_count("jump"), 1);
+        assert!(!input.just_pressed("jump"));
+
+        input.advance_frame(0.016f32);
+        assert_eq!(input.repeat_count("jump"), 0);
+        assert_eq!(input.repeat_count("unmapped"), 0);
+    }
+
+    #[test]
+    fn wasd_vector_is_normalized_and_opposite_keys_cancel() {
+        let mut input = Input::new();
+        input.set_vector("move", vec![KeyCode::KeyW], vec![KeyCode::KeyS], vec![KeyCode::KeyA], vec![KeyCode::KeyD]);
+        input.set_axis("strafe", vec![KeyCode::KeyD], vec![KeyCode::KeyA]);
+
+        input.handle_event(key(KeyCode::KeyD, true));
+        input.handle_event(key(KeyCode::KeyW, true));
+        input.advance_frame(0.016f32);
+        let diagonal = input.vector("move");
+        assert!((diagonal.magnitude() - 1f32).abs() < 1e-6f32);
+        assert!((diagonal.x - diagonal.y).abs() < 1e-6f32);
+        assert_eq!(input.axis("strafe"), 1f32);
+
+        input.handle_event(key(KeyCode::KeyA, true));
+        input.advance_frame(0.016f32);
+        assert_eq!(input.vector("move"), Vector2::new(0f32, 1f32));
+        assert_eq!(input.axis("strafe"), 0f32);
+
+        input.set_vector_settings("move", VectorSettings { normalize: false, ..Default::default() }).unwrap();
+        input.handle_event(key(KeyCode::KeyA, false));
+        input.advance_frame(0.016f32);
+        assert_eq!(input.vector("move"), Vector2::new(1f32, 1f32));
+    }
+
+    #[test]
+    fn mock_gamepads_drive_actions_and_axes() {
+        let gamepads = MockGamepadBackend::new();
+        let mut input = Input::new();
+        input.set_gamepad_backend(Box::new(gamepads.clone()));
+        input.set_mapping("jump", vec![GamepadButton::South]);
+        input.set_axis_binding("steer", AxisBinding {
+            sources: vec![AxisSource::Gamepad(GamepadAxis::LeftStickX)],
+            settings: AxisSettings { dead_zone: 0.2f32, ..Default::default() },
+        });
+
+        let first = gamepads.connect("first");
+        let second = gamepads.connect("second");
+        input.advance_frame(0.016f32);
+        assert_eq!(input.gamepad_events().len(), 2);
+        assert_eq!(input.connected_gamepads().collect::<Vec<_>>(), vec![(first, "first"), (second, "second")]);
+
+        // the axis reads the pad that is pushed furthest
+        gamepads.press(first, GamepadButton::South);
+        gamepads.set_axis(first, GamepadAxis::LeftStickX, 0.3f32);
+        gamepads.set_axis(second, GamepadAxis::LeftStickX, -0.6f32);
+        input.advance_frame(0.016f32);
+        assert!(input.gamepad_events().is_empty());
+        assert!(input.just_pressed("jump"));
+        assert!(input.is_gamepad_button_pressed(first, GamepadButton::South));
+        assert!(!input.is_gamepad_button_pressed(second, GamepadButton::South));
+        assert!((input.axis("steer") + 0.5f32).abs() < 1e-6f32);
+
+        // a button held on two pads stays down until both let go
+        gamepads.press(second, GamepadButton::South);
+        gamepads.release(first, GamepadButton::South);
+        input.advance_frame(0.016f32);
+        assert!(input.is_pressed("jump") && !input.just_released("jump"));
+
+        // disconnecting releases everything the pad held
+        gamepads.disconnect(second);
+        input.advance_frame(0.016f32);
+        assert_eq!(input.gamepad_events(), &[GamepadEvent::Disconnected { id: second }]);
+        assert!(input.just_released("jump"));
+        assert!((input.axis("steer") - 0.125f32).abs() < 1e-6f32);
+
+        // events for pads that aren't connected are dropped
+        gamepads.press(second, GamepadButton::South);
+        input.advance_frame(0.016f32);
+        assert!(!input.is_pressed("jump"));
+    }
+
+    #[test]
+    fn rebind_replaces_or_appends_the_next_pressed_button() {
+        let mut input = bound_input();
+        input.set_mapping("dash", vec![KeyCode::KeyF]);
+
+        input.start_rebind(BindingSlot::Action { name: "jump".to_string(), index: 0 });
+        assert!(input.is_rebinding());
+        input.handle_event(key(KeyCode::KeyF, true));
+        // only the first press of the frame is taken
+        input.handle_event(key(KeyCode::KeyG, true));
+        assert!(input.take_rebind_result().is_none());
+        input.advance_frame(0.016f32);
+
+        let result = input.take_rebind_result().unwrap();
+        assert!(!input.is_rebinding());
+        assert_eq!(result.slot, BindingSlot::Action { name: "jump".to_string(), index: 0 });
+        assert_eq!(result.button, InputButton::Key(PhysicalKey::Code(KeyCode::KeyF)));
+        assert_eq!(result.conflicts, vec!["dash".to_string()]);
+        assert_eq!(input.get_bindings().actions["jump"], vec![InputButton::Key(PhysicalKey::Code(KeyCode::KeyF))]);
+        assert!(input.take_rebind_result().is_none());
+        assert_eq!(input.find_conflicts().len(), 1);
+
+        // an index past the end appends, and the rebound button works once pressed again
+        input.start_rebind(BindingSlot::Action { name: "jump".to_string(), index: 5 });
+        input.handle_event(mouse(MouseButton::Right, true));
+        input.advance_frame(0.016f32);
+        assert!(input.take_rebind_result().unwrap().conflicts.is_empty());
+        assert_eq!(input.get_bindings().actions["jump"].len(), 2);
+        input.handle_event(key(KeyCode::KeyF, false));
+        input.handle_event(mouse(MouseButton::Right, false));
+        input.advance_frame(0.016f32);
+        assert!(!input.is_pressed("jump"));
+        input.handle_event(mouse(MouseButton::Right, true));
+        input.advance_frame(0.016f32);
+        assert!(input.just_pressed("jump"));
+
+        // slots that don't name a button list are left alone
+        input.set_axis("steer", vec![KeyCode::KeyD], vec![KeyCode::KeyA]);
+        input.start_rebind(BindingSlot::Axis { name: "steer".to_string(), source: 1, positive: true, index: 0 });
+        input.handle_event(key(KeyCode::KeyH, true));
+        input.advance_frame(0.016f32);
+        assert!(input.take_rebind_result().is_none());
+        assert_eq!(input.get_bindings().axes["steer"].sources.len(), 1);
+
+        // a cancelled rebind ignores the next press
+        input.start_rebind(BindingSlot::Action { name: "fire".to_string(), index: 0 });
+        input.cancel_rebind();
+        input.handle_event(key(KeyCode::KeyJ, true));
+        input.advance_frame(0.016f32);
+        assert!(input.take_rebind_result().is_none());
+        assert_eq!(input.get_bindings().actions["fire"], vec![InputButton::Mouse(MouseButton::Left)]);
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+use serde::{Deserialize, Serialize};
+use winit::event::{Ime, KeyEvent, MouseButton, MouseScrollDelta, WindowEvent};
+use winit::keyboard::PhysicalKey;
+use crate::input::gamepad::GamepadEvent;
+use crate::input::touch::TouchState;
+use crate::input::files::DroppedFile;
+
+// the part of a window, device or gamepad event that `Input` looks at, everything
+// is funneled through this so recorded and live input take the same path
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum InputEvent {
+    Key { key: PhysicalKey, pressed: bool, repeat: bool },
+    MouseButton { button: MouseButton, pressed: bool },
+    CursorMoved { x: f32, y: f32 },
+    CursorLeft,
+    WheelLines { x: f32, y: f32 },
+    WheelPixels { x: f32, y: f32 },
+    ScaleFactorChanged(f64),
+    RawMouseMotion { x: f32, y: f32 },
+    Gamepad(GamepadEvent),
+    // recorded so a replay hides the same input from the game as the ui did
+    UiCapture { pointer: bool, keyboard: bool },
+    // characters typed without an ime, control characters are left to key bindings
+    Text(String),
+    ImeEnabled(bool),
+    // byte range of the ime cursor inside `text`, if it should be shown
+    ImePreedit { text: String, cursor: Option<(usize, usize)> },
+    ImeCommit(String),
+    Touch { id: u64, state: TouchState, x: f32, y: f32 },
+    // the dropped bytes are recorded too, so a replay doesn't depend on the file still being there
+    FileHovered(String),
+    FileHoverCancelled,
+    FileDropped(DroppedFile),
+    // not used by `Input` itself, only passed on to listeners
+    Focused(bool),
+    Resized { width: u32, height: u32 },
+}
+
+impl InputEvent {
+    pub(crate) fn from_window_event(event: &WindowEvent, events: &mut Vec<Self>) {
+        let input_event = match event {
+            WindowEvent::KeyboardInput {
+                event: KeyEvent { physical_key, state, repeat, text, .. }, ..
+            } => {
+                events.push(InputEvent::Key { key: *physical_key, pressed: state.is_pressed(), repeat: *repeat });
+                let text: String = text.iter()
+                    .flat_map(|text| text.chars())
+                    .filter(|c| !c.is_control())
+                    .collect();
+                if !state.is_pressed() || text.is_empty() {
+                    return;
+                }
+                InputEvent::Text(text)
+            }
+            WindowEvent::MouseInput { state, button, .. } =>
+                InputEvent::MouseButton { button: *button, pressed: state.is_pressed() },
+            WindowEvent::CursorMoved { position, .. } =>
+                InputEvent::CursorMoved { x: position.x as f32, y: position.y as f32 },
+            WindowEvent::CursorLeft { .. } => InputEvent::CursorLeft,
+            WindowEvent::MouseWheel { delta: MouseScrollDelta::LineDelta(x, y), .. } =>
+                InputEvent::WheelLines { x: *x, y: *y },
+            WindowEvent::MouseWheel { delta: MouseScrollDelta::PixelDelta(position), .. } =>
+                InputEvent::WheelPixels { x: position.x as f32, y: position.y as f32 },
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => InputEvent::ScaleFactorChanged(*scale_factor),
+            WindowEvent::Focused(focused) => InputEvent::Focused(*focused),
+            WindowEvent::Resized(size) => InputEvent::Resized { width: size.width, height: size.height },
+            WindowEvent::Ime(Ime::Enabled) => InputEvent::ImeEnabled(true),
+            WindowEvent::Ime(Ime::Disabled) => InputEvent::ImeEnabled(false),
+            WindowEvent::Ime(Ime::Preedit(text, cursor)) => InputEvent::ImePreedit { text: text.clone(), cursor: *cursor },
+            WindowEvent::Ime(Ime::Commit(text)) => InputEvent::ImeCommit(text.clone()),
+            WindowEvent::Touch(touch) => InputEvent::Touch {
+                id: touch.id,
+                state: touch.phase.into(),
+                x: touch.location.x as f32,
+                y: touch.location.y as f32,
+            },
+            #[cfg(not(target_arch = "wasm32"))]
+            WindowEvent::HoveredFile(path) => InputEvent::FileHovered(crate::input::files::get_file_name(path)),
+            WindowEvent::HoveredFileCancelled => InputEvent::FileHoverCancelled,
+            _ => return,
+        };
+        events.push(input_event);
+    }
+}
+
+#[derive(Debug)]
+pub enum RecordingError {
+    Io(std::io::Error),
+    Decode(bincode::Error),
+    UnsupportedVersion(u32),
+}
+
+impl Display for RecordingError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecordingError::Io(err) => write!(f, "failed to access input recording: {err}"),
+            RecordingError::Decode(err) => write!(f, "invalid input recording: {err}"),
+            RecordingError::UnsupportedVersion(version) =>
+                write!(f, "input recording version {version} is not supported, expected {}", InputRecording::VERSION),
+        }
+    }
+}
+
+impl std::error::Error for RecordingError {}
+
+impl From<std::io::Error> for RecordingError {
+    fn from(err: std::io::Error) -> Self {
+        RecordingError::Io(err)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RecordedFrame {
+    // counted from the start of the recording
+    pub index: u64,
+    // unscaled seconds, replayed instead of the real frame time
+    pub delta: f32,
+    // everything that arrived before this frame's snapshot was taken
+    pub events: Vec<InputEvent>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct InputRecording {
+    version: u32,
+    pub scale_factor: f64,
+    pub mouse_position: (f32, f32),
+    pub frames: Vec<RecordedFrame>,
+}
+
+impl InputRecording {
+    const VERSION: u32 = 1;
+
+    pub(crate) fn new(scale_factor: f64, mouse_position: (f32, f32)) -> Self {
+        Self {
+            version: Self::VERSION,
+            scale_factor,
+            mouse_position,
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn frame_count(self: &Self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn to_bytes(self: &Self) -> Vec<u8> {
+        bincode::serialize(self).expect("input recordings are always serializable")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, RecordingError> {
+        let recording: Self = bincode::deserialize(bytes).map_err(RecordingError::Decode)?;
+        if recording.version != Self::VERSION {
+            return Err(RecordingError::UnsupportedVersion(recording.version));
+        }
+        Ok(recording)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(self: &Self, path: &std::path::Path) -> Result<(), RecordingError> {
+        Ok(std::fs::write(path, self.to_bytes())?)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: &std::path::Path) -> Result<Self, RecordingError> {
+        Self::from_bytes(std::fs::read(path)?.as_slice())
+    }
+}
+
+pub(crate) struct InputRecorder {
+    recording: InputRecording,
+    pending: Vec<InputEvent>,
+}
+
+impl InputRecorder {
+    pub(crate) fn new(scale_factor: f64, mouse_position: (f32, f32)) -> Self {
+        Self {
+            recording: InputRecording::new(scale_factor, mouse_position),
+            pending: Vec::new(),
+        }
+    }
+
+    pub(crate) fn push(self: &mut Self, event: InputEvent) {
+        self.pending.push(event);
+    }
+
+    pub(crate) fn end_frame(self: &mut Self, delta: f32) {
+        let index = self.recording.frames.len() as u64;
+        self.recording.frames.push(RecordedFrame {
+            index,
+            delta,
+            events: std::mem::take(&mut self.pending),
+        });
+    }
+
+    // events after the last frame never reached the game, so they are dropped
+    pub(crate) fn finish(self: Self) -> InputRecording {
+        self.recording
+    }
+}
+
+pub(crate) struct InputReplay {
+    frames: VecDeque<RecordedFrame>,
+}
+
+impl InputReplay {
+    pub(crate) fn new(recording: InputRecording) -> Self {
+        Self { frames: recording.frames.into() }
+    }
+
+    pub(crate) fn peek_delta(self: &Self) -> Option<f32> {
+        self.frames.front().map(|frame| frame.delta)
+    }
+
+    pub(crate) fn next_frame(self: &mut Self) -> Option<RecordedFrame> {
+        self.frames.pop_front()
+    }
+
+    pub(crate) fn is_finished(self: &Self) -> bool {
+        self.frames.is_empty()
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use winit::event::TouchPhase;
+
+// a finger that moves less than this (in logical pixels) and lifts quickly is a tap
+const TAP_SLOP: f32 = 10f32;
+const TAP_MAX_DURATION: f32 = 0.3f32;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TouchState {
+    Started,
+    Moved,
+    Ended,
+    Cancelled,
+}
+
+impl From<TouchPhase> for TouchState {
+    fn from(phase: TouchPhase) -> Self {
+        match phase {
+            TouchPhase::Started => TouchState::Started,
+            TouchPhase::Moved => TouchState::Moved,
+            TouchPhase::Ended => TouchState::Ended,
+            TouchPhase::Cancelled => TouchState::Cancelled,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Touch {
+    pub id: u64,
+    // physical pixels
+    pub start_position: (f32, f32),
+    pub position: (f32, f32),
+    // seconds since the finger went down
+    pub duration: f32,
+    moved_past_slop: bool,
+}
+
+// what the fingers did during one frame, drags and pans in physical pixels
+#[derive(Clone, Debug, Default)]
+pub struct Gestures {
+    pub taps: Vec<(f32, f32)>,
+    // a single finger moving
+    pub drag: (f32, f32),
+    // two fingers, the centroid movement
+    pub pan: (f32, f32),
+    // two fingers, the ratio of the new to the old finger distance minus 1, positive when spreading
+    pub pinch: f32,
+    // two fingers, radians, counter-clockwise on screen
+    pub rotation: f32,
+}
+
+#[derive(Default)]
+pub(crate) struct TouchTracker {
+    touches: BTreeMap<u64, Touch>,
+    // where the touches were when the last frame was taken
+    last_positions: BTreeMap<u64, (f32, f32)>,
+    pending_taps: Vec<(f32, f32)>,
+}
+
+impl TouchTracker {
+    pub(crate) fn process(self: &mut Self, id: u64, state: TouchState, position: (f32, f32), scale_factor: f64) {
+        match state {
+            TouchState::Started => {
+                self.touches.insert(id, Touch {
+                    id,
+                    start_position: position,
+                    position,
+                    duration: 0f32,
+                    moved_past_slop: false,
+                });
+            }
+            TouchState::Moved => {
+                let Some(touch) = self.touches.get_mut(&id) else { return; };
+                touch.position = position;
+                let slop = TAP_SLOP * scale_factor as f32;
+                let moved = (position.0 - touch.start_position.0).hypot(position.1 - touch.start_position.1);
+                touch.moved_past_slop |= moved > slop;
+            }
+            TouchState::Ended => {
+                let Some(touch) = self.touches.remove(&id) else { return; };
+                if !touch.moved_past_slop && touch.duration <= TAP_MAX_DURATION {
+                    self.pending_taps.push(position);
+                }
+            }
+            TouchState::Cancelled => {
+                self.touches.remove(&id);
+            }
+        }
+    }
+
+    pub(crate) fn get_touches(self: &Self) -> impl Iterator<Item = &Touch> {
+        self.touches.values()
+    }
+
+    // only fingers that were down for the whole frame count towards movement,
+    // so a finger landing or lifting never reads as a jump
+    pub(crate) fn advance_frame(self: &mut Self, delta: f32) -> Gestures {
+        let mut gestures = Gestures {
+            taps: std::mem::take(&mut self.pending_taps),
+            ..Default::default()
+        };
+
+        let moved: Vec<((f32, f32), (f32, f32))> = self.touches.values()
+            .filter(|touch| touch.moved_past_slop)
+            .filter_map(|touch| self.last_positions.get(&touch.id).map(|last| (*last, touch.position)))
+            .collect();
+
+        match (self.touches.len(), moved.as_slice()) {
+            (1, [(last, now)]) => {
+                gestures.drag = (now.0 - last.0, now.1 - last.1);
+            }
+            (2, _) => {
+                let pair: Vec<((f32, f32), (f32, f32))> = self.touches.values()
+                    .filter_map(|touch| self.last_positions.get(&touch.id).map(|last| (*last, touch.position)))
+                    .collect();
+                if let [(last_a, now_a), (last_b, now_b)] = pair.as_slice() {
+                    let centroid = |a: &(f32, f32), b: &(f32, f32)| ((a.0 + b.0) / 2f32, (a.1 + b.1) / 2f32);
+                    let (last_center, now_center) = (centroid(last_a, last_b), centroid(now_a, now_b));
+                    gestures.pan = (now_center.0 - last_center.0, now_center.1 - last_center.1);
+
+                    let last_span = (last_b.0 - last_a.0, last_b.1 - last_a.1);
+                    let now_span = (now_b.0 - now_a.0, now_b.1 - now_a.1);
+                    let last_length = last_span.0.hypot(last_span.1);
+                    if last_length > 0f32 {
+                        gestures.pinch = now_span.0.hypot(now_span.1) / last_length - 1f32;
+                    }
+                    // screen y points down, flip it so positive is counter-clockwise
+                    let angle = |span: (f32, f32)| (-span.1).atan2(span.0);
+                    let mut rotation = angle(now_span) - angle(last_span);
+                    if rotation > std::f32::consts::PI { rotation -= std::f32::consts::TAU; }
+                    if rotation < -std::f32::consts::PI { rotation += std::f32::consts::TAU; }
+                    gestures.rotation = rotation;
+                }
+            }
+            _ => {}
+        }
+
+        for touch in self.touches.values_mut() {
+            touch.duration += delta;
+        }
+        self.last_positions = self.touches.values().map(|touch| (touch.id, touch.position)).collect();
+
+        gestures
+    }
+}
@@ -11,6 +11,8 @@ pub mod input;
 pub mod utils;
 mod game_elements;
 pub mod math;
+pub mod time;
+pub mod context;
 mod egui;
 
 use parking_lot::Mutex;
@@ -18,6 +20,11 @@ use parking_lot::Mutex;
 use crate::render_backend::RenderBackend;
 use winit::event_loop::EventLoop;
 use crate::input::Input;
+use crate::input::gamepad::GilrsGamepadBackend;
+use crate::time::Time;
+use crate::context::EngineContext;
+pub use crate::windowing::{FullscreenMode, PresentMode, WindowConfig};
+use winit::keyboard::KeyCode;
 
 #[cfg(target_arch = "wasm32")]
 use {
@@ -26,18 +33,26 @@ use {
     web_sys
 };
 use crate::egui::EguiRenderer;
-use crate::render_backend::web::EguiUiShow;
+use crate::render_backend::EguiUiShow;
 
 pub trait PoissonGame {
     type Ren: RenderBackend;
     fn new() -> Self;
+    fn window_config(self: &Self) -> WindowConfig {
+        WindowConfig::default()
+    }
     fn pre_init(self: &mut Self, input: &mut Input);
-    fn init(self: &mut Self, input: &mut Input, renderer: &mut Self::Ren);
-    fn update(self: &mut Self, input: &mut Input, renderer: &mut Self::Ren);
+    fn init(self: &mut Self, input: &mut Input, renderer: &mut Self::Ren, context: &mut EngineContext);
+    // runs zero or more times per frame at `Time::fixed_timestep`, put simulation here.
+    // `just_pressed` and `just_released` here are true in exactly one fixed step, the first
+    // one after the edge, even when a frame runs several steps or none
+    fn fixed_update(self: &mut Self, _input: &mut Input, _time: &mut Time) {}
+    // runs once per frame, `time.alpha()` says how far past the last fixed step this frame is
+    fn update(self: &mut Self, input: &mut Input, renderer: &mut Self::Ren, time: &mut Time, context: &mut EngineContext);
     fn get_egui_ui_show(self: &mut Self) -> &mut impl EguiUiShow;
 }
 
-trait AsAny {
+pub trait AsAny {
     fn as_any(self: &Self) -> &dyn Any;
     fn as_any_mut(self: &mut Self) -> &mut dyn Any;
 }
@@ -47,9 +62,18 @@ where GameType: PoissonGame
 {
     window: Option<Arc<Window>>,
     input: Input,
+    time: Time,
+    context: EngineContext,
     renderer: Arc<Mutex<Option<GameType::Ren>>>,
     game: GameType,
     done_init: bool,
+    fullscreen_mode: FullscreenMode,
+    fullscreen_toggle_key: Option<KeyCode>,
+    // recording and replay start once, when the first window exists
+    #[cfg(not(target_arch = "wasm32"))]
+    input_recording_started: bool,
+    #[cfg(not(target_arch = "wasm32"))]
+    input_recording_path: Option<std::path::PathBuf>,
 }
 
 impl<GameType: PoissonGame> PoissonEngine<GameType>
@@ -57,26 +81,89 @@ impl<GameType: PoissonGame> PoissonEngine<GameType>
     pub fn new() -> Self {
         let mut game = GameType::new();
         let mut input = Input::new();
+        if let Some(gamepads) = GilrsGamepadBackend::new() {
+            input.set_gamepad_backend(Box::new(gamepads));
+        }
         game.pre_init(&mut input);
         Self {
             window: None,
             input,
+            time: Time::new(),
+            context: EngineContext::new(),
             renderer: Default::default(),
             game,
-            done_init: false
+            done_init: false,
+            fullscreen_mode: FullscreenMode::Windowed,
+            fullscreen_toggle_key: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            input_recording_started: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            input_recording_path: None,
+        }
+    }
+
+    // POISSON_RECORD_INPUT=<file> records the session and writes it on exit,
+    // POISSON_REPLAY_INPUT=<file> plays one back instead of live input.
+    // called from `resumed` after the window's scale factor reached `Input`, so the recording
+    // starts from it and a replay can override it
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn start_input_recording(self: &mut Self) {
+        if self.input_recording_started {
+            return;
+        }
+        self.input_recording_started = true;
+
+        if let Some(path) = std::env::var_os("POISSON_REPLAY_INPUT") {
+            match crate::input::InputRecording::load(std::path::Path::new(&path)) {
+                Ok(recording) => self.input.start_replay(recording),
+                Err(err) => log::error!("not replaying {}: {err}", path.to_string_lossy()),
+            }
+        }
+
+        if let Some(path) = std::env::var_os("POISSON_RECORD_INPUT") {
+            self.input.start_recording();
+            self.input_recording_path = Some(std::path::PathBuf::from(path));
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn save_input_recording(self: &mut Self) {
+        let (Some(path), Some(recording)) = (self.input_recording_path.take(), self.input.stop_recording()) else {
+            return;
+        };
+        match recording.save(path.as_path()) {
+            Ok(()) => log::info!("saved {} frames of input to {}", recording.frame_count(), path.display()),
+            Err(err) => log::error!("{err}"),
         }
     }
     
     fn init(self: &mut Self) {
         if let Some(backend) = self.renderer.lock().as_mut() {
-            self.game.init(&mut self.input, backend);
+            self.game.init(&mut self.input, backend, &mut self.context);
+            self.time.reset_clock();
             self.done_init = true;
         }
     }
     
     fn update(self: &mut Self) {
         if let Some(render_backend) = self.renderer.lock().as_mut() {
-            self.game.update(&mut self.input, render_backend);
+            match self.input.get_replay_delta() {
+                Some(delta) => {
+                    self.time.advance_by(delta);
+                    self.time.reset_clock();
+                },
+                None => self.time.advance(),
+            }
+            if let Some(egui_context) = render_backend.get_egui_context() {
+                self.input.set_ui_capture(egui_context.wants_pointer_input(), egui_context.wants_keyboard_input());
+            }
+            self.input.advance_frame(self.time.unscaled_delta());
+            while self.time.consume_fixed_step() {
+                self.input.begin_fixed_step();
+                self.game.fixed_update(&mut self.input, &mut self.time);
+                self.input.end_fixed_step();
+            }
+            self.game.update(&mut self.input, render_backend, &mut self.time, &mut self.context);
             render_backend.render(self.window.as_ref().unwrap(), self.game.get_egui_ui_show());
         }
     }
@@ -4,19 +4,24 @@ use std::any::Any;
 use std::marker::PhantomData;
 use std::sync::Arc;
 use bytemuck::{Pod, Zeroable};
+use image::DynamicImage;
 use winit::window::Window;
 use parking_lot::Mutex;
 use winit::event::WindowEvent;
-use crate::{AsAny, PoissonGame};
+use crate::{AsAny, PoissonGame, PresentMode};
 use crate::egui::EguiRenderer;
 use crate::render_backend::render_interface::RenderObject;
-use crate::render_backend::render_interface::resources::GpuBufferHandle;
-use crate::render_backend::web::EguiUiShow;
+use crate::render_backend::render_interface::drawlets::PassHandle;
+use crate::render_backend::render_interface::resources::{BufferUsage, GpuBufferHandle, GpuTextureHandle, TextureOptions};
+use crate::render_backend::registry::{GenerationalKey, RegistryKey};
 // #[cfg(not(target_arch = "wasm32"))]
 // pub mod vulkan;
 
 pub mod web;
+pub mod null;
+pub mod software;
 pub mod render_interface;
+pub mod registry;
 
 
 #[repr(C)]
@@ -25,16 +30,41 @@ pub struct Mat4Ubo {
     pub data: cgmath::Matrix4<f32>
 }
 
-#[derive(Hash, PartialEq, Eq, Clone, Copy)]
-pub struct PassID(usize);
+// handed out by the registry that owns the object, a pass by its backend,
+// a pipeline by its pass, a drawlet by its pipeline and an instance by its drawlet
+#[derive(Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+pub struct PassID(GenerationalKey);
 
-#[derive(Hash, PartialEq, Eq, Clone, Copy)]
-pub struct PipelineID(usize);
+#[derive(Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+pub struct PipelineID(GenerationalKey);
 
-#[derive(Hash, PartialEq, Eq, Clone, Copy)]
-pub struct DrawletID(usize);
+#[derive(Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+pub struct DrawletID(GenerationalKey);
 
-#[derive(Hash, PartialEq, Eq, Clone, Copy)]
+#[derive(Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+pub struct InstanceID(GenerationalKey);
+
+impl RegistryKey for PassID {
+    fn from_key(key: GenerationalKey) -> Self { PassID(key) }
+    fn key(self: Self) -> GenerationalKey { self.0 }
+}
+
+impl RegistryKey for PipelineID {
+    fn from_key(key: GenerationalKey) -> Self { PipelineID(key) }
+    fn key(self: Self) -> GenerationalKey { self.0 }
+}
+
+impl RegistryKey for DrawletID {
+    fn from_key(key: GenerationalKey) -> Self { DrawletID(key) }
+    fn key(self: Self) -> GenerationalKey { self.0 }
+}
+
+impl RegistryKey for InstanceID {
+    fn from_key(key: GenerationalKey) -> Self { InstanceID(key) }
+    fn key(self: Self) -> GenerationalKey { self.0 }
+}
+
+#[derive(Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
 pub struct ViewID(usize);
 
 
@@ -44,33 +74,48 @@ pub struct Wgpu {} impl Ren for Wgpu {}
 pub struct Vk {} impl Ren for Vk {}
 
 
+pub trait EguiUiShow {
+    fn show(&mut self, ctx: &egui::Context);
+}
+
+// everything a game needs to build its scene, so `PoissonGame::Ren` can be swapped
+// for any backend without touching the game code
 pub trait RenderBackend {
     const PERSPECTIVE_ALIGNMENT: [f32; 3];
-    fn init(backend_clone: Arc<Mutex<Option<Self>>>, window: Arc<Window>) where Self: Sized;
+    fn init(backend_clone: Arc<Mutex<Option<Self>>>, window: Arc<Window>, present_mode: PresentMode) where Self: Sized;
     fn render(self: &mut Self, window: &Arc<Window>, egui_show_obj: &mut dyn EguiUiShow);
     fn process_event(self: &mut Self, window: &Window, event: &WindowEvent);
     fn resize(self: &mut Self, width: u32, height: u32);
-    fn create_index_buffer(self: &Self, data: &[u32]) -> GpuBufferHandle<u32>;
-    fn create_vertex_buffer<T:Sized + 'static>(self: &Self, data: &[T]) -> GpuBufferHandle<T>;
+    fn create_render_pass(self: &mut Self) -> PassHandle;
+    fn create_index_buffer_with_usage(self: &Self, data: &[u32], usage: BufferUsage) -> GpuBufferHandle<u32>;
+    // `T` has to be a multiple of 4 bytes, wgpu only copies whole words
+    fn create_vertex_buffer_with_usage<T:Sized + 'static>(self: &Self, data: &[T], usage: BufferUsage) -> GpuBufferHandle<T>;
+    fn create_index_buffer(self: &Self, data: &[u32]) -> GpuBufferHandle<u32> {
+        self.create_index_buffer_with_usage(data, BufferUsage::Static)
+    }
+    fn create_vertex_buffer<T:Sized + 'static>(self: &Self, data: &[T]) -> GpuBufferHandle<T> {
+        self.create_vertex_buffer_with_usage(data, BufferUsage::Static)
+    }
+    // overwrites `data.len()` elements starting at element `offset`, the buffer keeps its size
+    fn write_buffer<T:Sized + 'static>(self: &Self, buffer: &GpuBufferHandle<T>, offset: usize, data: &[T]) {
+        buffer.write(offset, data)
+    }
+    fn create_texture_with_options(self: &Self, image: &DynamicImage, options: TextureOptions) -> GpuTextureHandle;
+    // an sRGB texture without mips
+    fn create_texture(self: &Self, image: &DynamicImage) -> GpuTextureHandle {
+        self.create_texture_with_options(image, TextureOptions::default())
+    }
 
     fn get_width(self: &Self) -> u32;
     fn get_height(self: &Self) -> u32;
 
-    fn get_egui_renderer(self: &Self) -> EguiRenderer;
-
-    fn get_render_pass_id() -> PassID {
-        use std::sync::atomic::{AtomicUsize, Ordering};
-        static COUNTER:AtomicUsize = AtomicUsize::new(1);
-        PassID(COUNTER.fetch_add(1, Ordering::Relaxed))
-    }
+    // `None` for backends that don't draw egui
+    fn get_egui_renderer(self: &Self) -> Option<&EguiRenderer>;
+    // `None` when egui never sees window events, so it can't capture input
+    fn get_egui_context(self: &Self) -> Option<egui::Context>;
 }
 
 pub trait RenderPipeline<RenObj: RenderObject> {
-    fn get_drawlet_id() -> DrawletID {
-        use std::sync::atomic::{AtomicUsize, Ordering};
-        static COUNTER:AtomicUsize = AtomicUsize::new(1);
-        DrawletID(COUNTER.fetch_add(1, Ordering::Relaxed))
-    }
 }
 
 pub trait RenderDrawlet: Sized {
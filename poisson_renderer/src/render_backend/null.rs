@@ -0,0 +1,612 @@
+use std::any::{Any, TypeId};
+use std::marker::PhantomData;
+use std::sync::Arc;
+use cgmath::{Matrix4, Vector3};
+use image::DynamicImage;
+use parking_lot::Mutex;
+use winit::event::WindowEvent;
+use winit::window::Window;
+use crate::{AsAny, PresentMode};
+use crate::egui::EguiRenderer;
+use crate::render_backend::{DrawletID, InstanceID, PassID, PipelineID, RenderBackend, RenderPipeline};
+use crate::render_backend::registry::Registry;
+use crate::render_backend::render_interface::{Mesh, RenderObject};
+use crate::render_backend::render_interface::drawlets::{erase_pipeline, DrawletTrait, PassHandle, PassTrait, PipelineError, PipelineFactories, PipelineTrait, ReleaseQueue};
+use crate::render_backend::render_interface::drawlets::colored_mesh::{ColoredMesh, ColoredMeshData, ColoredMeshDrawletTrait};
+use crate::render_backend::render_interface::drawlets::instanced_mesh::{Instance, InstanceList, InstancedColoredMesh, InstancedColoredMeshData, InstancedColoredMeshDrawletTrait, InstancedDrawletTrait, InstancedLitMesh, InstancedLitMeshData, InstancedLitMeshDrawletTrait};
+use crate::render_backend::render_interface::drawlets::lit_colored_mesh::{LitColoredMesh, LitColoredMeshData, LitColoredMeshDrawletTrait};
+use crate::render_backend::render_interface::drawlets::textured_mesh::{TexturedMesh, TexturedMeshData, TexturedMeshDrawletTrait};
+use crate::render_backend::render_interface::resources::{max_mip_level_count, BufferUsage, GpuBufferHandle, GpuBufferTrait, GpuTextureHandle, GpuTextureTrait, TextureFormat, TextureOptions};
+use crate::render_backend::EguiUiShow;
+
+// a backend that creates no gpu objects at all, every call is recorded so game
+// logic can be driven and asserted against from `cargo test` on headless machines
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NullUniforms {
+    // the view projection for instanced meshes
+    pub mvp: Option<Matrix4<f32>>,
+    pub light_dir: Option<Vector3<f32>>,
+    pub view_dir: Option<Vector3<f32>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct NullDrawCall {
+    pub pass: PassID,
+    pub pipeline: PipelineID,
+    pub shader_path: String,
+    pub drawlet: DrawletID,
+    pub num_indices: u32,
+    pub num_vertices: u32,
+    pub uniforms: NullUniforms,
+    // empty unless the render object is instanced
+    pub instances: Vec<Instance>,
+    // width and height of the bound texture, if the render object has one
+    pub texture_size: Option<(u32, u32)>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct NullFrame {
+    pub index: u64,
+    pub width: u32,
+    pub height: u32,
+    pub draw_calls: Vec<NullDrawCall>,
+}
+
+pub trait NullRenderObject: RenderObject + Sized + 'static {
+    fn new_drawlet(init_data: &Self::Data) -> NullDrawlet;
+    fn upcast_drawlet(drawlet: &mut NullDrawlet) -> &mut Self::DynDrawlet;
+}
+
+pub struct NullDrawlet {
+    // the mesh's buffers, counts are read when a frame is recorded so writes to them show up
+    index_buffer: GpuBufferHandle<u32>,
+    vertex_count: Box<dyn Fn() -> usize>,
+    uniforms: NullUniforms,
+    instances: InstanceList,
+    texture: Option<GpuTextureHandle>,
+}
+
+impl NullDrawlet {
+    pub fn new<V: 'static>(mesh: &Mesh<V>, uniforms: NullUniforms) -> Self {
+        let vertex_buffer = mesh.vertex.clone();
+
+        Self {
+            index_buffer: mesh.index.clone(),
+            vertex_count: Box::new(move || vertex_buffer.get_count()),
+            uniforms,
+            instances: InstanceList::new(),
+            texture: None,
+        }
+    }
+
+    pub fn with_texture(self: Self, texture: &GpuTextureHandle) -> Self {
+        Self {
+            texture: Some(texture.clone()),
+            ..self
+        }
+    }
+
+    pub fn get_uniforms(self: &Self) -> NullUniforms {
+        self.uniforms
+    }
+
+    // for the drawlet traits of render objects defined outside this crate
+    pub fn get_uniforms_mut(self: &mut Self) -> &mut NullUniforms {
+        &mut self.uniforms
+    }
+}
+
+impl<T: RenderObject> DrawletTrait<T> for NullDrawlet {}
+
+impl ColoredMeshDrawletTrait for NullDrawlet {
+    fn set_mvp(self: &mut Self, mvp: Matrix4<f32>) {
+        self.uniforms.mvp = Some(mvp);
+    }
+}
+
+impl TexturedMeshDrawletTrait for NullDrawlet {
+    fn set_mvp(self: &mut Self, mvp: Matrix4<f32>) {
+        self.uniforms.mvp = Some(mvp);
+    }
+
+    fn set_texture(self: &mut Self, texture: &GpuTextureHandle) {
+        self.texture = Some(texture.clone());
+    }
+}
+
+impl LitColoredMeshDrawletTrait for NullDrawlet {
+    fn set_mvp(self: &mut Self, mvp: Matrix4<f32>) {
+        self.uniforms.mvp = Some(mvp);
+    }
+
+    fn set_light_dir(self: &mut Self, light_dir: Vector3<f32>) {
+        self.uniforms.light_dir = Some(light_dir);
+    }
+
+    fn set_view_dir(self: &mut Self, view_dir: Vector3<f32>) {
+        self.uniforms.view_dir = Some(view_dir);
+    }
+}
+
+impl InstancedDrawletTrait for NullDrawlet {
+    fn add_instance(self: &mut Self, instance: Instance) -> InstanceID {
+        self.instances.add(instance)
+    }
+
+    fn remove_instance(self: &mut Self, id: InstanceID) {
+        if self.instances.remove(id).is_none() {
+            log::warn!("ignoring stale instance {:?}", id);
+        }
+    }
+
+    fn update_instance(self: &mut Self, id: InstanceID, instance: Instance) {
+        if self.instances.update(id, instance).is_none() {
+            log::warn!("ignoring stale instance {:?}", id);
+        }
+    }
+
+    fn get_instance(self: &Self, id: InstanceID) -> Option<Instance> {
+        self.instances.get(id).copied()
+    }
+
+    fn instance_count(self: &Self) -> usize {
+        self.instances.len()
+    }
+}
+
+impl InstancedColoredMeshDrawletTrait for NullDrawlet {
+    fn set_view_proj(self: &mut Self, view_proj: Matrix4<f32>) {
+        self.uniforms.mvp = Some(view_proj);
+    }
+}
+
+impl InstancedLitMeshDrawletTrait for NullDrawlet {
+    fn set_view_proj(self: &mut Self, view_proj: Matrix4<f32>) {
+        self.uniforms.mvp = Some(view_proj);
+    }
+
+    fn set_light_dir(self: &mut Self, light_dir: Vector3<f32>) {
+        self.uniforms.light_dir = Some(light_dir);
+    }
+
+    fn set_view_dir(self: &mut Self, view_dir: Vector3<f32>) {
+        self.uniforms.view_dir = Some(view_dir);
+    }
+}
+
+impl NullRenderObject for ColoredMesh {
+    fn new_drawlet(init_data: &ColoredMeshData) -> NullDrawlet {
+        NullDrawlet::new(
+            &init_data.mesh,
+            NullUniforms {
+                mvp: Some(init_data.mvp_data),
+                light_dir: None,
+                view_dir: None,
+            },
+        )
+    }
+
+    fn upcast_drawlet(drawlet: &mut NullDrawlet) -> &mut (dyn ColoredMeshDrawletTrait + 'static) {
+        drawlet
+    }
+}
+
+impl NullRenderObject for TexturedMesh {
+    fn new_drawlet(init_data: &TexturedMeshData) -> NullDrawlet {
+        NullDrawlet::new(
+            &init_data.mesh,
+            NullUniforms {
+                mvp: Some(init_data.mvp_data),
+                light_dir: None,
+                view_dir: None,
+            },
+        ).with_texture(&init_data.texture)
+    }
+
+    fn upcast_drawlet(drawlet: &mut NullDrawlet) -> &mut (dyn TexturedMeshDrawletTrait + 'static) {
+        drawlet
+    }
+}
+
+impl NullRenderObject for InstancedColoredMesh {
+    fn new_drawlet(init_data: &InstancedColoredMeshData) -> NullDrawlet {
+        NullDrawlet::new(
+            &init_data.mesh,
+            NullUniforms {
+                mvp: Some(init_data.view_proj),
+                light_dir: None,
+                view_dir: None,
+            },
+        )
+    }
+
+    fn upcast_drawlet(drawlet: &mut NullDrawlet) -> &mut (dyn InstancedColoredMeshDrawletTrait + 'static) {
+        drawlet
+    }
+}
+
+impl NullRenderObject for InstancedLitMesh {
+    fn new_drawlet(init_data: &InstancedLitMeshData) -> NullDrawlet {
+        NullDrawlet::new(
+            &init_data.mesh,
+            NullUniforms {
+                mvp: Some(init_data.view_proj),
+                light_dir: Some(init_data.light_dir.truncate()),
+                view_dir: Some(init_data.view_dir.truncate()),
+            },
+        )
+    }
+
+    fn upcast_drawlet(drawlet: &mut NullDrawlet) -> &mut (dyn InstancedLitMeshDrawletTrait + 'static) {
+        drawlet
+    }
+}
+
+impl NullRenderObject for LitColoredMesh {
+    fn new_drawlet(init_data: &LitColoredMeshData) -> NullDrawlet {
+        NullDrawlet::new(
+            &init_data.mesh,
+            NullUniforms {
+                mvp: Some(init_data.mvp_data),
+                light_dir: Some(init_data.light_dir.truncate()),
+                view_dir: Some(init_data.view_dir.truncate()),
+            },
+        )
+    }
+
+    fn upcast_drawlet(drawlet: &mut NullDrawlet) -> &mut (dyn LitColoredMeshDrawletTrait + 'static) {
+        drawlet
+    }
+}
+
+pub trait NullPipelineDyn: AsAny {
+    fn get_shader_path(self: &Self) -> &str;
+    fn get_instances(self: &Self) -> Box<dyn Iterator<Item=(DrawletID, &NullDrawlet)> + '_>;
+    fn collect_released(self: &mut Self);
+}
+
+pub struct NullPipeline<RenObjType: NullRenderObject> {
+    shader_path: String,
+    drawlets: Registry<DrawletID, NullDrawlet>,
+    released: ReleaseQueue<DrawletID>,
+    _phantom_data: PhantomData<RenObjType>
+}
+
+impl<RenObjType: NullRenderObject> NullPipeline<RenObjType> {
+    fn new(shader_path: &str) -> Self {
+        Self {
+            shader_path: shader_path.to_owned(),
+            drawlets: Registry::new(),
+            released: ReleaseQueue::default(),
+            _phantom_data: PhantomData::default()
+        }
+    }
+}
+
+impl<RenObjType: NullRenderObject> RenderPipeline<RenObjType> for NullPipeline<RenObjType> {}
+
+impl<RenObjType: NullRenderObject> AsAny for NullPipeline<RenObjType> {
+    fn as_any(self: &Self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(self: &mut Self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl<RenObjType: NullRenderObject> NullPipelineDyn for NullPipeline<RenObjType> {
+    fn get_shader_path(self: &Self) -> &str {
+        self.shader_path.as_str()
+    }
+
+    fn get_instances(self: &Self) -> Box<dyn Iterator<Item=(DrawletID, &NullDrawlet)> + '_> {
+        Box::new(self.drawlets.iter())
+    }
+
+    fn collect_released(self: &mut Self) {
+        for id in self.released.take() {
+            self.drawlets.remove(id);
+        }
+    }
+}
+
+impl<RenObjType: NullRenderObject> PipelineTrait<RenObjType> for NullPipeline<RenObjType> {
+    fn create_drawlet(&mut self, init_data: RenObjType::Data) -> DrawletID {
+        self.drawlets.insert(RenObjType::new_drawlet(&init_data))
+    }
+
+    fn remove_drawlet(&mut self, id: DrawletID) {
+        let _ = self.drawlets.remove(id);
+    }
+
+    fn get_drawlet_mut(&mut self, id: DrawletID) -> Option<&mut RenObjType::DynDrawlet> {
+        self.drawlets.get_mut(id).map(RenObjType::upcast_drawlet)
+    }
+
+    fn get_drawlet_releases(self: &Self) -> ReleaseQueue<DrawletID> {
+        self.released.clone()
+    }
+}
+
+pub struct NullRenderPass {
+    pipelines: Registry<PipelineID, Box<dyn NullPipelineDyn>>,
+    released: ReleaseQueue<PipelineID>,
+    pipeline_factories: PipelineFactories<NullRenderPass>,
+}
+
+fn build_pipeline<RenObjType: NullRenderObject>(pass: &mut NullRenderPass, shader_path: &str, _shader_text: &str)
+    -> Result<(PipelineID, Box<dyn Any>), PipelineError>
+{
+    let pipeline_id = pass.pipelines.insert(Box::new(NullPipeline::<RenObjType>::new(shader_path)));
+    Ok(erase_pipeline::<RenObjType, NullPipeline<RenObjType>>(pipeline_id))
+}
+
+impl PassTrait for NullRenderPass {
+    fn create_erased_pipeline(&mut self, render_object: TypeId, shader_path: &str, shader_text: &str)
+        -> Result<(PipelineID, Box<dyn Any>), PipelineError>
+    {
+        self.pipeline_factories.clone().create_pipeline(self, render_object, shader_path, shader_text)
+    }
+
+    fn get_pipeline_mut(&mut self, id: PipelineID) -> Option<&mut dyn Any> {
+        self.pipelines.get_mut(id).map(|pipeline| pipeline.as_any_mut())
+    }
+
+    fn get_pipeline_releases(self: &Self) -> ReleaseQueue<PipelineID> {
+        self.released.clone()
+    }
+}
+
+impl NullRenderPass {
+    fn new(pipeline_factories: &PipelineFactories<NullRenderPass>) -> Self {
+        Self {
+            pipelines: Registry::new(),
+            released: ReleaseQueue::default(),
+            pipeline_factories: pipeline_factories.clone(),
+        }
+    }
+
+    fn collect_released(self: &mut Self) {
+        for id in self.released.take() {
+            self.pipelines.remove(id);
+        }
+        for pipeline in self.pipelines.values_mut() {
+            pipeline.collect_released();
+        }
+    }
+
+    fn record(self: &Self, pass_id: PassID, draw_calls: &mut Vec<NullDrawCall>) {
+        for (pipeline_id, pipeline) in self.pipelines.iter() {
+            for (drawlet_id, drawlet) in pipeline.get_instances() {
+                draw_calls.push(NullDrawCall {
+                    pass: pass_id,
+                    pipeline: pipeline_id,
+                    shader_path: pipeline.get_shader_path().to_owned(),
+                    drawlet: drawlet_id,
+                    num_indices: drawlet.index_buffer.get_count() as u32,
+                    num_vertices: (drawlet.vertex_count)() as u32,
+                    uniforms: drawlet.get_uniforms(),
+                    instances: drawlet.instances.as_slice().to_vec(),
+                    texture_size: drawlet.texture.as_ref()
+                        .map(|texture| (texture.get_width(), texture.get_height())),
+                });
+            }
+        }
+    }
+}
+
+pub struct NullBuffer<T> {
+    size: usize,
+    capacity: usize,
+    usage: BufferUsage,
+    _phantom_data: PhantomData<T>
+}
+
+impl<T: 'static> GpuBufferTrait<T> for NullBuffer<T> {
+    fn get_size_bytes(&self) -> usize {
+        self.size * size_of::<T>()
+    }
+    fn get_count(&self) -> usize {
+        self.size
+    }
+    fn get_capacity(&self) -> usize {
+        self.capacity
+    }
+    fn get_usage(&self) -> BufferUsage {
+        self.usage
+    }
+
+    fn write(&mut self, offset: usize, data: &[T]) {
+        assert!(offset + data.len() <= self.size,
+            "writing {} elements at {} overflows a buffer of {}", data.len(), offset, self.size);
+    }
+
+    fn resize(&mut self, count: usize) {
+        if count > self.capacity {
+            self.capacity = self.usage.grow_capacity(self.capacity, count);
+        }
+        self.size = count;
+    }
+}
+
+pub struct NullTexture {
+    width: u32,
+    height: u32,
+    format: TextureFormat,
+    mip_level_count: u32,
+}
+
+impl GpuTextureTrait for NullTexture {
+    fn get_width(&self) -> u32 {
+        self.width
+    }
+    fn get_height(&self) -> u32 {
+        self.height
+    }
+    fn get_format(&self) -> TextureFormat {
+        self.format
+    }
+    fn get_mip_level_count(&self) -> u32 {
+        self.mip_level_count
+    }
+
+    fn update_region(&mut self, _x: u32, _y: u32, _image: &image::RgbaImage) {}
+}
+
+pub struct NullRenderBackend {
+    width: u32,
+    height: u32,
+    frame_index: u64,
+    frames: Vec<NullFrame>,
+    render_passes: Arc<Mutex<Registry<PassID, NullRenderPass>>>,
+    released_passes: ReleaseQueue<PassID>,
+    pipeline_factories: PipelineFactories<NullRenderPass>,
+    egui_context: egui::Context,
+}
+
+impl NullRenderBackend {
+    pub fn new(width: u32, height: u32) -> Self {
+        let pipeline_factories = PipelineFactories::default();
+        pipeline_factories.register::<ColoredMesh>(build_pipeline::<ColoredMesh>);
+        pipeline_factories.register::<LitColoredMesh>(build_pipeline::<LitColoredMesh>);
+        pipeline_factories.register::<TexturedMesh>(build_pipeline::<TexturedMesh>);
+        pipeline_factories.register::<InstancedColoredMesh>(build_pipeline::<InstancedColoredMesh>);
+        pipeline_factories.register::<InstancedLitMesh>(build_pipeline::<InstancedLitMesh>);
+
+        Self {
+            width,
+            height,
+            frame_index: 0,
+            frames: Vec::new(),
+            render_passes: Arc::new(Mutex::new(Registry::new())),
+            released_passes: ReleaseQueue::default(),
+            pipeline_factories,
+            egui_context: egui::Context::default(),
+        }
+    }
+
+    // `PassHandle::create_pipeline::<RenObjType>` works for the render object afterwards
+    pub fn register_render_object<RenObjType: NullRenderObject>(self: &mut Self) {
+        self.pipeline_factories.register::<RenObjType>(build_pipeline::<RenObjType>);
+    }
+
+    // drops whatever lost its last handle since the previous frame
+    fn collect_released(self: &mut Self) {
+        let mut render_passes = self.render_passes.lock();
+        for id in self.released_passes.take() {
+            render_passes.remove(id);
+        }
+        for render_pass in render_passes.values_mut() {
+            render_pass.collect_released();
+        }
+    }
+
+    // same as `RenderBackend::render`, minus the window, so tests can step frames
+    pub fn render_frame(self: &mut Self, egui_show_obj: &mut dyn EguiUiShow) -> &NullFrame {
+        self.collect_released();
+
+        let mut draw_calls = Vec::new();
+        for (pass_id, render_pass) in self.render_passes.lock().iter() {
+            render_pass.record(pass_id, &mut draw_calls);
+        }
+
+        let _ = self.egui_context.run(egui::RawInput::default(), |ctx| egui_show_obj.show(ctx));
+
+        self.frames.push(NullFrame {
+            index: self.frame_index,
+            width: self.width,
+            height: self.height,
+            draw_calls,
+        });
+        self.frame_index += 1;
+
+        self.frames.last().unwrap()
+    }
+
+    pub fn get_frames(self: &Self) -> &[NullFrame] {
+        self.frames.as_slice()
+    }
+
+    pub fn get_last_frame(self: &Self) -> Option<&NullFrame> {
+        self.frames.last()
+    }
+
+    pub fn get_frame_index(self: &Self) -> u64 {
+        self.frame_index
+    }
+
+    pub fn clear_frames(self: &mut Self) {
+        self.frames.clear();
+    }
+}
+
+impl RenderBackend for NullRenderBackend {
+    const PERSPECTIVE_ALIGNMENT: [f32; 3] = [1f32, 1f32, -1f32];
+
+    fn init(backend_clone: Arc<Mutex<Option<Self>>>, window: Arc<Window>, _present_mode: PresentMode) where Self: Sized {
+        let size = window.inner_size();
+        backend_clone.lock().replace(NullRenderBackend::new(size.width, size.height));
+    }
+
+    fn render(self: &mut Self, _window: &Arc<Window>, egui_show_obj: &mut dyn EguiUiShow) {
+        self.render_frame(egui_show_obj);
+    }
+
+    fn process_event(self: &mut Self, _window: &Window, _event: &WindowEvent) {}
+
+    fn resize(self: &mut Self, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+    }
+
+    fn create_render_pass(self: &mut Self) -> PassHandle {
+        let id = self.render_passes.lock().insert(NullRenderPass::new(&self.pipeline_factories));
+
+        PassHandle::new(id, self.render_passes.clone(), &self.released_passes)
+    }
+
+    fn create_index_buffer_with_usage(self: &Self, data: &[u32], usage: BufferUsage) -> GpuBufferHandle<u32> {
+        self.create_vertex_buffer_with_usage(data, usage)
+    }
+
+    fn create_vertex_buffer_with_usage<T: Sized + 'static>(self: &Self, data: &[T], usage: BufferUsage) -> GpuBufferHandle<T> {
+        let buffer_own = rj::Own::new(
+            NullBuffer::<T> {
+                size: data.len(),
+                capacity: usage.initial_capacity(data.len()),
+                usage,
+                _phantom_data: PhantomData::default()
+            });
+
+        GpuBufferHandle::from_own(buffer_own.upcast())
+    }
+
+    fn create_texture_with_options(self: &Self, image: &DynamicImage, options: TextureOptions) -> GpuTextureHandle {
+        let texture = NullTexture {
+            width: image.width(),
+            height: image.height(),
+            format: options.format,
+            mip_level_count: options.mip_level_count.clamp(1, max_mip_level_count(image.width(), image.height())),
+        };
+
+        GpuTextureHandle::from_own(rj::Own::new(texture).upcast())
+    }
+
+    fn get_width(self: &Self) -> u32 {
+        self.width
+    }
+
+    fn get_height(self: &Self) -> u32 {
+        self.height
+    }
+
+    fn get_egui_renderer(self: &Self) -> Option<&EguiRenderer> {
+        None
+    }
+
+    fn get_egui_context(self: &Self) -> Option<egui::Context> {
+        None
+    }
+}
@@ -0,0 +1,155 @@
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+// only counts registries, the keys inside one are plain slot indices
+static NEXT_OWNER: AtomicU32 = AtomicU32::new(0);
+
+// a slot index plus the generation it was handed out in, a removed slot bumps its generation
+// so every key that still points at it reads as stale instead of reaching whatever moves in next.
+// `owner` names the registry that made the key, any other registry treats it as stale too
+#[derive(Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+pub struct GenerationalKey {
+    owner: u32,
+    index: u32,
+    generation: u32,
+}
+
+impl GenerationalKey {
+    pub fn owner(self: Self) -> u32 {
+        self.owner
+    }
+
+    pub fn index(self: Self) -> u32 {
+        self.index
+    }
+
+    pub fn generation(self: Self) -> u32 {
+        self.generation
+    }
+}
+
+pub trait RegistryKey: Copy {
+    fn from_key(key: GenerationalKey) -> Self;
+    fn key(self: Self) -> GenerationalKey;
+}
+
+struct Slot<V> {
+    generation: u32,
+    value: Option<V>,
+}
+
+// owns whatever it is given by value and only answers to keys it made itself. a backend keeps
+// its passes in one registry shared with the handles it gives out, passes keep their pipelines
+// and pipelines their drawlets, so drawing a frame takes that one lock and nothing else
+pub struct Registry<K: RegistryKey, V> {
+    owner: u32,
+    slots: Vec<Slot<V>>,
+    free: Vec<u32>,
+    len: usize,
+    _key: PhantomData<K>,
+}
+
+impl<K: RegistryKey, V> Default for Registry<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: RegistryKey, V> Registry<K, V> {
+    pub fn new() -> Self {
+        Self {
+            owner: NEXT_OWNER.fetch_add(1, Ordering::Relaxed),
+            slots: Vec::new(),
+            free: Vec::new(),
+            len: 0,
+            _key: PhantomData::default(),
+        }
+    }
+
+    pub fn insert(self: &mut Self, value: V) -> K {
+        self.len += 1;
+        if let Some(index) = self.free.pop() {
+            let slot = &mut self.slots[index as usize];
+            slot.value = Some(value);
+            return K::from_key(GenerationalKey { owner: self.owner, index, generation: slot.generation });
+        }
+
+        let index = self.slots.len() as u32;
+        self.slots.push(Slot { generation: 0, value: Some(value) });
+        K::from_key(GenerationalKey { owner: self.owner, index, generation: 0 })
+    }
+
+    fn get_slot_mut(self: &mut Self, key: GenerationalKey) -> Option<&mut Slot<V>> {
+        if key.owner != self.owner {
+            return None;
+        }
+        self.slots.get_mut(key.index as usize)
+            .filter(|slot| slot.generation == key.generation)
+    }
+
+    // `None` if the key is stale or from another registry
+    pub fn remove(self: &mut Self, key: K) -> Option<V> {
+        let key = key.key();
+        let slot = self.get_slot_mut(key)?;
+        let value = slot.value.take()?;
+
+        // a slot whose generation would wrap is retired instead of reused
+        slot.generation = slot.generation.wrapping_add(1);
+        if slot.generation != 0 {
+            self.free.push(key.index);
+        }
+        self.len -= 1;
+        Some(value)
+    }
+
+    pub fn get(self: &Self, key: K) -> Option<&V> {
+        let key = key.key();
+        if key.owner != self.owner {
+            return None;
+        }
+        self.slots.get(key.index as usize)
+            .filter(|slot| slot.generation == key.generation)
+            .and_then(|slot| slot.value.as_ref())
+    }
+
+    pub fn get_mut(self: &mut Self, key: K) -> Option<&mut V> {
+        self.get_slot_mut(key.key()).and_then(|slot| slot.value.as_mut())
+    }
+
+    pub fn contains(self: &Self, key: K) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn len(self: &Self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(self: &Self) -> bool {
+        self.len == 0
+    }
+
+    // in slot order, which is insertion order until slots get reused
+    pub fn iter(self: &Self) -> impl Iterator<Item = (K, &V)> {
+        let owner = self.owner;
+        self.slots.iter().enumerate().filter_map(move |(index, slot)| {
+            let key = GenerationalKey { owner, index: index as u32, generation: slot.generation };
+            slot.value.as_ref().map(|value| (K::from_key(key), value))
+        })
+    }
+
+    pub fn iter_mut(self: &mut Self) -> impl Iterator<Item = (K, &mut V)> {
+        let owner = self.owner;
+        self.slots.iter_mut().enumerate().filter_map(move |(index, slot)| {
+            let key = GenerationalKey { owner, index: index as u32, generation: slot.generation };
+            slot.value.as_mut().map(|value| (K::from_key(key), value))
+        })
+    }
+
+    pub fn values(self: &Self) -> impl Iterator<Item = &V> {
+        self.slots.iter().filter_map(|slot| slot.value.as_ref())
+    }
+
+    pub fn values_mut(self: &mut Self) -> impl Iterator<Item = &mut V> {
+        self.slots.iter_mut().filter_map(|slot| slot.value.as_mut())
+    }
+}
@@ -8,7 +8,7 @@ pub trait ColoredMeshDrawletTrait: DrawletTrait<ColoredMesh> {
 
 impl DrawletHandle<ColoredMesh> {
     pub fn set_mvp(self: &mut Self, mvp: cgmath::Matrix4<f32>) {
-        self.ptr.access().set_mvp(mvp);
+        self.with_drawlet(|drawlet| drawlet.set_mvp(mvp));
     }
 }
 #[repr(C)]
@@ -0,0 +1,234 @@
+use std::sync::Arc;
+use cgmath::{Matrix4, SquareMatrix, Vector4};
+use crate::render_backend::InstanceID;
+use crate::render_backend::registry::Registry;
+use crate::render_backend::render_interface::drawlets::{DrawletHandle, DrawletTrait};
+use crate::render_backend::render_interface::drawlets::colored_mesh::ColoredVertex;
+use crate::render_backend::render_interface::drawlets::lit_colored_mesh::NormalColoredVertex;
+use crate::render_backend::render_interface::{Mesh, RenderObject};
+
+// one copy of an instanced mesh, the tint multiplies the vertex color
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Instance {
+    pub model: Matrix4<f32>,
+    pub tint: Vector4<f32>,
+}
+
+impl Instance {
+    pub fn from_model(model: Matrix4<f32>) -> Self {
+        Self { model, tint: Vector4::new(1f32, 1f32, 1f32, 1f32) }
+    }
+}
+
+impl Default for Instance {
+    fn default() -> Self {
+        Self::from_model(Matrix4::identity())
+    }
+}
+
+// keeps the instances packed so a drawlet can upload them as one buffer,
+// removing one moves the last instance into its slot
+#[derive(Default)]
+pub struct InstanceList {
+    instances: Vec<Instance>,
+    ids: Vec<InstanceID>,
+    slots: Registry<InstanceID, usize>,
+}
+
+impl InstanceList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // the new instance is always the last one
+    pub fn add(self: &mut Self, instance: Instance) -> InstanceID {
+        let id = self.slots.insert(self.instances.len());
+        self.instances.push(instance);
+        self.ids.push(id);
+        id
+    }
+
+    // the index that changed, `None` if the id is stale
+    pub fn update(self: &mut Self, id: InstanceID, instance: Instance) -> Option<usize> {
+        let index = *self.slots.get(id)?;
+        self.instances[index] = instance;
+        Some(index)
+    }
+
+    // the index the last instance moved into, which equals `len()` if the removed one was last
+    pub fn remove(self: &mut Self, id: InstanceID) -> Option<usize> {
+        let index = self.slots.remove(id)?;
+        self.instances.swap_remove(index);
+        self.ids.swap_remove(index);
+        if let Some(moved) = self.ids.get(index) {
+            *self.slots.get_mut(*moved).unwrap() = index;
+        }
+        Some(index)
+    }
+
+    pub fn get(self: &Self, id: InstanceID) -> Option<&Instance> {
+        self.slots.get(id).map(|index| &self.instances[*index])
+    }
+
+    pub fn as_slice(self: &Self) -> &[Instance] {
+        &self.instances
+    }
+
+    pub fn len(self: &Self) -> usize {
+        self.instances.len()
+    }
+
+    pub fn is_empty(self: &Self) -> bool {
+        self.instances.is_empty()
+    }
+}
+
+// shared by every instanced render object, stale instance ids are ignored with a warning
+pub trait InstancedDrawletTrait {
+    fn add_instance(self: &mut Self, instance: Instance) -> InstanceID;
+    fn remove_instance(self: &mut Self, id: InstanceID);
+    fn update_instance(self: &mut Self, id: InstanceID, instance: Instance);
+    fn get_instance(self: &Self, id: InstanceID) -> Option<Instance>;
+    fn instance_count(self: &Self) -> usize;
+}
+
+impl<RenObjType: RenderObject> DrawletHandle<RenObjType> where RenObjType::DynDrawlet: InstancedDrawletTrait {
+    // `None` if the drawlet itself is gone
+    pub fn add_instance(self: &mut Self, instance: Instance) -> Option<InstanceID> {
+        self.with_drawlet(|drawlet| drawlet.add_instance(instance))
+    }
+    pub fn remove_instance(self: &mut Self, id: InstanceID) {
+        self.with_drawlet(|drawlet| drawlet.remove_instance(id));
+    }
+    pub fn update_instance(self: &mut Self, id: InstanceID, instance: Instance) {
+        self.with_drawlet(|drawlet| drawlet.update_instance(id, instance));
+    }
+    pub fn get_instance(self: &mut Self, id: InstanceID) -> Option<Instance> {
+        self.with_drawlet(|drawlet| drawlet.get_instance(id)).flatten()
+    }
+    pub fn instance_count(self: &mut Self) -> usize {
+        self.with_drawlet(|drawlet| drawlet.instance_count()).unwrap_or(0)
+    }
+}
+
+pub trait InstancedColoredMeshDrawletTrait: DrawletTrait<InstancedColoredMesh> + InstancedDrawletTrait {
+    fn set_view_proj(self: &mut Self, view_proj: Matrix4<f32>);
+}
+
+impl DrawletHandle<InstancedColoredMesh> {
+    pub fn set_view_proj(self: &mut Self, view_proj: Matrix4<f32>) {
+        self.with_drawlet(|drawlet| drawlet.set_view_proj(view_proj));
+    }
+}
+
+// every instance of a `ColoredMesh` in one draw call, the shader gets the model matrix
+// columns and the tint as per instance attributes after the vertex attributes
+pub struct InstancedColoredMesh {}
+impl RenderObject for InstancedColoredMesh {
+    type Data = InstancedColoredMeshData;
+    type DynDrawlet = dyn InstancedColoredMeshDrawletTrait;
+}
+// starts without instances
+pub struct InstancedColoredMeshData {
+    pub view_proj: Matrix4<f32>,
+    pub mesh: Arc<Mesh<ColoredVertex>>
+}
+
+pub trait InstancedLitMeshDrawletTrait: DrawletTrait<InstancedLitMesh> + InstancedDrawletTrait {
+    fn set_view_proj(self: &mut Self, view_proj: Matrix4<f32>);
+    fn set_light_dir(self: &mut Self, light_dir: cgmath::Vector3<f32>);
+    fn set_view_dir(self: &mut Self, view_dir: cgmath::Vector3<f32>);
+}
+
+impl DrawletHandle<InstancedLitMesh> {
+    pub fn set_view_proj(self: &mut Self, view_proj: Matrix4<f32>) {
+        self.with_drawlet(|drawlet| drawlet.set_view_proj(view_proj));
+    }
+    pub fn set_light_direction(self: &mut Self, light_dir: cgmath::Vector3<f32>) {
+        self.with_drawlet(|drawlet| drawlet.set_light_dir(light_dir));
+    }
+    pub fn set_view_direction(self: &mut Self, view_dir: cgmath::Vector3<f32>) {
+        self.with_drawlet(|drawlet| drawlet.set_view_dir(view_dir));
+    }
+}
+
+// the instanced version of `LitColoredMesh`, light and view directions are in world space
+pub struct InstancedLitMesh {}
+impl RenderObject for InstancedLitMesh {
+    type Data = InstancedLitMeshData;
+    type DynDrawlet = dyn InstancedLitMeshDrawletTrait;
+}
+pub struct InstancedLitMeshData {
+    pub view_proj: Matrix4<f32>,
+    pub light_dir: Vector4<f32>,
+    pub view_dir: Vector4<f32>,
+    pub mesh: Arc<Mesh<NormalColoredVertex>>
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(x: f32) -> Instance {
+        Instance::from_model(Matrix4::from_translation(cgmath::Vector3::new(x, 0f32, 0f32)))
+    }
+
+    fn xs(list: &InstanceList) -> Vec<f32> {
+        list.as_slice().iter().map(|instance| instance.model.w.x).collect()
+    }
+
+    #[test]
+    fn removing_the_last_instance_moves_nothing() {
+        let mut list = InstanceList::new();
+        let first = list.add(at(0f32));
+        let last = list.add(at(1f32));
+
+        assert_eq!(list.remove(last), Some(1));
+        assert_eq!(list.len(), 1);
+        assert_eq!(list.get(first), Some(&at(0f32)));
+
+        assert_eq!(list.remove(first), Some(0));
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn removing_from_the_middle_keeps_the_moved_id_valid() {
+        let mut list = InstanceList::new();
+        let ids: Vec<InstanceID> = (0..4).map(|x| list.add(at(x as f32))).collect();
+
+        // the last instance takes the removed one's slot
+        assert_eq!(list.remove(ids[1]), Some(1));
+        assert_eq!(xs(&list), vec![0f32, 3f32, 2f32]);
+        assert_eq!(list.get(ids[3]), Some(&at(3f32)));
+
+        assert_eq!(list.update(ids[3], at(30f32)), Some(1));
+        assert_eq!(xs(&list), vec![0f32, 30f32, 2f32]);
+
+        let added = list.add(at(4f32));
+        assert_eq!(list.remove(ids[0]), Some(0));
+        assert_eq!(xs(&list), vec![4f32, 30f32, 2f32]);
+        assert_eq!(list.update(added, at(40f32)), Some(0));
+        assert_eq!(list.get(ids[2]), Some(&at(2f32)));
+    }
+
+    #[test]
+    fn stale_ids_are_ignored() {
+        let mut list = InstanceList::new();
+        let removed = list.add(at(0f32));
+        list.remove(removed);
+        // reuses the removed slot with a new generation
+        let kept = list.add(at(1f32));
+
+        assert_eq!(list.remove(removed), None);
+        assert_eq!(list.update(removed, at(2f32)), None);
+        assert_eq!(list.get(removed), None);
+        assert_eq!(xs(&list), vec![1f32]);
+        assert_eq!(list.get(kept), Some(&at(1f32)));
+
+        // ids of another list never match
+        let mut other = InstanceList::new();
+        other.add(at(5f32));
+        assert_eq!(other.get(kept), None);
+        assert_eq!(other.remove(kept), None);
+    }
+}
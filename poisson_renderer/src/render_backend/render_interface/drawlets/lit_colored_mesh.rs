@@ -19,13 +19,13 @@ pub trait LitColoredMeshDrawletTrait: DrawletTrait<LitColoredMesh> {
 
 impl DrawletHandle<LitColoredMesh> {
     pub fn set_mvp(self: &mut Self, mvp: cgmath::Matrix4<f32>) {
-        self.ptr.access().set_mvp(mvp);
+        self.with_drawlet(|drawlet| drawlet.set_mvp(mvp));
     }
     pub fn set_light_direction(self: &mut Self, light_dir: cgmath::Vector3<f32>) {
-        self.ptr.access().set_light_dir(light_dir);
+        self.with_drawlet(|drawlet| drawlet.set_light_dir(light_dir));
     }
     pub fn set_view_direction(self: &mut Self, view_dir: cgmath::Vector3<f32>) {
-        self.ptr.access().set_view_dir(view_dir);
+        self.with_drawlet(|drawlet| drawlet.set_view_dir(view_dir));
     }
 }
 
@@ -1,70 +1,360 @@
 pub mod colored_mesh;
 pub mod textured_mesh;
 pub mod lit_colored_mesh;
+pub mod instanced_mesh;
+pub mod reflected_mesh;
 
-use crate::render_backend::render_interface::drawlets::colored_mesh::ColoredMesh;
-use crate::render_backend::render_interface::drawlets::textured_mesh::TexturedMesh;
 use crate::render_backend::render_interface::RenderObject;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use parking_lot::Mutex;
 use crate::render_backend::{DrawletID, PassID, PipelineID};
-use crate::render_backend::render_interface::drawlets::lit_colored_mesh::LitColoredMesh;
+use crate::render_backend::registry::Registry;
+use crate::render_backend::render_interface::drawlets::reflected_mesh::ReflectionError;
 
 pub trait DrawletTrait<RenObjType: RenderObject> {}
 
 
-pub trait CreatePipeline<T>  where T: RenderObject {
-    fn create_pipeline(&mut self, shader_path: &str, shader_text: &str)
-        -> (PipelineID, rj::Own<(dyn PipelineTrait<T> + 'static)>);
+// ids of handles that were dropped, whoever owns the objects removes them before drawing the next frame
+pub struct ReleaseQueue<Id> {
+    ids: Arc<Mutex<Vec<Id>>>,
 }
 
-pub trait PassTrait: std::any::Any +
-    CreatePipeline<TexturedMesh> +
-    CreatePipeline<ColoredMesh> +
-    CreatePipeline<LitColoredMesh>
-{}
+impl<Id> Clone for ReleaseQueue<Id> {
+    fn clone(&self) -> Self {
+        Self { ids: self.ids.clone() }
+    }
+}
+
+impl<Id> Default for ReleaseQueue<Id> {
+    fn default() -> Self {
+        Self { ids: Arc::new(Mutex::new(Vec::new())) }
+    }
+}
+
+impl<Id> ReleaseQueue<Id> {
+    // pipelines drain this before drawing and remove the drawlets it returns
+    pub fn take(self: &Self) -> Vec<Id> {
+        std::mem::take(&mut *self.ids.lock())
+    }
+}
+
+// shared by every clone of a handle, queues the id once the last clone is gone
+struct HandleGuard<Id: Copy> {
+    id: Id,
+    releases: ReleaseQueue<Id>,
+    detached: AtomicBool,
+}
+
+impl<Id: Copy> HandleGuard<Id> {
+    fn new(id: Id, releases: ReleaseQueue<Id>) -> Arc<Self> {
+        Arc::new(Self { id, releases, detached: AtomicBool::new(false) })
+    }
+}
+
+impl<Id: Copy> Drop for HandleGuard<Id> {
+    fn drop(&mut self) {
+        if !self.detached.load(Ordering::Relaxed) {
+            self.releases.ids.lock().push(self.id);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PipelineError {
+    // the backend that created the pass has no factory for the render object
+    NotRegistered,
+    Reflection(ReflectionError),
+}
+
+impl Display for PipelineError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PipelineError::NotRegistered => write!(f, "no pipeline is registered for the render object"),
+            PipelineError::Reflection(err) => write!(f, "shader doesn't fit the render object: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for PipelineError {}
+
+impl From<ReflectionError> for PipelineError {
+    fn from(err: ReflectionError) -> Self {
+        PipelineError::Reflection(err)
+    }
+}
+
+// builds the pipeline of one render object type in a concrete pass,
+// the box holds the `PipelineAccess<T>` for that type
+pub type PipelineFactory<Pass> = fn(&mut Pass, &str, &str) -> Result<(PipelineID, Box<dyn Any>), PipelineError>;
+
+// the render object types a backend can draw, shared by the backend and every pass it creates
+pub struct PipelineFactories<Pass> {
+    factories: Arc<Mutex<HashMap<TypeId, PipelineFactory<Pass>>>>,
+}
+
+impl<Pass> Clone for PipelineFactories<Pass> {
+    fn clone(&self) -> Self {
+        Self { factories: self.factories.clone() }
+    }
+}
+
+impl<Pass> Default for PipelineFactories<Pass> {
+    fn default() -> Self {
+        Self { factories: Arc::new(Mutex::new(HashMap::new())) }
+    }
+}
+
+impl<Pass> PipelineFactories<Pass> {
+    // registering a type again replaces its factory, passes created earlier pick it up too
+    pub(crate) fn register<T: RenderObject + 'static>(self: &Self, factory: PipelineFactory<Pass>) {
+        self.factories.lock().insert(TypeId::of::<T>(), factory);
+    }
 
+    pub(crate) fn create_pipeline(self: &Self, pass: &mut Pass, render_object: TypeId, shader_path: &str, shader_text: &str)
+        -> Result<(PipelineID, Box<dyn Any>), PipelineError>
+    {
+        let factory = *self.factories.lock().get(&render_object).ok_or(PipelineError::NotRegistered)?;
+        factory(pass, shader_path, shader_text)
+    }
+}
+
+// passes store their pipelines type-erased, this turns one back into the
+// pipeline trait of the render object it was created for
+pub type PipelineAccess<RenObjType> = fn(&mut dyn Any) -> &mut dyn PipelineTrait<RenObjType>;
+
+fn access_pipeline<RenObjType, Pipeline>(pipeline: &mut dyn Any) -> &mut dyn PipelineTrait<RenObjType>
+where RenObjType: RenderObject, Pipeline: PipelineTrait<RenObjType> + 'static
+{
+    pipeline.downcast_mut::<Pipeline>().expect("pipeline id reached a pipeline of another render object")
+}
+
+// what pipeline factories return for a pipeline they just stored in their pass
+pub(crate) fn erase_pipeline<RenObjType, Pipeline>(id: PipelineID) -> (PipelineID, Box<dyn Any>)
+where RenObjType: RenderObject + 'static, Pipeline: PipelineTrait<RenObjType> + 'static
+{
+    let access: PipelineAccess<RenObjType> = access_pipeline::<RenObjType, Pipeline>;
+    (id, Box::new(access))
+}
+
+pub trait PassTrait: Any {
+    fn create_erased_pipeline(&mut self, render_object: TypeId, shader_path: &str, shader_text: &str)
+        -> Result<(PipelineID, Box<dyn Any>), PipelineError>;
+    // `None` for ids whose pipeline was already removed, or that belong to another pass
+    fn get_pipeline_mut(&mut self, id: PipelineID) -> Option<&mut dyn Any>;
+    fn get_pipeline_releases(self: &Self) -> ReleaseQueue<PipelineID>;
+}
+
+// the registry a backend keeps its passes in, every handle reaches its object through it
+pub trait PassStore {
+    fn get_pass_mut(&mut self, id: PassID) -> Option<&mut dyn PassTrait>;
+}
+
+impl<Pass: PassTrait> PassStore for Registry<PassID, Pass> {
+    fn get_pass_mut(&mut self, id: PassID) -> Option<&mut dyn PassTrait> {
+        self.get_mut(id).map(|pass| pass as &mut dyn PassTrait)
+    }
+}
+
+pub type SharedPasses = Arc<Mutex<dyn PassStore>>;
+
+// dropping the last clone removes the pass, and everything drawn in it, before the next frame
 pub struct PassHandle {
     pub(crate) id: PassID,
-    pub(crate) ptr: rj::Own<dyn PassTrait>
+    passes: SharedPasses,
+    guard: Arc<HandleGuard<PassID>>,
+}
+
+impl Clone for PassHandle {
+    fn clone(&self) -> Self {
+        Self { id: self.id, passes: self.passes.clone(), guard: self.guard.clone() }
+    }
 }
 
 impl PassHandle {
-    pub fn create_pipeline<T: RenderObject>(&mut self, shader_path: &str, shader_text: &str)
+    pub(crate) fn new(id: PassID, passes: SharedPasses, releases: &ReleaseQueue<PassID>) -> Self {
+        Self { id, passes, guard: HandleGuard::new(id, releases.clone()) }
+    }
+
+    // panics if `T` was never registered with the backend that created this pass,
+    // or if the shader doesn't fit it
+    pub fn create_pipeline<T: RenderObject + 'static>(&mut self, shader_path: &str, shader_text: &str)
         -> PipelineHandle<T>
-        where (dyn PassTrait + 'static): CreatePipeline<T>
     {
-        let (id, pipe) = self.ptr.access().create_pipeline(shader_path, shader_text);
-        PipelineHandle {
+        self.try_create_pipeline(shader_path, shader_text)
+            .unwrap_or_else(|err| panic!("failed to create pipeline for {}: {err}", std::any::type_name::<T>()))
+    }
+
+    pub fn try_create_pipeline<T: RenderObject + 'static>(&mut self, shader_path: &str, shader_text: &str)
+        -> Result<PipelineHandle<T>, PipelineError>
+    {
+        let mut passes = self.passes.lock();
+        // the pass outlives every clone of its handle
+        let pass = passes.get_pass_mut(self.id).expect("pass was removed while a handle to it was alive");
+        let (id, access) = pass.create_erased_pipeline(TypeId::of::<T>(), shader_path, shader_text)?;
+        let access = *access.downcast::<PipelineAccess<T>>()
+            .expect("pipeline factory returned a pipeline for another render object");
+        let releases = pass.get_pipeline_releases();
+        Ok(PipelineHandle {
             id,
-            ptr: pipe
-        }
+            pass: self.id,
+            passes: self.passes.clone(),
+            access,
+            guard: HandleGuard::new(id, releases),
+        })
+    }
+
+    pub fn id(self: &Self) -> PassID {
+        self.id
+    }
+
+    // keeps the pass alive for as long as the renderer, even with no handle left
+    pub fn detach(self: Self) {
+        self.guard.detached.store(true, Ordering::Relaxed);
     }
 }
 
 pub trait PipelineTrait<RenObjType: RenderObject> {
-    fn create_drawlet(&mut self, init_data: RenObjType::Data) -> (DrawletID, rj::Own<RenObjType::DynDrawlet>);
-    fn remove_drawlet(&mut self, drawlet: DrawletHandle<RenObjType>);
+    fn create_drawlet(&mut self, init_data: RenObjType::Data) -> DrawletID;
+    fn remove_drawlet(&mut self, id: DrawletID);
+    // `None` for ids whose drawlet was already removed
+    fn get_drawlet_mut(&mut self, id: DrawletID) -> Option<&mut RenObjType::DynDrawlet>;
+    fn get_drawlet_releases(self: &Self) -> ReleaseQueue<DrawletID>;
+}
+
+// reaches a pipeline through the pass registry, `None` once its pass or the pipeline itself is gone
+fn with_pipeline<RenObjType: RenderObject, R>(
+    passes: &SharedPasses,
+    pass: PassID,
+    pipeline: PipelineID,
+    access: PipelineAccess<RenObjType>,
+    f: impl FnOnce(&mut dyn PipelineTrait<RenObjType>) -> R,
+) -> Option<R> {
+    let mut passes = passes.lock();
+    let pipeline = passes.get_pass_mut(pass)?.get_pipeline_mut(pipeline)?;
+    Some(f(access(pipeline)))
 }
+
+// dropping the last clone removes the pipeline and its drawlets before the next frame
 pub struct PipelineHandle<RenObjType: RenderObject> {
     id: PipelineID,
-    ptr: rj::Own<dyn PipelineTrait<RenObjType>>
+    pass: PassID,
+    passes: SharedPasses,
+    access: PipelineAccess<RenObjType>,
+    guard: Arc<HandleGuard<PipelineID>>,
+}
+
+impl<RenObjType: RenderObject> Clone for PipelineHandle<RenObjType> {
+    fn clone(&self) -> Self {
+        Self { id: self.id, pass: self.pass, passes: self.passes.clone(), access: self.access, guard: self.guard.clone() }
+    }
 }
 
 impl<RenObjType: RenderObject> PipelineHandle<RenObjType> {
+    fn with_pipeline<R>(self: &Self, f: impl FnOnce(&mut dyn PipelineTrait<RenObjType>) -> R) -> Option<R> {
+        with_pipeline(&self.passes, self.pass, self.id, self.access, f)
+    }
+
+    // panics if the pass this pipeline was created in was dropped
     pub fn create_drawlet(&mut self, init_data: RenObjType::Data) -> DrawletHandle<RenObjType> {
-        let (id, ptr_drawlet) = self.ptr.access().create_drawlet(init_data);
+        let (id, releases) = self.with_pipeline(|pipeline| {
+            (pipeline.create_drawlet(init_data), pipeline.get_drawlet_releases())
+        }).expect("pipeline's pass was dropped");
         DrawletHandle::<RenObjType> {
             id,
-            ptr: ptr_drawlet,
+            pass: self.pass,
+            pipeline: self.id,
+            passes: self.passes.clone(),
+            access: self.access,
+            guard: HandleGuard::new(id, releases),
         }
     }
 
+    // removes the drawlet right away instead of before the next frame, other clones of it go stale
     pub fn remove_drawlet(&mut self, drawlet: DrawletHandle<RenObjType>) {
-        self.ptr.access().remove_drawlet(drawlet);
+        self.remove_drawlet_by_id(drawlet.id);
+    }
+
+    // ids of drawlets in other pipelines are never contained
+    pub fn contains_drawlet(self: &Self, id: DrawletID) -> bool {
+        self.with_pipeline(|pipeline| pipeline.get_drawlet_mut(id).is_some()).unwrap_or(false)
+    }
+
+    // reaches a drawlet by its copyable id, e.g. one kept after `DrawletHandle::detach`,
+    // `None` if it was removed or belongs to another pipeline
+    pub fn with_drawlet<R>(self: &mut Self, id: DrawletID, f: impl FnOnce(&mut RenObjType::DynDrawlet) -> R) -> Option<R> {
+        self.with_pipeline(|pipeline| pipeline.get_drawlet_mut(id).map(f)).flatten()
+    }
+
+    // like `remove_drawlet` for a drawlet that is only known by its id
+    pub fn remove_drawlet_by_id(&mut self, id: DrawletID) {
+        self.with_pipeline(|pipeline| pipeline.remove_drawlet(id));
+    }
+
+    pub fn id(self: &Self) -> PipelineID {
+        self.id
+    }
+
+    pub fn detach(self: Self) {
+        self.guard.detached.store(true, Ordering::Relaxed);
     }
 }
 
+// dropping the last clone removes the drawlet from its pipeline before the next frame
 pub struct DrawletHandle<RenObjType: RenderObject> {
     pub(crate) id: DrawletID,
-    pub(crate) ptr: rj::Own<RenObjType::DynDrawlet>
+    pass: PassID,
+    pipeline: PipelineID,
+    passes: SharedPasses,
+    access: PipelineAccess<RenObjType>,
+    guard: Arc<HandleGuard<DrawletID>>,
+}
+
+impl<RenObjType: RenderObject> Clone for DrawletHandle<RenObjType> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            pass: self.pass,
+            pipeline: self.pipeline,
+            passes: self.passes.clone(),
+            access: self.access,
+            guard: self.guard.clone(),
+        }
+    }
 }
 
+impl<RenObjType: RenderObject> DrawletHandle<RenObjType> {
+    // a copyable key for the drawlet, check it with `PipelineHandle::contains_drawlet`
+    pub fn id(self: &Self) -> DrawletID {
+        self.id
+    }
+
+    // false once the drawlet was removed through `PipelineHandle::remove_drawlet`, or its pipeline went away
+    pub fn is_alive(self: &Self) -> bool {
+        with_pipeline(&self.passes, self.pass, self.pipeline, self.access, |pipeline| pipeline.get_drawlet_mut(self.id).is_some())
+            .unwrap_or(false)
+    }
+
+    // stale handles are ignored with a warning instead of touching another drawlet,
+    // setters for a render object's drawlet trait go through here
+    pub fn with_drawlet<R>(self: &mut Self, f: impl FnOnce(&mut RenObjType::DynDrawlet) -> R) -> Option<R> {
+        let id = self.id;
+        match with_pipeline(&self.passes, self.pass, self.pipeline, self.access, |pipeline| pipeline.get_drawlet_mut(id).map(f)).flatten() {
+            Some(ret) => Some(ret),
+            None => {
+                log::warn!("ignoring stale drawlet handle {:?}", self.id);
+                None
+            }
+        }
+    }
+
+    // for fire-and-forget objects, it keeps drawing until its pipeline goes away.
+    // the returned id still reaches it through `PipelineHandle::with_drawlet`
+    pub fn detach(self: Self) -> DrawletID {
+        self.guard.detached.store(true, Ordering::Relaxed);
+        self.id
+    }
+}
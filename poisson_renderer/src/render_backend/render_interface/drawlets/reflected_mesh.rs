@@ -0,0 +1,177 @@
+use std::fmt::{Display, Formatter};
+use std::marker::PhantomData;
+use std::sync::Arc;
+use crate::render_backend::render_interface::drawlets::{DrawletHandle, DrawletTrait};
+use crate::render_backend::render_interface::drawlets::colored_mesh::ColoredVertex;
+use crate::render_backend::render_interface::drawlets::lit_colored_mesh::NormalColoredVertex;
+use crate::render_backend::render_interface::{Mesh, RenderObject};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttributeFormat {
+    Float,
+    Float2,
+    Float3,
+    Float4,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct VertexAttribute {
+    pub location: u32,
+    pub offset: usize,
+    pub format: AttributeFormat,
+}
+
+// checked against the shader's vertex input when the pipeline is created
+pub trait ReflectedVertex: Copy + 'static {
+    const ATTRIBUTES: &'static [VertexAttribute];
+}
+
+impl ReflectedVertex for ColoredVertex {
+    const ATTRIBUTES: &'static [VertexAttribute] = &[
+        VertexAttribute { location: 0, offset: 0, format: AttributeFormat::Float3 },
+        VertexAttribute { location: 1, offset: size_of::<[f32; 3]>(), format: AttributeFormat::Float3 },
+    ];
+}
+
+impl ReflectedVertex for NormalColoredVertex {
+    const ATTRIBUTES: &'static [VertexAttribute] = &[
+        VertexAttribute { location: 0, offset: 0, format: AttributeFormat::Float3 },
+        VertexAttribute { location: 1, offset: size_of::<[f32; 3]>(), format: AttributeFormat::Float4 },
+        VertexAttribute { location: 2, offset: size_of::<[f32; 7]>(), format: AttributeFormat::Float3 },
+    ];
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum UniformValue {
+    Float(f32),
+    Float2(cgmath::Vector2<f32>),
+    Float3(cgmath::Vector3<f32>),
+    Float4(cgmath::Vector4<f32>),
+    Float4x4(cgmath::Matrix4<f32>),
+}
+
+impl UniformValue {
+    pub fn type_name(self: &Self) -> &'static str {
+        match self {
+            UniformValue::Float(_) => "float",
+            UniformValue::Float2(_) => "float2",
+            UniformValue::Float3(_) => "float3",
+            UniformValue::Float4(_) => "float4",
+            UniformValue::Float4x4(_) => "float4x4",
+        }
+    }
+
+    // the floats as they are laid out in a uniform buffer, matrices are column major
+    pub fn as_floats(self: &Self) -> &[f32] {
+        match self {
+            UniformValue::Float(value) => std::slice::from_ref(value),
+            UniformValue::Float2(value) => AsRef::<[f32; 2]>::as_ref(value),
+            UniformValue::Float3(value) => AsRef::<[f32; 3]>::as_ref(value),
+            UniformValue::Float4(value) => AsRef::<[f32; 4]>::as_ref(value),
+            UniformValue::Float4x4(value) => AsRef::<[f32; 16]>::as_ref(value),
+        }
+    }
+}
+
+impl From<f32> for UniformValue {
+    fn from(value: f32) -> Self {
+        UniformValue::Float(value)
+    }
+}
+
+impl From<cgmath::Vector2<f32>> for UniformValue {
+    fn from(value: cgmath::Vector2<f32>) -> Self {
+        UniformValue::Float2(value)
+    }
+}
+
+impl From<cgmath::Vector3<f32>> for UniformValue {
+    fn from(value: cgmath::Vector3<f32>) -> Self {
+        UniformValue::Float3(value)
+    }
+}
+
+impl From<cgmath::Vector4<f32>> for UniformValue {
+    fn from(value: cgmath::Vector4<f32>) -> Self {
+        UniformValue::Float4(value)
+    }
+}
+
+impl From<cgmath::Matrix4<f32>> for UniformValue {
+    fn from(value: cgmath::Matrix4<f32>) -> Self {
+        UniformValue::Float4x4(value)
+    }
+}
+
+#[derive(Debug)]
+pub enum ReflectionError {
+    // the shader has no vertex entry point, or it doesn't take an `AssembledVertex`
+    MissingVertexInput,
+    MissingVertexAttribute { name: String, location: u32 },
+    VertexMismatch { name: String, location: u32, shader: String, vertex: AttributeFormat },
+    UnsupportedUniformType { name: String, shader: String },
+    // a global that isn't a ConstantBuffer or ParameterBlock of a struct, e.g. a texture or sampler
+    UnsupportedParameter(String),
+    // two blocks reflected at the same binding of one bind group
+    SharedBinding { name: String, group: u32, binding: u32 },
+    // uniforms are set by field name, so it has to be unique across parameter blocks
+    DuplicateUniform(String),
+    UnknownUniform(String),
+    UniformTypeMismatch { name: String, shader: &'static str, value: &'static str },
+}
+
+impl Display for ReflectionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReflectionError::MissingVertexInput =>
+                write!(f, "shader has no vertex entry point taking an AssembledVertex"),
+            ReflectionError::MissingVertexAttribute { name, location } =>
+                write!(f, "vertex type has no attribute at location {location} for `{name}`"),
+            ReflectionError::VertexMismatch { name, location, shader, vertex } =>
+                write!(f, "`{name}` at location {location} is {shader} in the shader but {vertex:?} in the vertex type"),
+            ReflectionError::UnsupportedUniformType { name, shader } =>
+                write!(f, "uniform `{name}` has unsupported type {shader}"),
+            ReflectionError::UnsupportedParameter(name) =>
+                write!(f, "shader parameter `{name}` isn't a block of uniforms, textures and samplers aren't reflected"),
+            ReflectionError::SharedBinding { name, group, binding } =>
+                write!(f, "`{name}` is reflected at binding {binding} of group {group}, which another block already uses"),
+            ReflectionError::DuplicateUniform(name) =>
+                write!(f, "uniform `{name}` appears in more than one parameter block"),
+            ReflectionError::UnknownUniform(name) =>
+                write!(f, "shader has no uniform named `{name}`"),
+            ReflectionError::UniformTypeMismatch { name, shader, value } =>
+                write!(f, "uniform `{name}` is {shader} in the shader but was set with a {value}"),
+        }
+    }
+}
+
+impl std::error::Error for ReflectionError {}
+
+pub trait ReflectedMeshDrawletTrait<V: ReflectedVertex>: DrawletTrait<ReflectedMesh<V>> {
+    fn set_uniform(self: &mut Self, name: &str, value: UniformValue) -> Result<(), ReflectionError>;
+}
+
+impl<V: ReflectedVertex> DrawletHandle<ReflectedMesh<V>> {
+    // `name` is the field name inside the shader's parameter block, e.g. "mvp"
+    pub fn set_uniform(self: &mut Self, name: &str, value: impl Into<UniformValue>) -> Result<(), ReflectionError> {
+        self.with_drawlet(|drawlet| drawlet.set_uniform(name, value.into()))
+            .unwrap_or(Ok(()))
+    }
+}
+
+// a mesh drawn with whatever shader its pipeline was created from,
+// the bind groups and vertex layout come from the shader's reflection.
+// only ConstantBuffers and ParameterBlocks of float, vector and matrix uniforms are
+// reflected, shaders with textures or samplers fail with `UnsupportedParameter`,
+// textured meshes still go through `TexturedMesh`
+pub struct ReflectedMesh<V: ReflectedVertex> {
+    _phantom_data: PhantomData<V>,
+}
+impl<V: ReflectedVertex> RenderObject for ReflectedMesh<V> {
+    type Data = ReflectedMeshData<V>;
+    type DynDrawlet = dyn ReflectedMeshDrawletTrait<V>;
+}
+// uniforms start zeroed until they're set through the handle
+pub struct ReflectedMeshData<V> {
+    pub mesh: Arc<Mesh<V>>
+}
@@ -1,15 +1,20 @@
 use std::sync::Arc;
-use image::DynamicImage;
 use crate::render_backend::render_interface::drawlets::{DrawletHandle, DrawletTrait};
+use crate::render_backend::render_interface::resources::GpuTextureHandle;
 use crate::render_backend::render_interface::{Mesh, RenderObject};
 
 pub trait TexturedMeshDrawletTrait: DrawletTrait<TexturedMesh> {
     fn set_mvp(self: &mut Self, mvp: cgmath::Matrix4<f32>);
+    fn set_texture(self: &mut Self, texture: &GpuTextureHandle);
 }
 
 impl DrawletHandle<TexturedMesh> {
     pub fn set_mvp(self: &mut Self, mvp: cgmath::Matrix4<f32>) {
-        self.ptr.access().set_mvp(mvp);
+        self.with_drawlet(|drawlet| drawlet.set_mvp(mvp));
+    }
+    // the texture must come from the same backend as the drawlet
+    pub fn set_texture(self: &mut Self, texture: &GpuTextureHandle) {
+        self.with_drawlet(|drawlet| drawlet.set_texture(texture));
     }
 }
 
@@ -27,5 +32,6 @@ impl RenderObject for TexturedMesh {
 pub struct TexturedMeshData {
     pub mvp_data: cgmath::Matrix4<f32>,
     pub mesh: Arc<Mesh<UvVertex>>,
-    pub texture_data: DynamicImage
+    // from `RenderBackend::create_texture`, any number of drawlets can share it
+    pub texture: GpuTextureHandle
 }
\ No newline at end of file
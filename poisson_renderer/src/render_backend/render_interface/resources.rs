@@ -2,14 +2,63 @@
 
 use std::any::Any;
 
+// how often a buffer's contents are expected to change, backends use it to pick
+// how much room to leave when a buffer has to grow
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BufferUsage {
+    // written once, grows to exactly the requested size
+    #[default]
+    Static,
+    // rewritten now and then, doubles its capacity when it grows
+    Dynamic,
+    // rewritten every frame, starts at a power of two and doubles like `Dynamic`,
+    // growing doesn't keep the old contents since they're about to be replaced
+    Stream,
+}
+
+impl BufferUsage {
+    pub fn initial_capacity(self: Self, count: usize) -> usize {
+        match self {
+            BufferUsage::Static | BufferUsage::Dynamic => count,
+            BufferUsage::Stream => count.next_power_of_two(),
+        }
+    }
+
+    pub fn grow_capacity(self: Self, capacity: usize, count: usize) -> usize {
+        match self {
+            BufferUsage::Static => count,
+            BufferUsage::Dynamic | BufferUsage::Stream => count.max(capacity * 2),
+        }
+    }
+
+    pub fn keeps_contents_on_grow(self: Self) -> bool {
+        self != BufferUsage::Stream
+    }
+}
+
+// counts and offsets are in elements, not bytes
 pub trait GpuBufferTrait<T>: Any {
     fn get_size_bytes(&self) -> usize;
     fn get_count(&self) -> usize;
+    fn get_capacity(&self) -> usize;
+    fn get_usage(&self) -> BufferUsage;
+    // overwrites `data.len()` elements starting at `offset`, panics past `get_count`
+    fn write(&mut self, offset: usize, data: &[T]);
+    // reallocates when `count` is past the capacity, new elements are zeroed
+    fn resize(&mut self, count: usize);
 }
 
-
+// cloning shares the buffer, drawlets created from a mesh keep seeing its
+// writes and resizes without being recreated
 pub struct GpuBufferHandle<T> {
-    pub(crate) buffer: rj::Own<dyn GpuBufferTrait<T>>
+    pub buffer: rj::Own<dyn GpuBufferTrait<T>>
+}
+impl<T> Clone for GpuBufferHandle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            buffer: self.buffer.clone()
+        }
+    }
 }
 impl<T: 'static> GpuBufferHandle<T> {
     pub fn from_own(buffer: rj::Own<dyn GpuBufferTrait<T>>) -> Self {
@@ -23,12 +72,114 @@ impl<T: 'static> GpuBufferHandle<T> {
     pub fn get_count(&self) -> usize {
         self.buffer.access().get_count()
     }
+    pub fn get_capacity(&self) -> usize {
+        self.buffer.access().get_capacity()
+    }
+    pub fn get_usage(&self) -> BufferUsage {
+        self.buffer.access().get_usage()
+    }
+    pub fn write(&self, offset: usize, data: &[T]) {
+        self.buffer.access().write(offset, data)
+    }
+    pub fn resize(&self, count: usize) {
+        self.buffer.access().resize(count)
+    }
+    // replaces the whole contents, growing or shrinking the buffer to fit
+    pub fn set_data(&self, data: &[T]) {
+        let mut buffer = self.buffer.access();
+        buffer.resize(data.len());
+        buffer.write(0, data);
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TextureFormat {
+    // color images, sampling converts to linear
+    #[default]
+    Rgba8UnormSrgb,
+    // data that isn't a color, e.g. height or normal maps
+    Rgba8Unorm,
 }
 
-pub trait GpuTextureTrait: Any {}
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TextureOptions {
+    pub format: TextureFormat,
+    // the levels below the first are downscaled from the image, clamped to the full chain
+    pub mip_level_count: u32,
+}
+
+impl Default for TextureOptions {
+    fn default() -> Self {
+        Self {
+            format: TextureFormat::default(),
+            mip_level_count: 1,
+        }
+    }
+}
+
+impl TextureOptions {
+    pub fn with_full_mip_chain(format: TextureFormat) -> Self {
+        Self {
+            format,
+            mip_level_count: u32::MAX,
+        }
+    }
+}
+
+pub fn max_mip_level_count(width: u32, height: u32) -> u32 {
+    32 - width.max(height).max(1).leading_zeros()
+}
+
+// each mip level of `image`, starting with the image itself
+pub fn build_mip_chain(image: &image::RgbaImage, mip_level_count: u32) -> Vec<image::RgbaImage> {
+    let mut levels = vec![image.clone()];
+    for level in 1..mip_level_count {
+        let width = (image.width() >> level).max(1);
+        let height = (image.height() >> level).max(1);
+        levels.push(image::imageops::resize(image, width, height, image::imageops::FilterType::Triangle));
+    }
+    levels
+}
+
+pub trait GpuTextureTrait: Any {
+    fn get_width(&self) -> u32;
+    fn get_height(&self) -> u32;
+    fn get_format(&self) -> TextureFormat;
+    fn get_mip_level_count(&self) -> u32;
+    // replaces the texels at (`x`, `y`) with `image`, the lower mip levels
+    // are rebuilt from the region alone
+    fn update_region(&mut self, x: u32, y: u32, image: &image::RgbaImage);
+}
+
+// cloning shares the texture, so one upload can back any number of drawlets
+#[derive(Clone)]
 pub struct GpuTextureHandle {
-    texture: rj::Own<dyn GpuTextureTrait>
+    pub texture: rj::Own<dyn GpuTextureTrait>
 }
 impl GpuTextureHandle {
-
+    pub fn from_own(texture: rj::Own<dyn GpuTextureTrait>) -> Self {
+        Self {
+            texture
+        }
+    }
+    pub fn get_width(&self) -> u32 {
+        self.texture.access().get_width()
+    }
+    pub fn get_height(&self) -> u32 {
+        self.texture.access().get_height()
+    }
+    pub fn get_format(&self) -> TextureFormat {
+        self.texture.access().get_format()
+    }
+    pub fn get_mip_level_count(&self) -> u32 {
+        self.texture.access().get_mip_level_count()
+    }
+    // panics if the region doesn't fit inside the texture
+    pub fn update_region(&self, x: u32, y: u32, image: &image::RgbaImage) {
+        let mut texture = self.texture.access();
+        assert!(x + image.width() <= texture.get_width() && y + image.height() <= texture.get_height(),
+            "a {}x{} region at ({}, {}) overflows a {}x{} texture",
+            image.width(), image.height(), x, y, texture.get_width(), texture.get_height());
+        texture.update_region(x, y, image)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,61 @@
+use cgmath::{Matrix4, Vector4};
+use crate::render_backend::render_interface::drawlets::DrawletTrait;
+use crate::render_backend::render_interface::drawlets::colored_mesh::{ColoredMesh, ColoredMeshData, ColoredMeshDrawletTrait, ColoredVertex};
+use crate::render_backend::software::{SoftwareBuffer, SoftwareDrawlet, SoftwarePipelineConfig, SoftwareRenderObject};
+use crate::render_backend::software::raster::SoftwareFramebuffer;
+
+impl SoftwareRenderObject for ColoredMesh {
+    type Drawlet = ColoredMeshDrawlet;
+
+    fn new_drawlet(init_data: ColoredMeshData, _config: &SoftwarePipelineConfig) -> ColoredMeshDrawlet {
+        ColoredMeshDrawlet::new(&init_data)
+    }
+
+    fn upcast_drawlet(drawlet: &mut ColoredMeshDrawlet) -> &mut (dyn ColoredMeshDrawletTrait + 'static) {
+        drawlet
+    }
+}
+
+pub struct ColoredMeshDrawlet {
+    mvp: Matrix4<f32>,
+    vertex_buffer: rj::Own<SoftwareBuffer<ColoredVertex>>,
+    index_buffer: rj::Own<SoftwareBuffer<u32>>
+}
+
+impl ColoredMeshDrawlet {
+    fn new(init_data: &ColoredMeshData) -> Self {
+        let vertex_buffer = init_data.mesh.vertex.buffer.downcast()
+            .expect("failed to cast vertex buffer to drawlet buffer type");
+
+        let index_buffer = init_data.mesh.index.buffer.downcast()
+            .expect("failed to cast index buffer to drawlet buffer type");
+
+        Self {
+            mvp: init_data.mvp_data,
+            vertex_buffer,
+            index_buffer
+        }
+    }
+}
+
+impl SoftwareDrawlet for ColoredMeshDrawlet {
+    fn draw(self: &Self, framebuffer: &mut SoftwareFramebuffer) {
+        let vertices = self.vertex_buffer.access().read();
+        let indices = self.index_buffer.access().read();
+
+        let clip_positions: Vec<Vector4<f32>> = vertices.iter()
+            .map(|v| self.mvp * Vector4::new(v.pos[0], v.pos[1], v.pos[2], 1f32))
+            .collect();
+        let varyings: Vec<[f32; 3]> = vertices.iter().map(|v| v.color).collect();
+
+        // colored_mesh.slang: float4(color, 1.0)
+        framebuffer.draw_indexed(&clip_positions, &varyings, &indices, |c| [c[0], c[1], c[2], 1f32]);
+    }
+}
+
+impl DrawletTrait<ColoredMesh> for ColoredMeshDrawlet {}
+impl ColoredMeshDrawletTrait for ColoredMeshDrawlet {
+    fn set_mvp(self: &mut Self, mvp: Matrix4<f32>) {
+        self.mvp = mvp;
+    }
+}
@@ -0,0 +1,216 @@
+use cgmath::{InnerSpace, Matrix4, Vector3, Vector4};
+use crate::render_backend::InstanceID;
+use crate::render_backend::render_interface::drawlets::DrawletTrait;
+use crate::render_backend::render_interface::drawlets::colored_mesh::ColoredVertex;
+use crate::render_backend::render_interface::drawlets::instanced_mesh::{Instance, InstanceList, InstancedColoredMesh, InstancedColoredMeshData, InstancedColoredMeshDrawletTrait, InstancedDrawletTrait, InstancedLitMesh, InstancedLitMeshData, InstancedLitMeshDrawletTrait};
+use crate::render_backend::render_interface::drawlets::lit_colored_mesh::NormalColoredVertex;
+use crate::render_backend::software::{SoftwareBuffer, SoftwareDrawlet, SoftwarePipelineConfig, SoftwareRenderObject};
+use crate::render_backend::software::lit_colored_mesh::{shade_lit, LitShading};
+use crate::render_backend::software::raster::SoftwareFramebuffer;
+
+impl SoftwareRenderObject for InstancedColoredMesh {
+    type Drawlet = InstancedColoredMeshDrawlet;
+
+    fn new_drawlet(init_data: InstancedColoredMeshData, _config: &SoftwarePipelineConfig) -> InstancedColoredMeshDrawlet {
+        InstancedColoredMeshDrawlet::new(&init_data)
+    }
+
+    fn upcast_drawlet(drawlet: &mut InstancedColoredMeshDrawlet) -> &mut (dyn InstancedColoredMeshDrawletTrait + 'static) {
+        drawlet
+    }
+}
+
+impl SoftwareRenderObject for InstancedLitMesh {
+    type Drawlet = InstancedLitMeshDrawlet;
+
+    fn new_drawlet(init_data: InstancedLitMeshData, config: &SoftwarePipelineConfig) -> InstancedLitMeshDrawlet {
+        InstancedLitMeshDrawlet::new(&init_data, config.shading)
+    }
+
+    fn upcast_drawlet(drawlet: &mut InstancedLitMeshDrawlet) -> &mut (dyn InstancedLitMeshDrawletTrait + 'static) {
+        drawlet
+    }
+}
+
+fn remove_instance(instances: &mut InstanceList, id: InstanceID) {
+    if instances.remove(id).is_none() {
+        log::warn!("ignoring stale instance {:?}", id);
+    }
+}
+
+fn update_instance(instances: &mut InstanceList, id: InstanceID, instance: Instance) {
+    if instances.update(id, instance).is_none() {
+        log::warn!("ignoring stale instance {:?}", id);
+    }
+}
+
+pub struct InstancedColoredMeshDrawlet {
+    view_proj: Matrix4<f32>,
+    vertex_buffer: rj::Own<SoftwareBuffer<ColoredVertex>>,
+    index_buffer: rj::Own<SoftwareBuffer<u32>>,
+    instances: InstanceList,
+}
+
+impl InstancedColoredMeshDrawlet {
+    fn new(init_data: &InstancedColoredMeshData) -> Self {
+        let vertex_buffer = init_data.mesh.vertex.buffer.downcast()
+            .expect("failed to cast vertex buffer to drawlet buffer type");
+
+        let index_buffer = init_data.mesh.index.buffer.downcast()
+            .expect("failed to cast index buffer to drawlet buffer type");
+
+        Self {
+            view_proj: init_data.view_proj,
+            vertex_buffer,
+            index_buffer,
+            instances: InstanceList::new(),
+        }
+    }
+}
+
+impl SoftwareDrawlet for InstancedColoredMeshDrawlet {
+    fn draw(self: &Self, framebuffer: &mut SoftwareFramebuffer) {
+        let vertices = self.vertex_buffer.access().read();
+        let indices = self.index_buffer.access().read();
+
+        for instance in self.instances.as_slice() {
+            let mvp = self.view_proj * instance.model;
+            let clip_positions: Vec<Vector4<f32>> = vertices.iter()
+                .map(|v| mvp * Vector4::new(v.pos[0], v.pos[1], v.pos[2], 1f32))
+                .collect();
+            let varyings: Vec<[f32; 3]> = vertices.iter().map(|v| v.color).collect();
+
+            // instanced_colored_mesh.slang: float4(color * tint.rgb, tint.a)
+            let tint = instance.tint;
+            framebuffer.draw_indexed(&clip_positions, &varyings, &indices,
+                |c| [c[0] * tint.x, c[1] * tint.y, c[2] * tint.z, tint.w]);
+        }
+    }
+}
+
+impl DrawletTrait<InstancedColoredMesh> for InstancedColoredMeshDrawlet {}
+
+impl InstancedDrawletTrait for InstancedColoredMeshDrawlet {
+    fn add_instance(self: &mut Self, instance: Instance) -> InstanceID {
+        self.instances.add(instance)
+    }
+
+    fn remove_instance(self: &mut Self, id: InstanceID) {
+        remove_instance(&mut self.instances, id);
+    }
+
+    fn update_instance(self: &mut Self, id: InstanceID, instance: Instance) {
+        update_instance(&mut self.instances, id, instance);
+    }
+
+    fn get_instance(self: &Self, id: InstanceID) -> Option<Instance> {
+        self.instances.get(id).copied()
+    }
+
+    fn instance_count(self: &Self) -> usize {
+        self.instances.len()
+    }
+}
+
+impl InstancedColoredMeshDrawletTrait for InstancedColoredMeshDrawlet {
+    fn set_view_proj(self: &mut Self, view_proj: Matrix4<f32>) {
+        self.view_proj = view_proj;
+    }
+}
+
+pub struct InstancedLitMeshDrawlet {
+    view_proj: Matrix4<f32>,
+    light_dir: Vector3<f32>,
+    view_dir: Vector3<f32>,
+    shading: LitShading,
+    vertex_buffer: rj::Own<SoftwareBuffer<NormalColoredVertex>>,
+    index_buffer: rj::Own<SoftwareBuffer<u32>>,
+    instances: InstanceList,
+}
+
+impl InstancedLitMeshDrawlet {
+    fn new(init_data: &InstancedLitMeshData, shading: LitShading) -> Self {
+        let vertex_buffer = init_data.mesh.vertex.buffer.downcast()
+            .expect("failed to cast vertex buffer to drawlet buffer type");
+
+        let index_buffer = init_data.mesh.index.buffer.downcast()
+            .expect("failed to cast index buffer to drawlet buffer type");
+
+        Self {
+            view_proj: init_data.view_proj,
+            light_dir: init_data.light_dir.truncate(),
+            view_dir: init_data.view_dir.truncate(),
+            shading,
+            vertex_buffer,
+            index_buffer,
+            instances: InstanceList::new(),
+        }
+    }
+}
+
+impl SoftwareDrawlet for InstancedLitMeshDrawlet {
+    fn draw(self: &Self, framebuffer: &mut SoftwareFramebuffer) {
+        let vertices = self.vertex_buffer.access().read();
+        let indices = self.index_buffer.access().read();
+
+        let light_vec = self.light_dir.normalize();
+        let view_vec = self.view_dir.normalize();
+
+        for instance in self.instances.as_slice() {
+            let mvp = self.view_proj * instance.model;
+            let clip_positions: Vec<Vector4<f32>> = vertices.iter()
+                .map(|v| mvp * Vector4::new(v.pos[0], v.pos[1], v.pos[2], 1f32))
+                .collect();
+
+            // the tint scales the color, the alpha is left for `LitShading::alpha_is_gloss`
+            let tint = instance.tint;
+            let varyings: Vec<[f32; 7]> = vertices.iter()
+                .map(|v| {
+                    let normal = instance.model * Vector4::new(v.normal[0], v.normal[1], v.normal[2], 0f32);
+                    [v.color[0] * tint.x, v.color[1] * tint.y, v.color[2] * tint.z, v.color[3],
+                        normal.x, normal.y, normal.z]
+                })
+                .collect();
+
+            framebuffer.draw_indexed(&clip_positions, &varyings, &indices, |v| shade_lit(&self.shading, v, light_vec, view_vec));
+        }
+    }
+}
+
+impl DrawletTrait<InstancedLitMesh> for InstancedLitMeshDrawlet {}
+
+impl InstancedDrawletTrait for InstancedLitMeshDrawlet {
+    fn add_instance(self: &mut Self, instance: Instance) -> InstanceID {
+        self.instances.add(instance)
+    }
+
+    fn remove_instance(self: &mut Self, id: InstanceID) {
+        remove_instance(&mut self.instances, id);
+    }
+
+    fn update_instance(self: &mut Self, id: InstanceID, instance: Instance) {
+        update_instance(&mut self.instances, id, instance);
+    }
+
+    fn get_instance(self: &Self, id: InstanceID) -> Option<Instance> {
+        self.instances.get(id).copied()
+    }
+
+    fn instance_count(self: &Self) -> usize {
+        self.instances.len()
+    }
+}
+
+impl InstancedLitMeshDrawletTrait for InstancedLitMeshDrawlet {
+    fn set_view_proj(self: &mut Self, view_proj: Matrix4<f32>) {
+        self.view_proj = view_proj;
+    }
+
+    fn set_light_dir(self: &mut Self, light_dir: Vector3<f32>) {
+        self.light_dir = light_dir;
+    }
+
+    fn set_view_dir(self: &mut Self, view_dir: Vector3<f32>) {
+        self.view_dir = view_dir;
+    }
+}
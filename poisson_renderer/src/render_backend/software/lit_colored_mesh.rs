@@ -0,0 +1,122 @@
+use cgmath::{InnerSpace, Matrix4, Vector3, Vector4};
+use crate::render_backend::render_interface::drawlets::DrawletTrait;
+use crate::render_backend::render_interface::drawlets::lit_colored_mesh::{LitColoredMesh, LitColoredMeshData, LitColoredMeshDrawletTrait, NormalColoredVertex};
+use crate::render_backend::software::{SoftwareBuffer, SoftwareDrawlet, SoftwarePipelineConfig, SoftwareRenderObject};
+use crate::render_backend::software::raster::SoftwareFramebuffer;
+
+impl SoftwareRenderObject for LitColoredMesh {
+    type Drawlet = LitColoredMeshDrawlet;
+
+    fn new_drawlet(init_data: LitColoredMeshData, config: &SoftwarePipelineConfig) -> LitColoredMeshDrawlet {
+        LitColoredMeshDrawlet::new(&init_data, config.shading)
+    }
+
+    fn upcast_drawlet(drawlet: &mut LitColoredMeshDrawlet) -> &mut (dyn LitColoredMeshDrawletTrait + 'static) {
+        drawlet
+    }
+}
+
+// the constants of a lit fragment shader, the software backend can't run
+// the game's shader so it shades with these, see `SoftwareRenderBackend::set_pipeline_config`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LitShading {
+    pub ambient: f32,
+    pub specular: f32,
+    pub shininess: f32,
+    // the vertex alpha moves weight from diffuse to specular instead of being ignored
+    pub alpha_is_gloss: bool,
+}
+
+impl LitShading {
+    pub fn shade(self: &Self, color: [f32; 4], normal: Vector3<f32>, light_vec: Vector3<f32>, view_vec: Vector3<f32>) -> [f32; 4] {
+        let (diffuse_weight, specular_weight) = if self.alpha_is_gloss {
+            (1f32 - color[3], color[3])
+        } else {
+            (1f32, 1f32)
+        };
+        let normal_vec = normal.normalize();
+
+        let diffuse = light_vec.dot(normal_vec).max(0f32) * diffuse_weight;
+        let reflect_vector = (2f32 * normal_vec.dot(light_vec)) * normal_vec - light_vec;
+        let rv = reflect_vector.dot(view_vec).max(0f32);
+        let specular = self.specular * specular_weight * rv.powf(self.shininess);
+
+        let shade = |channel: f32| self.ambient + diffuse * channel + specular;
+        [shade(color[0]), shade(color[1]), shade(color[2]), 1f32]
+    }
+}
+
+// the defaults most of the shipped lit shaders use
+impl Default for LitShading {
+    fn default() -> Self {
+        Self { ambient: 0.1f32, specular: 0.4f32, shininess: 50f32, alpha_is_gloss: false }
+    }
+}
+
+pub struct LitColoredMeshDrawlet {
+    mvp: Matrix4<f32>,
+    light_dir: Vector3<f32>,
+    view_dir: Vector3<f32>,
+    shading: LitShading,
+    vertex_buffer: rj::Own<SoftwareBuffer<NormalColoredVertex>>,
+    index_buffer: rj::Own<SoftwareBuffer<u32>>
+}
+
+impl LitColoredMeshDrawlet {
+    fn new(init_data: &LitColoredMeshData, shading: LitShading) -> Self {
+        let vertex_buffer = init_data.mesh.vertex.buffer.downcast()
+            .expect("failed to cast vertex buffer to drawlet buffer type");
+
+        let index_buffer = init_data.mesh.index.buffer.downcast()
+            .expect("failed to cast index buffer to drawlet buffer type");
+
+        Self {
+            mvp: init_data.mvp_data,
+            light_dir: init_data.light_dir.truncate(),
+            view_dir: init_data.view_dir.truncate(),
+            shading,
+            vertex_buffer,
+            index_buffer
+        }
+    }
+}
+
+impl SoftwareDrawlet for LitColoredMeshDrawlet {
+    fn draw(self: &Self, framebuffer: &mut SoftwareFramebuffer) {
+        let vertices = self.vertex_buffer.access().read();
+        let indices = self.index_buffer.access().read();
+
+        let clip_positions: Vec<Vector4<f32>> = vertices.iter()
+            .map(|v| self.mvp * Vector4::new(v.pos[0], v.pos[1], v.pos[2], 1f32))
+            .collect();
+        let varyings: Vec<[f32; 7]> = vertices.iter()
+            .map(|v| [v.color[0], v.color[1], v.color[2], v.color[3], v.normal[0], v.normal[1], v.normal[2]])
+            .collect();
+
+        let light_vec = self.light_dir.normalize();
+        let view_vec = self.view_dir.normalize();
+
+        framebuffer.draw_indexed(&clip_positions, &varyings, &indices, |v| shade_lit(&self.shading, v, light_vec, view_vec));
+    }
+}
+
+// `v` is the color followed by the normal
+pub(super) fn shade_lit(shading: &LitShading, v: &[f32; 7], light_vec: Vector3<f32>, view_vec: Vector3<f32>) -> [f32; 4] {
+    shading.shade([v[0], v[1], v[2], v[3]], Vector3::new(v[4], v[5], v[6]), light_vec, view_vec)
+}
+
+impl DrawletTrait<LitColoredMesh> for LitColoredMeshDrawlet {}
+
+impl LitColoredMeshDrawletTrait for LitColoredMeshDrawlet {
+    fn set_mvp(self: &mut Self, mvp: Matrix4<f32>) {
+        self.mvp = mvp;
+    }
+
+    fn set_light_dir(self: &mut Self, light_dir: Vector3<f32>) {
+        self.light_dir = light_dir;
+    }
+
+    fn set_view_dir(self: &mut Self, view_dir: Vector3<f32>) {
+        self.view_dir = view_dir;
+    }
+}
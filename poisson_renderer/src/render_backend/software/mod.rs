@@ -0,0 +1,438 @@
+pub mod raster;
+pub mod colored_mesh;
+pub mod textured_mesh;
+pub mod lit_colored_mesh;
+pub mod instanced_mesh;
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use image::{DynamicImage, RgbaImage};
+use parking_lot::Mutex;
+use winit::event::WindowEvent;
+use winit::window::Window;
+use crate::PoissonGame;
+use crate::{AsAny, PresentMode};
+use crate::egui::EguiRenderer;
+use crate::input::{Input, InputRecording};
+use crate::time::Time;
+use crate::context::EngineContext;
+use crate::render_backend::{DrawletID, PassID, PipelineID, RenderBackend, RenderPipeline};
+use crate::render_backend::registry::Registry;
+use crate::render_backend::render_interface::RenderObject;
+use crate::render_backend::render_interface::drawlets::{erase_pipeline, PassHandle, PassTrait, PipelineError, PipelineFactories, PipelineTrait, ReleaseQueue};
+use crate::render_backend::render_interface::drawlets::colored_mesh::ColoredMesh;
+use crate::render_backend::render_interface::drawlets::lit_colored_mesh::LitColoredMesh;
+use crate::render_backend::render_interface::drawlets::instanced_mesh::{InstancedColoredMesh, InstancedLitMesh};
+use crate::render_backend::render_interface::drawlets::textured_mesh::TexturedMesh;
+use crate::render_backend::render_interface::resources::{BufferUsage, GpuBufferHandle, GpuBufferTrait, GpuTextureHandle, TextureOptions};
+use crate::render_backend::software::lit_colored_mesh::LitShading;
+use crate::render_backend::software::raster::{SoftwareFramebuffer, CLEAR_COLOR};
+use crate::render_backend::software::textured_mesh::SoftwareTexture;
+use crate::render_backend::EguiUiShow;
+
+// rasterizes on the cpu with the fixed shading of the shipped slang shaders, lit meshes
+// are shaded with the `SoftwarePipelineConfig` set for their shader, meant for golden image
+// tests on machines without a gpu
+
+// stands in for the parts of a shader the software backend can't run
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SoftwarePipelineConfig {
+    pub shading: LitShading,
+}
+
+// keyed by shader path, shared by the backend and every pass it creates
+type PipelineConfigs = Arc<Mutex<HashMap<String, SoftwarePipelineConfig>>>;
+
+pub trait SoftwareRenderObject: RenderObject + Sized + 'static {
+    type Drawlet: SoftwareDrawlet + 'static;
+    fn new_drawlet(init_data: Self::Data, config: &SoftwarePipelineConfig) -> Self::Drawlet;
+    fn upcast_drawlet(drawlet: &mut Self::Drawlet) -> &mut Self::DynDrawlet;
+}
+
+pub trait SoftwareDrawlet {
+    fn draw(self: &Self, framebuffer: &mut SoftwareFramebuffer);
+}
+
+pub trait SoftwarePipelineDyn: AsAny {
+    fn draw(self: &Self, framebuffer: &mut SoftwareFramebuffer);
+    fn collect_released(self: &mut Self);
+}
+
+pub struct SoftwarePipeline<RenObjType: SoftwareRenderObject> {
+    config: SoftwarePipelineConfig,
+    drawlets: Registry<DrawletID, RenObjType::Drawlet>,
+    released: ReleaseQueue<DrawletID>,
+}
+
+impl<RenObjType: SoftwareRenderObject> RenderPipeline<RenObjType> for SoftwarePipeline<RenObjType> {}
+
+impl<RenObjType: SoftwareRenderObject> AsAny for SoftwarePipeline<RenObjType> {
+    fn as_any(self: &Self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(self: &mut Self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl<RenObjType: SoftwareRenderObject> SoftwarePipelineDyn for SoftwarePipeline<RenObjType> {
+    fn draw(self: &Self, framebuffer: &mut SoftwareFramebuffer) {
+        for drawlet in self.drawlets.values() {
+            drawlet.draw(framebuffer);
+        }
+    }
+
+    fn collect_released(self: &mut Self) {
+        for id in self.released.take() {
+            self.drawlets.remove(id);
+        }
+    }
+}
+
+impl<RenObjType: SoftwareRenderObject> PipelineTrait<RenObjType> for SoftwarePipeline<RenObjType> {
+    fn create_drawlet(&mut self, init_data: RenObjType::Data) -> DrawletID {
+        self.drawlets.insert(RenObjType::new_drawlet(init_data, &self.config))
+    }
+
+    fn remove_drawlet(&mut self, id: DrawletID) {
+        let _ = self.drawlets.remove(id);
+    }
+
+    fn get_drawlet_mut(&mut self, id: DrawletID) -> Option<&mut RenObjType::DynDrawlet> {
+        self.drawlets.get_mut(id).map(RenObjType::upcast_drawlet)
+    }
+
+    fn get_drawlet_releases(self: &Self) -> ReleaseQueue<DrawletID> {
+        self.released.clone()
+    }
+}
+
+pub struct SoftwareRenderPass {
+    pipelines: Registry<PipelineID, Box<dyn SoftwarePipelineDyn>>,
+    released: ReleaseQueue<PipelineID>,
+    pipeline_factories: PipelineFactories<SoftwareRenderPass>,
+    pipeline_configs: PipelineConfigs,
+}
+
+fn build_pipeline<RenObjType: SoftwareRenderObject>(pass: &mut SoftwareRenderPass, shader_path: &str, _shader_text: &str)
+    -> Result<(PipelineID, Box<dyn Any>), PipelineError>
+{
+    let config = pass.pipeline_configs.lock().get(shader_path).copied().unwrap_or_default();
+    let pipeline_id = pass.pipelines.insert(Box::new(SoftwarePipeline::<RenObjType> {
+        config,
+        drawlets: Registry::new(),
+        released: ReleaseQueue::default(),
+    }));
+    Ok(erase_pipeline::<RenObjType, SoftwarePipeline<RenObjType>>(pipeline_id))
+}
+
+impl PassTrait for SoftwareRenderPass {
+    fn create_erased_pipeline(&mut self, render_object: TypeId, shader_path: &str, shader_text: &str)
+        -> Result<(PipelineID, Box<dyn Any>), PipelineError>
+    {
+        self.pipeline_factories.clone().create_pipeline(self, render_object, shader_path, shader_text)
+    }
+
+    fn get_pipeline_mut(&mut self, id: PipelineID) -> Option<&mut dyn Any> {
+        self.pipelines.get_mut(id).map(|pipeline| pipeline.as_any_mut())
+    }
+
+    fn get_pipeline_releases(self: &Self) -> ReleaseQueue<PipelineID> {
+        self.released.clone()
+    }
+}
+
+impl SoftwareRenderPass {
+    fn new(pipeline_factories: &PipelineFactories<SoftwareRenderPass>, pipeline_configs: &PipelineConfigs) -> Self {
+        Self {
+            pipelines: Registry::new(),
+            released: ReleaseQueue::default(),
+            pipeline_factories: pipeline_factories.clone(),
+            pipeline_configs: pipeline_configs.clone(),
+        }
+    }
+
+    fn collect_released(self: &mut Self) {
+        for id in self.released.take() {
+            self.pipelines.remove(id);
+        }
+        for pipeline in self.pipelines.values_mut() {
+            pipeline.collect_released();
+        }
+    }
+
+    fn render(self: &Self, framebuffer: &mut SoftwareFramebuffer) {
+        // every wgpu pass clears its target, mirror that here
+        framebuffer.clear(CLEAR_COLOR, 1f32);
+
+        for pipeline in self.pipelines.values() {
+            pipeline.draw(framebuffer);
+        }
+    }
+}
+
+// `data` holds `capacity` elements, only the first `size` are in use
+pub struct SoftwareBuffer<T> {
+    size: usize,
+    capacity: usize,
+    usage: BufferUsage,
+    data: Vec<u8>,
+    _phantom_data: PhantomData<T>
+}
+
+impl<T> SoftwareBuffer<T> {
+    fn new(data: &[T], usage: BufferUsage) -> Self {
+        let bytes: &[u8] = unsafe {
+            std::slice::from_raw_parts(
+                data.as_ptr() as *const u8, data.len() * size_of::<T>()
+            )
+        };
+
+        let capacity = usage.initial_capacity(data.len());
+        let mut storage = bytes.to_vec();
+        storage.resize(capacity * size_of::<T>(), 0);
+
+        Self {
+            size: data.len(),
+            capacity,
+            usage,
+            data: storage,
+            _phantom_data: PhantomData::default()
+        }
+    }
+
+    pub fn read(self: &Self) -> Vec<T> where T: Copy {
+        (0..self.size).map(|i| unsafe {
+            std::ptr::read_unaligned(self.data.as_ptr().add(i * size_of::<T>()) as *const T)
+        }).collect()
+    }
+}
+
+impl<T: 'static> GpuBufferTrait<T> for SoftwareBuffer<T> {
+    fn get_size_bytes(&self) -> usize {
+        self.size * size_of::<T>()
+    }
+    fn get_count(&self) -> usize {
+        self.size
+    }
+    fn get_capacity(&self) -> usize {
+        self.capacity
+    }
+    fn get_usage(&self) -> BufferUsage {
+        self.usage
+    }
+
+    fn write(&mut self, offset: usize, data: &[T]) {
+        assert!(offset + data.len() <= self.size,
+            "writing {} elements at {} overflows a buffer of {}", data.len(), offset, self.size);
+
+        let bytes: &[u8] = unsafe {
+            std::slice::from_raw_parts(
+                data.as_ptr() as *const u8, data.len() * size_of::<T>()
+            )
+        };
+
+        let start = offset * size_of::<T>();
+        self.data[start..start + bytes.len()].copy_from_slice(bytes);
+    }
+
+    fn resize(&mut self, count: usize) {
+        let mut kept = self.size.min(count);
+        if count > self.capacity {
+            self.capacity = self.usage.grow_capacity(self.capacity, count);
+            if !self.usage.keeps_contents_on_grow() {
+                kept = 0;
+            }
+        }
+
+        // everything past the kept elements reads as zero, like a fresh gpu allocation
+        self.data.truncate(kept * size_of::<T>());
+        self.data.resize(self.capacity * size_of::<T>(), 0);
+        self.size = count;
+    }
+}
+
+pub struct SoftwareRenderBackend {
+    framebuffer: SoftwareFramebuffer,
+    frame_index: u64,
+    render_passes: Arc<Mutex<Registry<PassID, SoftwareRenderPass>>>,
+    released_passes: ReleaseQueue<PassID>,
+    pipeline_factories: PipelineFactories<SoftwareRenderPass>,
+    pipeline_configs: PipelineConfigs,
+    egui_context: egui::Context,
+}
+
+impl SoftwareRenderBackend {
+    pub fn new(width: u32, height: u32) -> Self {
+        let pipeline_factories = PipelineFactories::default();
+        pipeline_factories.register::<ColoredMesh>(build_pipeline::<ColoredMesh>);
+        pipeline_factories.register::<LitColoredMesh>(build_pipeline::<LitColoredMesh>);
+        pipeline_factories.register::<TexturedMesh>(build_pipeline::<TexturedMesh>);
+        pipeline_factories.register::<InstancedColoredMesh>(build_pipeline::<InstancedColoredMesh>);
+        pipeline_factories.register::<InstancedLitMesh>(build_pipeline::<InstancedLitMesh>);
+
+        Self {
+            framebuffer: SoftwareFramebuffer::new(width, height),
+            frame_index: 0,
+            render_passes: Arc::new(Mutex::new(Registry::new())),
+            released_passes: ReleaseQueue::default(),
+            pipeline_factories,
+            pipeline_configs: PipelineConfigs::default(),
+            egui_context: egui::Context::default(),
+        }
+    }
+
+    // `PassHandle::create_pipeline::<RenObjType>` works for the render object afterwards
+    pub fn register_render_object<RenObjType: SoftwareRenderObject>(self: &mut Self) {
+        self.pipeline_factories.register::<RenObjType>(build_pipeline::<RenObjType>);
+    }
+
+    // pipelines created for `shader_path` afterwards use `config`, the others keep the default
+    pub fn set_pipeline_config(self: &mut Self, shader_path: &str, config: SoftwarePipelineConfig) {
+        self.pipeline_configs.lock().insert(shader_path.to_owned(), config);
+    }
+
+    // drops whatever lost its last handle since the previous frame
+    fn collect_released(self: &mut Self) {
+        let mut render_passes = self.render_passes.lock();
+        for id in self.released_passes.take() {
+            render_passes.remove(id);
+        }
+        for render_pass in render_passes.values_mut() {
+            render_pass.collect_released();
+        }
+    }
+
+    // egui is run so ui code executes, but it is not painted into the image
+    pub fn render_frame(self: &mut Self, egui_show_obj: &mut dyn EguiUiShow) -> &RgbaImage {
+        self.collect_released();
+
+        for render_pass in self.render_passes.lock().values() {
+            render_pass.render(&mut self.framebuffer);
+        }
+
+        let _ = self.egui_context.run(egui::RawInput::default(), |ctx| egui_show_obj.show(ctx));
+
+        self.frame_index += 1;
+
+        &self.framebuffer.color
+    }
+
+    pub fn get_image(self: &Self) -> &RgbaImage {
+        &self.framebuffer.color
+    }
+
+    pub fn get_depth(self: &Self) -> &[f32] {
+        self.framebuffer.depth.as_slice()
+    }
+
+    pub fn get_frame_index(self: &Self) -> u64 {
+        self.frame_index
+    }
+}
+
+// runs a game headless for `frames` frames and returns the last rendered image,
+// every frame advances the clock by exactly one fixed timestep so the result is reproducible
+pub fn render_frames<Game>(width: u32, height: u32, frames: usize) -> RgbaImage
+where Game: PoissonGame<Ren = SoftwareRenderBackend>
+{
+    run_headless::<Game>(width, height, frames, Input::new())
+}
+
+// replays recorded input with the recorded frame times and returns the last rendered image
+pub fn replay_frames<Game>(width: u32, height: u32, recording: InputRecording) -> RgbaImage
+where Game: PoissonGame<Ren = SoftwareRenderBackend>
+{
+    let frames = recording.frame_count();
+    let mut input = Input::new();
+    input.start_replay(recording);
+    run_headless::<Game>(width, height, frames, input)
+}
+
+fn run_headless<Game>(width: u32, height: u32, frames: usize, mut input: Input) -> RgbaImage
+where Game: PoissonGame<Ren = SoftwareRenderBackend>
+{
+    let mut game = Game::new();
+    let mut time = Time::new();
+    let mut context = EngineContext::headless();
+    game.pre_init(&mut input);
+
+    let mut backend = SoftwareRenderBackend::new(width, height);
+    game.init(&mut input, &mut backend, &mut context);
+
+    for _ in 0..frames {
+        let frame_delta = input.get_replay_delta().unwrap_or(time.fixed_timestep());
+        time.advance_by(frame_delta);
+        input.advance_frame(time.unscaled_delta());
+        while time.consume_fixed_step() {
+            input.begin_fixed_step();
+            game.fixed_update(&mut input, &mut time);
+            input.end_fixed_step();
+        }
+        game.update(&mut input, &mut backend, &mut time, &mut context);
+        backend.render_frame(game.get_egui_ui_show());
+    }
+
+    backend.get_image().clone()
+}
+
+impl RenderBackend for SoftwareRenderBackend {
+    const PERSPECTIVE_ALIGNMENT: [f32; 3] = [1f32, 1f32, -1f32];
+
+    fn init(backend_clone: Arc<Mutex<Option<Self>>>, window: Arc<Window>, _present_mode: PresentMode) where Self: Sized {
+        let size = window.inner_size();
+        backend_clone.lock().replace(SoftwareRenderBackend::new(size.width, size.height));
+    }
+
+    fn render(self: &mut Self, _window: &Arc<Window>, egui_show_obj: &mut dyn EguiUiShow) {
+        self.render_frame(egui_show_obj);
+    }
+
+    fn process_event(self: &mut Self, _window: &Window, _event: &WindowEvent) {}
+
+    fn resize(self: &mut Self, width: u32, height: u32) {
+        if width == self.framebuffer.width() && height == self.framebuffer.height() {
+            return;
+        }
+        self.framebuffer = SoftwareFramebuffer::new(width, height);
+    }
+
+    fn create_render_pass(self: &mut Self) -> PassHandle {
+        let id = self.render_passes.lock().insert(SoftwareRenderPass::new(&self.pipeline_factories, &self.pipeline_configs));
+
+        PassHandle::new(id, self.render_passes.clone(), &self.released_passes)
+    }
+
+    fn create_index_buffer_with_usage(self: &Self, data: &[u32], usage: BufferUsage) -> GpuBufferHandle<u32> {
+        self.create_vertex_buffer_with_usage(data, usage)
+    }
+
+    fn create_vertex_buffer_with_usage<T: Sized + 'static>(self: &Self, data: &[T], usage: BufferUsage) -> GpuBufferHandle<T> {
+        let buffer_own = rj::Own::new(SoftwareBuffer::<T>::new(data, usage));
+
+        GpuBufferHandle::from_own(buffer_own.upcast())
+    }
+
+    fn create_texture_with_options(self: &Self, image: &DynamicImage, options: TextureOptions) -> GpuTextureHandle {
+        GpuTextureHandle::from_own(rj::Own::new(SoftwareTexture::from_image(image, options)).upcast())
+    }
+
+    fn get_width(self: &Self) -> u32 {
+        self.framebuffer.width()
+    }
+
+    fn get_height(self: &Self) -> u32 {
+        self.framebuffer.height()
+    }
+
+    fn get_egui_renderer(self: &Self) -> Option<&EguiRenderer> {
+        None
+    }
+
+    fn get_egui_context(self: &Self) -> Option<egui::Context> {
+        None
+    }
+}
@@ -0,0 +1,148 @@
+use cgmath::Vector4;
+use image::{Rgba, RgbaImage};
+
+pub const CLEAR_COLOR: [f32; 4] = [1f32, 1f32, 1f32, 1f32];
+
+pub struct SoftwareFramebuffer {
+    pub color: RgbaImage,
+    pub depth: Vec<f32>,
+}
+
+impl SoftwareFramebuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        let mut ret = Self {
+            color: RgbaImage::new(width.max(1), height.max(1)),
+            depth: vec![1f32; (width.max(1) * height.max(1)) as usize],
+        };
+        ret.clear(CLEAR_COLOR, 1f32);
+        ret
+    }
+
+    pub fn width(self: &Self) -> u32 {
+        self.color.width()
+    }
+
+    pub fn height(self: &Self) -> u32 {
+        self.color.height()
+    }
+
+    pub fn clear(self: &mut Self, color: [f32; 4], depth: f32) {
+        let pixel = Rgba(color.map(to_unorm8));
+        for p in self.color.pixels_mut() {
+            *p = pixel;
+        }
+        self.depth.fill(depth);
+    }
+
+    // rasterizes an indexed triangle list with the same conventions as the wgpu pipelines:
+    // ndc z in [0, 1], depth compare `Less`, no culling and alpha blending on the color target
+    pub fn draw_indexed<const N: usize>(
+        self: &mut Self,
+        clip_positions: &[Vector4<f32>],
+        varyings: &[[f32; N]],
+        indices: &[u32],
+        shade: impl Fn(&[f32; N]) -> [f32; 4],
+    ) {
+        let width = self.width();
+        let height = self.height();
+
+        for triangle in indices.chunks_exact(3) {
+            let idx = [triangle[0] as usize, triangle[1] as usize, triangle[2] as usize];
+            let clip = idx.map(|i| clip_positions[i]);
+
+            // there is no near plane clipping, triangles reaching behind the eye are dropped
+            if clip.iter().any(|c| c.w <= f32::EPSILON) {
+                continue;
+            }
+
+            // [x, y, z, 1/w] in pixel space
+            let screen = clip.map(|c| {
+                let inv_w = 1f32 / c.w;
+                [
+                    (c.x * inv_w + 1f32) * 0.5f32 * width as f32,
+                    (1f32 - c.y * inv_w) * 0.5f32 * height as f32,
+                    c.z * inv_w,
+                    inv_w
+                ]
+            });
+
+            let area = edge(&screen[0], &screen[1], [screen[2][0], screen[2][1]]);
+            if area.abs() <= f32::EPSILON {
+                continue;
+            }
+
+            let min_x = screen.iter().map(|s| s[0]).fold(f32::MAX, f32::min).floor().max(0f32) as u32;
+            let min_y = screen.iter().map(|s| s[1]).fold(f32::MAX, f32::min).floor().max(0f32) as u32;
+            let max_x = (screen.iter().map(|s| s[0]).fold(f32::MIN, f32::max).ceil().max(0f32) as u32).min(width);
+            let max_y = (screen.iter().map(|s| s[1]).fold(f32::MIN, f32::max).ceil().max(0f32) as u32).min(height);
+
+            for py in min_y..max_y {
+                for px in min_x..max_x {
+                    let p = [px as f32 + 0.5f32, py as f32 + 0.5f32];
+                    let b = [
+                        edge(&screen[1], &screen[2], p) / area,
+                        edge(&screen[2], &screen[0], p) / area,
+                        edge(&screen[0], &screen[1], p) / area,
+                    ];
+                    if b.iter().any(|x| *x < 0f32) {
+                        continue;
+                    }
+
+                    let z = b[0] * screen[0][2] + b[1] * screen[1][2] + b[2] * screen[2][2];
+                    if !(0f32..=1f32).contains(&z) {
+                        continue;
+                    }
+
+                    let depth_idx = (py * width + px) as usize;
+                    if z >= self.depth[depth_idx] {
+                        continue;
+                    }
+
+                    // perspective correct weights
+                    let inv_w = b[0] * screen[0][3] + b[1] * screen[1][3] + b[2] * screen[2][3];
+                    let weights = [
+                        b[0] * screen[0][3] / inv_w,
+                        b[1] * screen[1][3] / inv_w,
+                        b[2] * screen[2][3] / inv_w,
+                    ];
+
+                    let mut varying = [0f32; N];
+                    for k in 0..N {
+                        varying[k] = weights[0] * varyings[idx[0]][k]
+                            + weights[1] * varyings[idx[1]][k]
+                            + weights[2] * varyings[idx[2]][k];
+                    }
+
+                    let src = shade(&varying);
+                    let dst = self.color.get_pixel(px, py).0.map(|c| c as f32 / 255f32);
+                    let alpha = src[3].clamp(0f32, 1f32);
+                    let blended = [
+                        src[0] * alpha + dst[0] * (1f32 - alpha),
+                        src[1] * alpha + dst[1] * (1f32 - alpha),
+                        src[2] * alpha + dst[2] * (1f32 - alpha),
+                        alpha + dst[3] * (1f32 - alpha),
+                    ];
+
+                    self.depth[depth_idx] = z;
+                    self.color.put_pixel(px, py, Rgba(blended.map(to_unorm8)));
+                }
+            }
+        }
+    }
+}
+
+fn edge(a: &[f32; 4], b: &[f32; 4], p: [f32; 2]) -> f32 {
+    (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+}
+
+fn to_unorm8(c: f32) -> u8 {
+    (c.clamp(0f32, 1f32) * 255f32).round() as u8
+}
+
+pub fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045f32 {
+        c / 12.92f32
+    } else {
+        ((c + 0.055f32) / 1.055f32).powf(2.4f32)
+    }
+}
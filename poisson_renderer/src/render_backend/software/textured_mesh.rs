@@ -0,0 +1,158 @@
+use cgmath::{Matrix4, Vector4};
+use crate::render_backend::render_interface::drawlets::DrawletTrait;
+use crate::render_backend::render_interface::resources::{max_mip_level_count, GpuTextureHandle, GpuTextureTrait, TextureFormat, TextureOptions};
+use crate::render_backend::render_interface::drawlets::textured_mesh::{TexturedMesh, TexturedMeshData, TexturedMeshDrawletTrait, UvVertex};
+use crate::render_backend::software::{SoftwareBuffer, SoftwareDrawlet, SoftwarePipelineConfig, SoftwareRenderObject};
+use crate::render_backend::software::raster::{srgb_to_linear, SoftwareFramebuffer};
+
+impl SoftwareRenderObject for TexturedMesh {
+    type Drawlet = TexturedMeshDrawlet;
+
+    fn new_drawlet(init_data: TexturedMeshData, _config: &SoftwarePipelineConfig) -> TexturedMeshDrawlet {
+        TexturedMeshDrawlet::new(&init_data)
+    }
+
+    fn upcast_drawlet(drawlet: &mut TexturedMeshDrawlet) -> &mut (dyn TexturedMeshDrawletTrait + 'static) {
+        drawlet
+    }
+}
+
+// texels are kept linear, matching what sampling the wgpu texture returns.
+// only the first mip level is stored since the rasterizer has no derivatives to pick a level
+pub struct SoftwareTexture {
+    width: u32,
+    height: u32,
+    format: TextureFormat,
+    mip_level_count: u32,
+    texels: Vec<[f32; 4]>,
+}
+
+impl SoftwareTexture {
+    pub fn from_image(img: &image::DynamicImage, options: TextureOptions) -> Self {
+        let rgba = img.to_rgba8();
+        let mut ret = Self {
+            width: rgba.width(),
+            height: rgba.height(),
+            format: options.format,
+            mip_level_count: options.mip_level_count.clamp(1, max_mip_level_count(rgba.width(), rgba.height())),
+            texels: vec![[0f32; 4]; (rgba.width() * rgba.height()) as usize],
+        };
+        ret.update_region(0, 0, &rgba);
+        ret
+    }
+
+    fn to_linear(self: &Self, pixel: &image::Rgba<u8>) -> [f32; 4] {
+        let channel = |c: u8| match self.format {
+            TextureFormat::Rgba8UnormSrgb => srgb_to_linear(c as f32 / 255f32),
+            TextureFormat::Rgba8Unorm => c as f32 / 255f32,
+        };
+        [channel(pixel.0[0]), channel(pixel.0[1]), channel(pixel.0[2]), pixel.0[3] as f32 / 255f32]
+    }
+
+    fn texel(self: &Self, x: i64, y: i64) -> [f32; 4] {
+        let x = x.rem_euclid(self.width as i64) as u32;
+        let y = y.rem_euclid(self.height as i64) as u32;
+        self.texels[(y * self.width + x) as usize]
+    }
+
+    // bilinear filtering with repeat addressing
+    pub fn sample(self: &Self, uv: [f32; 2]) -> [f32; 4] {
+        let x = uv[0] * self.width as f32 - 0.5f32;
+        let y = uv[1] * self.height as f32 - 0.5f32;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let t00 = self.texel(x0, y0);
+        let t10 = self.texel(x0 + 1, y0);
+        let t01 = self.texel(x0, y0 + 1);
+        let t11 = self.texel(x0 + 1, y0 + 1);
+
+        let mut ret = [0f32; 4];
+        for k in 0..4 {
+            let top = t00[k] * (1f32 - fx) + t10[k] * fx;
+            let bottom = t01[k] * (1f32 - fx) + t11[k] * fx;
+            ret[k] = top * (1f32 - fy) + bottom * fy;
+        }
+        ret
+    }
+}
+
+impl GpuTextureTrait for SoftwareTexture {
+    fn get_width(&self) -> u32 {
+        self.width
+    }
+    fn get_height(&self) -> u32 {
+        self.height
+    }
+    fn get_format(&self) -> TextureFormat {
+        self.format
+    }
+    fn get_mip_level_count(&self) -> u32 {
+        self.mip_level_count
+    }
+
+    fn update_region(&mut self, x: u32, y: u32, image: &image::RgbaImage) {
+        for (px, py, pixel) in image.enumerate_pixels() {
+            let texel = self.to_linear(pixel);
+            self.texels[((y + py) * self.width + x + px) as usize] = texel;
+        }
+    }
+}
+
+pub struct TexturedMeshDrawlet {
+    mvp: Matrix4<f32>,
+    texture: rj::Own<SoftwareTexture>,
+    vertex_buffer: rj::Own<SoftwareBuffer<UvVertex>>,
+    index_buffer: rj::Own<SoftwareBuffer<u32>>
+}
+
+impl TexturedMeshDrawlet {
+    fn new(init_data: &TexturedMeshData) -> Self {
+        let vertex_buffer = init_data.mesh.vertex.buffer.downcast()
+            .expect("failed to cast vertex buffer to drawlet buffer type");
+
+        let index_buffer = init_data.mesh.index.buffer.downcast()
+            .expect("failed to cast index buffer to drawlet buffer type");
+
+        Self {
+            mvp: init_data.mvp_data,
+            texture: init_data.texture.texture.downcast()
+                .expect("failed to cast texture to drawlet texture type"),
+            vertex_buffer,
+            index_buffer
+        }
+    }
+}
+
+impl SoftwareDrawlet for TexturedMeshDrawlet {
+    fn draw(self: &Self, framebuffer: &mut SoftwareFramebuffer) {
+        let vertices = self.vertex_buffer.access().read();
+        let indices = self.index_buffer.access().read();
+
+        let clip_positions: Vec<Vector4<f32>> = vertices.iter()
+            .map(|v| self.mvp * Vector4::new(v.pos[0], v.pos[1], v.pos[2], 1f32))
+            .collect();
+        let varyings: Vec<[f32; 2]> = vertices.iter().map(|v| v.tex_coord).collect();
+
+        // textured_mesh.slang samples the texture and forces alpha to 1
+        let texture = self.texture.access();
+        framebuffer.draw_indexed(&clip_positions, &varyings, &indices, |uv| {
+            let color = texture.sample(*uv);
+            [color[0], color[1], color[2], 1f32]
+        });
+    }
+}
+
+impl DrawletTrait<TexturedMesh> for TexturedMeshDrawlet {}
+
+impl TexturedMeshDrawletTrait for TexturedMeshDrawlet {
+    fn set_mvp(self: &mut Self, mvp: Matrix4<f32>) {
+        self.mvp = mvp;
+    }
+
+    fn set_texture(self: &mut Self, texture: &GpuTextureHandle) {
+        self.texture = texture.texture.downcast()
+            .expect("failed to cast texture to drawlet texture type");
+    }
+}
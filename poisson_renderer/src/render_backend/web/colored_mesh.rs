@@ -1,5 +1,6 @@
 use crate::render_backend::render_interface::drawlets::colored_mesh::{ColoredMesh, ColoredMeshData, ColoredMeshDrawletTrait, ColoredVertex};
-use crate::render_backend::render_interface::drawlets::{DrawletHandle, DrawletTrait, PipelineTrait};
+use crate::render_backend::render_interface::drawlets::{DrawletTrait, PipelineTrait, ReleaseQueue};
+use crate::render_backend::registry::Registry;
 use crate::render_backend::web::gpu_resources::gpu_mat4::GpuMat4;
 use crate::render_backend::web::gpu_resources::gpu_texture::Texture;
 use crate::render_backend::web::gpu_resources::interface::WgpuUniformResource;
@@ -9,10 +10,8 @@ use crate::render_backend::{DrawletID, Mat4Ubo, RenderDrawlet, RenderPipeline};
 use crate::AsAny;
 use cgmath::Matrix4;
 use poisson_macros::AsAny;
-use rj::Own;
 use std::any::Any;
 use std::borrow::Cow;
-use std::collections::HashMap;
 use std::sync::{Arc, Weak};
 use wgpu::SurfaceConfiguration;
 
@@ -24,7 +23,6 @@ impl WgpuRenderObject for ColoredMesh {
 
 pub struct ColoredMeshDrawlet {
     device: Weak<Device>,
-    num_indices: u32,
     mvp_buffer: GpuMat4,
     vertex_buffer: rj::Own<WgpuBuffer<ColoredVertex>>,
     index_buffer: rj::Own<WgpuBuffer<u32>>
@@ -46,7 +44,6 @@ impl ColoredMeshDrawlet {
 
         Self {
             device: Arc::downgrade(device),
-            num_indices: init_data.mesh.index.get_count() as u32,
             mvp_buffer: uniform_buffer,
             vertex_buffer,
             index_buffer
@@ -62,8 +59,9 @@ impl WgpuDrawlet for ColoredMeshDrawlet {
     fn draw(self: &Self, render_pass: &mut wgpu::RenderPass) {
         render_pass.set_bind_group(0, self.mvp_buffer.get_bind_group(), &[]);
         render_pass.set_vertex_buffer(0, self.vertex_buffer.access().slice());
-        render_pass.set_index_buffer(self.index_buffer.access().slice(), wgpu::IndexFormat::Uint32);
-        render_pass.draw_indexed(0..self.num_indices, 0, 0..1);
+        let index_buffer = self.index_buffer.access();
+        render_pass.set_index_buffer(index_buffer.slice(), wgpu::IndexFormat::Uint32);
+        render_pass.draw_indexed(0..index_buffer.num_elements(), 0, 0..1);
     }
 }
 
@@ -71,33 +69,32 @@ impl WgpuDrawlet for ColoredMeshDrawlet {
 pub struct ColoredMeshPipeline {
     device: Weak<Device>,
     render_pipeline: wgpu::RenderPipeline,
-    drawlets: HashMap<DrawletID, rj::Own<ColoredMeshDrawlet>>
+    drawlets: Registry<DrawletID, ColoredMeshDrawlet>,
+    released: ReleaseQueue<DrawletID>,
 }
 
 impl WgpuPipelineDyn for ColoredMeshPipeline {
     fn get_pipeline(self: &Self) -> &wgpu::RenderPipeline {
         &self.render_pipeline
     }
-    fn get_instances(self: &Self) -> Box<dyn Iterator<Item=rj::Own<dyn WgpuDrawletDyn>> + '_> {
-        Box::new(self.drawlets.iter().map(
-            |(_, x)|
-                rj::Own::<dyn WgpuDrawletDyn>::from_inner(x.clone().into_inner())
-        ))
+    fn get_instances(self: &Self) -> Box<dyn Iterator<Item=&dyn WgpuDrawletDyn> + '_> {
+        Box::new(self.drawlets.values().map(|drawlet| drawlet as &dyn WgpuDrawletDyn))
+    }
+
+    fn collect_released(self: &mut Self) {
+        for id in self.released.take() {
+            self.drawlets.remove(id);
+        }
     }
 }
 
 impl WgpuPipeline<ColoredMesh> for ColoredMeshPipeline {
-    fn create_drawlet(self: &mut Self, init_data: ColoredMeshData) -> (DrawletID, rj::Own<ColoredMeshDrawlet>) {
-        let id = <Self as RenderPipeline<ColoredMesh>>::get_drawlet_id();
+    fn create_drawlet(self: &mut Self, init_data: ColoredMeshData) -> DrawletID {
         let new_drawlet = ColoredMeshDrawlet::new(
             &self.device.upgrade().unwrap(),
             &init_data);
 
-        let own = rj::Own::new(new_drawlet);
-
-        self.drawlets.insert(id, own.clone());
-
-        (id, own)
+        self.drawlets.insert(new_drawlet)
     }
     
     fn new(device: &Arc<Device>, shader_u8: &[u8], surface_config: &SurfaceConfiguration) -> Self
@@ -170,12 +167,11 @@ impl WgpuPipeline<ColoredMesh> for ColoredMeshPipeline {
             cache: None,
         });
 
-        let drawlets = HashMap::new();
-
         Self {
             device: Arc::downgrade(device),
             render_pipeline,
-            drawlets
+            drawlets: Registry::new(),
+            released: ReleaseQueue::default(),
         }
     }
 }
@@ -194,13 +190,20 @@ impl ColoredMeshDrawlet {
 }
 
 impl PipelineTrait<ColoredMesh> for ColoredMeshPipeline {
-    fn create_drawlet(&mut self, init_data: ColoredMeshData) -> (DrawletID, Own<(dyn ColoredMeshDrawletTrait + 'static)>) {
-        let (id, own) = WgpuPipeline::create_drawlet(self, init_data);
+    fn create_drawlet(&mut self, init_data: ColoredMeshData) -> DrawletID {
+        WgpuPipeline::create_drawlet(self, init_data)
+    }
 
-        (id, own.upcast())
+    fn remove_drawlet(&mut self, id: DrawletID) {
+        let _ = self.drawlets.remove(id);
     }
-    fn remove_drawlet(&mut self, drawlet: DrawletHandle<ColoredMesh>) {
-        let _ = self.drawlets.remove(&drawlet.id);
+
+    fn get_drawlet_mut(&mut self, id: DrawletID) -> Option<&mut (dyn ColoredMeshDrawletTrait + 'static)> {
+        self.drawlets.get_mut(id).map(|drawlet| drawlet as &mut dyn ColoredMeshDrawletTrait)
+    }
+
+    fn get_drawlet_releases(self: &Self) -> ReleaseQueue<DrawletID> {
+        self.released.clone()
     }
 }
 
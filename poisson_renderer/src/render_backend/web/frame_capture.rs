@@ -0,0 +1,437 @@
+use std::sync::Arc;
+use image::RgbaImage;
+use parking_lot::Mutex;
+use wgpu::{CommandEncoder, SurfaceConfiguration, TextureFormat, TextureView};
+
+type CaptureCallback = Box<dyn FnOnce(RgbaImage) + Send>;
+
+// copies the offscreen frame onto surfaces that can't be a copy destination
+const BLIT_WGSL: &str = r#"
+@group(0) @binding(0) var frame: texture_2d<f32>;
+
+@vertex
+fn vertex(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    // a single triangle covering the whole screen
+    let corner = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(corner * 2.0 - 1.0, 0.0, 1.0);
+}
+
+@fragment
+fn fragment(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    return textureLoad(frame, vec2<i32>(position.xy), 0);
+}
+"#;
+
+struct SurfaceBlit {
+    format: TextureFormat,
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl SurfaceBlit {
+    fn new(device: &wgpu::Device, format: TextureFormat) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Frame Blit Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Frame Blit Shader"),
+            source: wgpu::ShaderSource::Wgsl(BLIT_WGSL.into()),
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Frame Blit Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Frame Blit Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vertex"),
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fragment"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        Self { format, bind_group_layout, pipeline }
+    }
+}
+
+// filled in once the frame has been copied back from the gpu, which can take a few frames
+#[derive(Clone)]
+pub struct ScreenshotHandle {
+    image: Arc<Mutex<Option<RgbaImage>>>
+}
+
+impl ScreenshotHandle {
+    pub fn is_ready(self: &Self) -> bool {
+        self.image.lock().is_some()
+    }
+
+    pub fn try_take(self: &Self) -> Option<RgbaImage> {
+        self.image.lock().take()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+struct PngSequence {
+    directory: std::path::PathBuf,
+    next_frame: u64,
+}
+
+// encodes and writes pngs on its own thread so saving frames doesn't stall rendering,
+// dropping it waits for the queued frames to be written
+#[cfg(not(target_arch = "wasm32"))]
+struct PngWriter {
+    sender: Option<std::sync::mpsc::Sender<(RgbaImage, std::path::PathBuf)>>,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl PngWriter {
+    fn new() -> Self {
+        Self { sender: None, thread: None }
+    }
+
+    fn callback(self: &mut Self, path: std::path::PathBuf) -> CaptureCallback {
+        let sender = self.sender.get_or_insert_with(|| {
+            let (sender, receiver) = std::sync::mpsc::channel::<(RgbaImage, std::path::PathBuf)>();
+            let thread = std::thread::Builder::new()
+                .name("png writer".to_string())
+                .spawn(move || {
+                    for (image, path) in receiver {
+                        save_png(image, path);
+                    }
+                })
+                .expect("failed to spawn the png writer thread");
+            self.thread = Some(thread);
+            sender
+        }).clone();
+
+        Box::new(move |img| {
+            if sender.send((img, path)).is_err() {
+                log::error!("png writer thread is gone, dropping a frame");
+            }
+        })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Drop for PngWriter {
+    fn drop(self: &mut Self) {
+        self.sender = None;
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+struct PendingReadback {
+    buffer: wgpu::Buffer,
+    width: u32,
+    height: u32,
+    padded_bytes_per_row: u32,
+    format: TextureFormat,
+    map_requested: bool,
+    map_result: Arc<Mutex<Option<Result<(), wgpu::BufferAsyncError>>>>,
+    callbacks: Vec<CaptureCallback>,
+}
+
+pub(crate) struct FrameCapture {
+    requests: Vec<CaptureCallback>,
+    #[cfg(not(target_arch = "wasm32"))]
+    recording: Option<PngSequence>,
+    pending: Vec<PendingReadback>,
+    target: Option<(wgpu::Texture, TextureView)>,
+    blit: Option<SurfaceBlit>,
+    // last so the pending callbacks holding its sender are dropped before it joins
+    #[cfg(not(target_arch = "wasm32"))]
+    png_writer: PngWriter,
+}
+
+impl FrameCapture {
+    pub(crate) fn new() -> Self {
+        Self {
+            requests: Vec::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            recording: None,
+            pending: Vec::new(),
+            target: None,
+            blit: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            png_writer: PngWriter::new(),
+        }
+    }
+
+    pub(crate) fn request(self: &mut Self, callback: CaptureCallback) {
+        self.requests.push(callback);
+    }
+
+    pub(crate) fn request_handle(self: &mut Self) -> ScreenshotHandle {
+        let handle = ScreenshotHandle { image: Arc::new(Mutex::new(None)) };
+        let image = handle.image.clone();
+        self.request(Box::new(move |img| { image.lock().replace(img); }));
+        handle
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn request_png(self: &mut Self, path: std::path::PathBuf) {
+        let callback = self.png_writer.callback(path);
+        self.request(callback);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn start_recording(self: &mut Self, directory: &std::path::Path) -> std::io::Result<()> {
+        std::fs::create_dir_all(directory)?;
+        self.recording = Some(PngSequence {
+            directory: directory.to_path_buf(),
+            next_frame: 0
+        });
+        Ok(())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn stop_recording(self: &mut Self) {
+        self.recording = None;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn is_recording(self: &Self) -> bool {
+        self.recording.is_some()
+    }
+
+    // everything that wants this frame, empty if the frame goes straight to the surface
+    pub(crate) fn take_callbacks(self: &mut Self) -> Vec<CaptureCallback> {
+        let mut callbacks: Vec<CaptureCallback> = self.requests.drain(..).collect();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(recording) = self.recording.as_mut() {
+            let path = recording.directory.join(format!("frame_{:06}.png", recording.next_frame));
+            recording.next_frame += 1;
+            callbacks.push(self.png_writer.callback(path));
+        }
+
+        callbacks
+    }
+
+    pub(crate) fn get_target_view(self: &mut Self, device: &wgpu::Device, config: &SurfaceConfiguration) -> TextureView {
+        let outdated = match &self.target {
+            Some((texture, _)) =>
+                texture.width() != config.width || texture.height() != config.height || texture.format() != config.format,
+            None => true,
+        };
+
+        if outdated {
+            let texture = device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("offscreen frame texture"),
+                size: wgpu::Extent3d {
+                    width: config.width.max(1),
+                    height: config.height.max(1),
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: config.format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+                view_formats: &[],
+            });
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            self.target = Some((texture, view));
+        }
+
+        self.target.as_ref().unwrap().1.clone()
+    }
+
+    pub(crate) fn get_target_texture(self: &Self) -> &wgpu::Texture {
+        &self.target.as_ref().expect("no offscreen target was rendered to").0
+    }
+
+    // draws the offscreen frame onto `surface_view`, for when it can't be copied there directly
+    pub(crate) fn blit_to(self: &mut Self, device: &wgpu::Device, encoder: &mut CommandEncoder, surface_view: &TextureView) {
+        let (texture, view) = self.target.as_ref().expect("no offscreen target was rendered to");
+        if self.blit.as_ref().is_none_or(|blit| blit.format != texture.format()) {
+            self.blit = Some(SurfaceBlit::new(device, texture.format()));
+        }
+        let blit = self.blit.as_ref().unwrap();
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Frame Blit Bind Group"),
+            layout: &blit.bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(view),
+            }],
+        });
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Frame Blit Pass"),
+            color_attachments: &[Some(
+                wgpu::RenderPassColorAttachment {
+                    view: surface_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+            ..Default::default()
+        });
+        render_pass.set_pipeline(&blit.pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    pub(crate) fn copy_to_readback(
+        self: &mut Self,
+        device: &wgpu::Device,
+        encoder: &mut CommandEncoder,
+        callbacks: Vec<CaptureCallback>
+    ) {
+        let texture = self.get_target_texture();
+        let (width, height) = (texture.width(), texture.height());
+        let format = texture.format();
+
+        let unpadded_bytes_per_row = 4 * width;
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(align) * align;
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Screenshot Readback Buffer"),
+            size: (padded_bytes_per_row * height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                aspect: wgpu::TextureAspect::All,
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        self.pending.push(PendingReadback {
+            buffer,
+            width,
+            height,
+            padded_bytes_per_row,
+            format,
+            map_requested: false,
+            map_result: Arc::new(Mutex::new(None)),
+            callbacks,
+        });
+    }
+
+    // has to run after the copy has been submitted
+    pub(crate) fn map_pending(self: &mut Self) {
+        for readback in self.pending.iter_mut().filter(|r| !r.map_requested) {
+            let map_result = readback.map_result.clone();
+            readback.buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+                map_result.lock().replace(result);
+            });
+            readback.map_requested = true;
+        }
+    }
+
+    pub(crate) fn poll(self: &mut Self, device: &wgpu::Device) {
+        if self.pending.is_empty() {
+            return;
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let _ = device.poll(wgpu::PollType::Poll);
+
+        let (done, still_pending): (Vec<_>, Vec<_>) = self.pending.drain(..)
+            .partition(|r| r.map_result.lock().is_some());
+        self.pending = still_pending;
+
+        for readback in done {
+            let map_result = readback.map_result.lock().take().unwrap();
+            match map_result {
+                Ok(()) => readback.deliver(),
+                Err(err) => log::error!("failed to read back frame: {err}"),
+            }
+        }
+    }
+}
+
+impl PendingReadback {
+    fn deliver(self: Self) {
+        let mut pixels = Vec::with_capacity((4 * self.width * self.height) as usize);
+        {
+            let mapped = self.buffer.slice(..).get_mapped_range();
+            for row in mapped.chunks_exact(self.padded_bytes_per_row as usize) {
+                pixels.extend_from_slice(&row[..(4 * self.width) as usize]);
+            }
+        }
+        self.buffer.unmap();
+
+        if matches!(self.format, TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb) {
+            for pixel in pixels.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+
+        let image = RgbaImage::from_raw(self.width, self.height, pixels)
+            .expect("readback buffer does not match the frame size");
+
+        let mut callbacks = self.callbacks;
+        let last = callbacks.pop();
+        for callback in callbacks {
+            callback(image.clone());
+        }
+        if let Some(callback) = last {
+            callback(image);
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn save_png(image: RgbaImage, path: std::path::PathBuf) {
+    if let Err(err) = image.save_with_format(&path, image::ImageFormat::Png) {
+        log::error!("failed to save frame to {}: {err}", path.display());
+    }
+}
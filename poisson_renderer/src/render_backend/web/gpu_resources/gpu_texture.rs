@@ -1,8 +1,18 @@
-use image::GenericImageView;
+use std::sync::{Arc, Weak};
+use image::RgbaImage;
 use anyhow::*;
 use wgpu::{BindGroup, BindGroupDescriptor, BindGroupLayout, BindGroupLayoutDescriptor};
+use crate::render_backend::render_interface::resources::{build_mip_chain, max_mip_level_count, GpuTextureTrait, TextureFormat, TextureOptions};
+use crate::render_backend::web::Device;
 use crate::render_backend::web::gpu_resources::interface::WgpuUniformResource;
 
+fn wgpu_format(format: TextureFormat) -> wgpu::TextureFormat {
+    match format {
+        TextureFormat::Rgba8UnormSrgb => wgpu::TextureFormat::Rgba8UnormSrgb,
+        TextureFormat::Rgba8Unorm => wgpu::TextureFormat::Rgba8Unorm,
+    }
+}
+
 pub struct Texture {
     pub texture: wgpu::Texture,
     pub view: wgpu::TextureView,
@@ -15,44 +25,40 @@ impl Texture {
         queue: &wgpu::Queue,
         img: &image::DynamicImage,
         label: Option<&str>
+    ) -> Result<Self> {
+        Self::from_image_with_options(device, queue, img, TextureOptions::default(), label)
+    }
+
+    pub fn from_image_with_options(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        img: &image::DynamicImage,
+        options: TextureOptions,
+        label: Option<&str>
     ) -> Result<Self> {
         let rgba = img.to_rgba8();
-        let dimensions = img.dimensions();
+        let mip_level_count = options.mip_level_count.clamp(1, max_mip_level_count(rgba.width(), rgba.height()));
 
         let size = wgpu::Extent3d {
-            width: dimensions.0,
-            height: dimensions.1,
+            width: rgba.width(),
+            height: rgba.height(),
             depth_or_array_layers: 1,
         };
         let texture = device.create_texture(
             &wgpu::TextureDescriptor {
                 label,
                 size,
-                mip_level_count: 1,
+                mip_level_count,
                 sample_count: 1,
                 dimension: wgpu::TextureDimension::D2,
-                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                format: wgpu_format(options.format),
                 usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
                 view_formats: &[],
             }
         );
-        queue.write_texture(
-            wgpu::TexelCopyTextureInfo {
-                aspect: wgpu::TextureAspect::All,
-                texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-            },
-            &rgba,
-            wgpu::TexelCopyBufferLayout {
-                offset: 0,
-                bytes_per_row: Some(4 * dimensions.0),
-                rows_per_image: Some(dimensions.1),
-            },
-            size,
-        );
 
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let mipmap_filter = if mip_level_count > 1 { wgpu::FilterMode::Linear } else { wgpu::FilterMode::Nearest };
         let sampler = device.create_sampler(
             &wgpu::SamplerDescriptor {
                 address_mode_u: wgpu::AddressMode::Repeat,
@@ -60,11 +66,55 @@ impl Texture {
                 address_mode_w: wgpu::AddressMode::Repeat,
                 mag_filter: wgpu::FilterMode::Linear,
                 min_filter: wgpu::FilterMode::Nearest,
-                mipmap_filter: wgpu::FilterMode::Nearest,
+                mipmap_filter,
                 ..Default::default()
             }
         );
-        Ok(Self { texture, view, sampler })
+
+        let ret = Self { texture, view, sampler };
+        ret.write_region(queue, 0, 0, &rgba);
+        Ok(ret)
+    }
+
+    // writes `img` at (`x`, `y`) in the first level and its downscaled copies into the others
+    pub fn write_region(self: &Self, queue: &wgpu::Queue, x: u32, y: u32, img: &RgbaImage) {
+        for (level, level_img) in build_mip_chain(img, self.texture.mip_level_count()).into_iter().enumerate() {
+            let level = level as u32;
+            let (origin_x, origin_y) = (x >> level, y >> level);
+
+            // regions that aren't aligned to the level can round past its edge
+            let width = level_img.width().min((self.texture.width() >> level).max(1).saturating_sub(origin_x));
+            let height = level_img.height().min((self.texture.height() >> level).max(1).saturating_sub(origin_y));
+            if width == 0 || height == 0 {
+                continue;
+            }
+            let level_img = if (width, height) == level_img.dimensions() {
+                level_img
+            } else {
+                image::imageops::crop_imm(&level_img, 0, 0, width, height).to_image()
+            };
+
+            let size = wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            };
+            queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    aspect: wgpu::TextureAspect::All,
+                    texture: &self.texture,
+                    mip_level: level,
+                    origin: wgpu::Origin3d { x: origin_x, y: origin_y, z: 0 },
+                },
+                &level_img,
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * width),
+                    rows_per_image: Some(height),
+                },
+                size,
+            );
+        }
     }
 
     pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
@@ -130,7 +180,10 @@ impl ShaderTexture {
         label: Option<&str>
     ) -> Result<Self> {
         let texture = Texture::from_image(device, queue, img, label)?;
+        Ok(Self::from_texture(device, texture))
+    }
 
+    pub fn from_texture(device: &wgpu::Device, texture: Texture) -> Self {
         let new_bind_group_layout = Self::create_bind_group_layout(device);
 
         let bind_group = device.create_bind_group(
@@ -150,7 +203,7 @@ impl ShaderTexture {
             }
         );
 
-        Ok(Self { texture, bind_group })
+        Self { texture, bind_group }
     }
 }
 
@@ -184,4 +237,44 @@ impl WgpuUniformResource for ShaderTexture {
     fn get_bind_group(self: &Self) -> &BindGroup {
         &self.bind_group
     }
+}
+
+// what `RenderBackend::create_texture` hands out, drawlets bind it directly
+// so every drawlet sharing it sees `update_region`
+pub struct WgpuTexture {
+    device: Weak<Device>,
+    format: TextureFormat,
+    pub shader_texture: ShaderTexture,
+}
+
+impl WgpuTexture {
+    pub fn new(device: &Arc<Device>, img: &image::DynamicImage, options: TextureOptions) -> Result<Self> {
+        let texture = Texture::from_image_with_options(&device.device, &device.queue, img, options, Some("Texture"))?;
+
+        Ok(Self {
+            device: Arc::downgrade(device),
+            format: options.format,
+            shader_texture: ShaderTexture::from_texture(&device.device, texture),
+        })
+    }
+}
+
+impl GpuTextureTrait for WgpuTexture {
+    fn get_width(&self) -> u32 {
+        self.shader_texture.texture.texture.width()
+    }
+    fn get_height(&self) -> u32 {
+        self.shader_texture.texture.texture.height()
+    }
+    fn get_format(&self) -> TextureFormat {
+        self.format
+    }
+    fn get_mip_level_count(&self) -> u32 {
+        self.shader_texture.texture.texture.mip_level_count()
+    }
+
+    fn update_region(&mut self, x: u32, y: u32, image: &RgbaImage) {
+        let device = self.device.upgrade().expect("texture outlived its device");
+        self.shader_texture.texture.write_region(&device.queue, x, y, image);
+    }
 }
\ No newline at end of file
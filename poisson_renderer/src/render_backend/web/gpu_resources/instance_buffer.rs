@@ -0,0 +1,107 @@
+use bytemuck::{Pod, Zeroable};
+use crate::render_backend::InstanceID;
+use crate::render_backend::render_interface::drawlets::instanced_mesh::{Instance, InstanceList};
+use crate::render_backend::web::Device;
+
+const INITIAL_CAPACITY: usize = 4;
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct InstanceRaw {
+    model: [[f32; 4]; 4],
+    tint: [f32; 4],
+}
+
+impl From<&Instance> for InstanceRaw {
+    fn from(instance: &Instance) -> Self {
+        Self {
+            model: instance.model.into(),
+            tint: instance.tint.into(),
+        }
+    }
+}
+
+// per instance vertex buffer, grows by doubling and only rewrites the instances that changed
+pub struct InstanceBuffer {
+    buffer: wgpu::Buffer,
+    capacity: usize,
+    instances: InstanceList,
+}
+
+impl InstanceBuffer {
+    pub fn new(device: &wgpu::Device) -> Self {
+        Self {
+            buffer: Self::create_buffer(device, INITIAL_CAPACITY),
+            capacity: INITIAL_CAPACITY,
+            instances: InstanceList::new(),
+        }
+    }
+
+    // model matrix columns then tint, each a `Float32x4` at the given locations
+    pub fn desc(attributes: &'static [wgpu::VertexAttribute; 5]) -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: size_of::<InstanceRaw>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes,
+        }
+    }
+
+    fn create_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Instance Buffer"),
+            size: (capacity * size_of::<InstanceRaw>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn write(self: &Self, device: &Device, start: usize, end: usize) {
+        let raw: Vec<InstanceRaw> = self.instances.as_slice()[start..end].iter()
+            .map(InstanceRaw::from)
+            .collect();
+        let offset = (start * size_of::<InstanceRaw>()) as wgpu::BufferAddress;
+        device.queue.write_buffer(&self.buffer, offset, bytemuck::cast_slice(&raw));
+    }
+
+    pub fn add(self: &mut Self, device: &Device, instance: Instance) -> InstanceID {
+        let id = self.instances.add(instance);
+        let len = self.instances.len();
+        if len > self.capacity {
+            self.capacity *= 2;
+            self.buffer = Self::create_buffer(&device.device, self.capacity);
+            self.write(device, 0, len);
+        } else {
+            self.write(device, len - 1, len);
+        }
+        id
+    }
+
+    pub fn remove(self: &mut Self, device: &Device, id: InstanceID) {
+        match self.instances.remove(id) {
+            Some(index) if index < self.instances.len() => self.write(device, index, index + 1),
+            Some(_) => {}
+            None => log::warn!("ignoring stale instance {:?}", id),
+        }
+    }
+
+    pub fn update(self: &mut Self, device: &Device, id: InstanceID, instance: Instance) {
+        match self.instances.update(id, instance) {
+            Some(index) => self.write(device, index, index + 1),
+            None => log::warn!("ignoring stale instance {:?}", id),
+        }
+    }
+
+    pub fn get(self: &Self, id: InstanceID) -> Option<Instance> {
+        self.instances.get(id).copied()
+    }
+
+    pub fn len(self: &Self) -> usize {
+        self.instances.len()
+    }
+
+    // `None` while there are no instances, wgpu doesn't take empty slices
+    pub fn slice(self: &Self) -> Option<wgpu::BufferSlice<'_>> {
+        let size = (self.instances.len() * size_of::<InstanceRaw>()) as wgpu::BufferAddress;
+        (size > 0).then(|| self.buffer.slice(..size))
+    }
+}
@@ -1,4 +1,5 @@
 pub mod gpu_texture;
 pub mod interface;
 pub mod gpu_mat4;
-pub mod gpu_vec4;
\ No newline at end of file
+pub mod gpu_vec4;
+pub mod instance_buffer;
\ No newline at end of file
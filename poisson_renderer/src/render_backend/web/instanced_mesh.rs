@@ -0,0 +1,450 @@
+use crate::AsAny;
+use std::any::Any;
+use std::borrow::Cow;
+use std::sync::{Arc, Weak};
+use cgmath::{Matrix4, Vector3};
+use poisson_macros::AsAny;
+use wgpu::SurfaceConfiguration;
+use crate::render_backend::{DrawletID, InstanceID, Mat4Ubo, RenderDrawlet, RenderPipeline};
+use crate::render_backend::registry::Registry;
+use crate::render_backend::render_interface::drawlets::{DrawletTrait, PipelineTrait, ReleaseQueue};
+use crate::render_backend::render_interface::drawlets::colored_mesh::ColoredVertex;
+use crate::render_backend::render_interface::drawlets::instanced_mesh::{Instance, InstancedColoredMesh, InstancedColoredMeshData, InstancedColoredMeshDrawletTrait, InstancedDrawletTrait, InstancedLitMesh, InstancedLitMeshData, InstancedLitMeshDrawletTrait};
+use crate::render_backend::render_interface::drawlets::lit_colored_mesh::NormalColoredVertex;
+use crate::render_backend::web::{Device, WgpuBuffer, WgpuDrawlet, WgpuDrawletDyn, WgpuPipeline, WgpuPipelineDyn, WgpuRenderObject};
+use crate::render_backend::web::gpu_resources::gpu_mat4::GpuMat4;
+use crate::render_backend::web::gpu_resources::gpu_texture::Texture;
+use crate::render_backend::web::gpu_resources::gpu_vec4::GpuVec4;
+use crate::render_backend::web::gpu_resources::instance_buffer::InstanceBuffer;
+use crate::render_backend::web::gpu_resources::interface::WgpuUniformResource;
+use crate::render_backend::web::per_vertex_impl::WgpuPerVertex;
+
+// the instance attributes come right after the vertex attributes
+const COLORED_INSTANCE_ATTRIBUTES: [wgpu::VertexAttribute; 5] =
+    wgpu::vertex_attr_array![2 => Float32x4, 3 => Float32x4, 4 => Float32x4, 5 => Float32x4, 6 => Float32x4];
+const LIT_INSTANCE_ATTRIBUTES: [wgpu::VertexAttribute; 5] =
+    wgpu::vertex_attr_array![3 => Float32x4, 4 => Float32x4, 5 => Float32x4, 6 => Float32x4, 7 => Float32x4];
+
+impl WgpuRenderObject for InstancedColoredMesh {
+    type Drawlet = InstancedColoredMeshDrawlet;
+    type Pipeline = InstancedColoredMeshPipeline;
+    type Data = InstancedColoredMeshData;
+}
+
+impl WgpuRenderObject for InstancedLitMesh {
+    type Drawlet = InstancedLitMeshDrawlet;
+    type Pipeline = InstancedLitMeshPipeline;
+    type Data = InstancedLitMeshData;
+}
+
+fn write_mat4(device: &Device, buffer: &wgpu::Buffer, mat4: Matrix4<f32>) {
+    let ubo = Mat4Ubo { data: mat4 };
+    let ubo_slice: &[u8] = unsafe {
+        std::slice::from_raw_parts(
+            (&ubo as *const Mat4Ubo) as *const u8, size_of::<Mat4Ubo>(),
+        )
+    };
+    device.queue.write_buffer(buffer, 0, ubo_slice);
+}
+
+fn write_vec3(device: &Device, buffer: &wgpu::Buffer, vec3: Vector3<f32>) {
+    let vec3_slice: &[f32; 3] = vec3.as_ref();
+    device.queue.write_buffer(buffer, 0, bytemuck::cast_slice(vec3_slice));
+}
+
+fn draw_instanced<V>(
+    render_pass: &mut wgpu::RenderPass,
+    vertex_buffer: &WgpuBuffer<V>,
+    index_buffer: &WgpuBuffer<u32>,
+    instances: &InstanceBuffer,
+) {
+    let Some(instance_buffer) = instances.slice() else {
+        return;
+    };
+    render_pass.set_vertex_buffer(0, vertex_buffer.slice());
+    render_pass.set_vertex_buffer(1, instance_buffer);
+    render_pass.set_index_buffer(index_buffer.slice(), wgpu::IndexFormat::Uint32);
+    render_pass.draw_indexed(0..index_buffer.num_elements(), 0, 0..instances.len() as u32);
+}
+
+fn create_render_pipeline(
+    device: &Device,
+    shader_u8: &[u8],
+    surface_config: &SurfaceConfiguration,
+    bind_group_layouts: &[&wgpu::BindGroupLayout],
+    buffers: &[wgpu::VertexBufferLayout],
+) -> wgpu::RenderPipeline {
+    let wgsl_str = str::from_utf8(shader_u8).unwrap();
+
+    let shader = device.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Shader"),
+        source: wgpu::ShaderSource::Wgsl(Cow::from(wgsl_str)),
+    });
+
+    let render_pipeline_layout =
+        device.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Render Pipeline Layout"),
+            bind_group_layouts,
+            push_constant_ranges: &[],
+        });
+
+    device.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Instanced Render Pipeline"),
+        layout: Some(&render_pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: Some("vertex"),
+            buffers,
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: Some("fragment"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format: surface_config.format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: Some(
+            wgpu::DepthStencilState {
+                format: Texture::DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+        multisample: wgpu::MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+        cache: None,
+    })
+}
+
+pub struct InstancedColoredMeshDrawlet {
+    device: Weak<Device>,
+    view_proj_buffer: GpuMat4,
+    vertex_buffer: rj::Own<WgpuBuffer<ColoredVertex>>,
+    index_buffer: rj::Own<WgpuBuffer<u32>>,
+    instances: InstanceBuffer,
+}
+
+impl InstancedColoredMeshDrawlet {
+    fn new(
+        device: &Arc<Device>,
+        init_data: &InstancedColoredMeshData
+    ) -> Self {
+        let vertex_buffer = init_data.mesh.vertex.buffer.downcast()
+            .expect("failed to cast vertex buffer to drawlet buffer type");
+
+        let index_buffer = init_data.mesh.index.buffer.downcast()
+            .expect("failed to cast index buffer to drawlet buffer type");
+
+        Self {
+            device: Arc::downgrade(device),
+            view_proj_buffer: GpuMat4::from_mat4(&device.device, &init_data.view_proj),
+            vertex_buffer,
+            index_buffer,
+            instances: InstanceBuffer::new(&device.device),
+        }
+    }
+}
+
+impl RenderDrawlet for InstancedColoredMeshDrawlet {
+    type Data = InstancedColoredMeshData;
+}
+
+impl WgpuDrawlet for InstancedColoredMeshDrawlet {
+    fn draw(self: &Self, render_pass: &mut wgpu::RenderPass) {
+        render_pass.set_bind_group(0, self.view_proj_buffer.get_bind_group(), &[]);
+        draw_instanced(
+            render_pass,
+            &self.vertex_buffer.access(),
+            &self.index_buffer.access(),
+            &self.instances);
+    }
+}
+
+impl DrawletTrait<InstancedColoredMesh> for InstancedColoredMeshDrawlet {}
+
+impl InstancedDrawletTrait for InstancedColoredMeshDrawlet {
+    fn add_instance(self: &mut Self, instance: Instance) -> InstanceID {
+        self.instances.add(&self.device.upgrade().unwrap(), instance)
+    }
+
+    fn remove_instance(self: &mut Self, id: InstanceID) {
+        self.instances.remove(&self.device.upgrade().unwrap(), id);
+    }
+
+    fn update_instance(self: &mut Self, id: InstanceID, instance: Instance) {
+        self.instances.update(&self.device.upgrade().unwrap(), id, instance);
+    }
+
+    fn get_instance(self: &Self, id: InstanceID) -> Option<Instance> {
+        self.instances.get(id)
+    }
+
+    fn instance_count(self: &Self) -> usize {
+        self.instances.len()
+    }
+}
+
+impl InstancedColoredMeshDrawletTrait for InstancedColoredMeshDrawlet {
+    fn set_view_proj(self: &mut Self, view_proj: Matrix4<f32>) {
+        write_mat4(&self.device.upgrade().unwrap(), &self.view_proj_buffer.buffer, view_proj);
+    }
+}
+
+#[derive(AsAny)]
+pub struct InstancedColoredMeshPipeline {
+    device: Weak<Device>,
+    render_pipeline: wgpu::RenderPipeline,
+    drawlets: Registry<DrawletID, InstancedColoredMeshDrawlet>,
+    released: ReleaseQueue<DrawletID>,
+}
+
+impl WgpuPipelineDyn for InstancedColoredMeshPipeline {
+    fn get_pipeline(self: &Self) -> &wgpu::RenderPipeline {
+        &self.render_pipeline
+    }
+    fn get_instances(self: &Self) -> Box<dyn Iterator<Item=&dyn WgpuDrawletDyn> + '_> {
+        Box::new(self.drawlets.values().map(|drawlet| drawlet as &dyn WgpuDrawletDyn))
+    }
+
+    fn collect_released(self: &mut Self) {
+        for id in self.released.take() {
+            self.drawlets.remove(id);
+        }
+    }
+}
+
+impl RenderPipeline<InstancedColoredMesh> for InstancedColoredMeshPipeline {}
+
+impl WgpuPipeline<InstancedColoredMesh> for InstancedColoredMeshPipeline {
+    fn create_drawlet(self: &mut Self, init_data: InstancedColoredMeshData) -> DrawletID {
+        let new_drawlet = InstancedColoredMeshDrawlet::new(
+            &self.device.upgrade().unwrap(),
+            &init_data);
+
+        self.drawlets.insert(new_drawlet)
+    }
+
+    fn new(device: &Arc<Device>, shader_u8: &[u8], surface_config: &SurfaceConfiguration) -> Self
+    where Self: Sized
+    {
+        let view_proj_bind_group_layout = GpuMat4::create_bind_group_layout(&device.device);
+
+        let render_pipeline = create_render_pipeline(
+            device,
+            shader_u8,
+            surface_config,
+            &[&view_proj_bind_group_layout],
+            &[ColoredVertex::desc(), InstanceBuffer::desc(&COLORED_INSTANCE_ATTRIBUTES)]);
+
+        Self {
+            device: Arc::downgrade(device),
+            render_pipeline,
+            drawlets: Registry::new(),
+            released: ReleaseQueue::default(),
+        }
+    }
+}
+
+impl PipelineTrait<InstancedColoredMesh> for InstancedColoredMeshPipeline {
+    fn create_drawlet(&mut self, init_data: InstancedColoredMeshData) -> DrawletID {
+        WgpuPipeline::create_drawlet(self, init_data)
+    }
+
+    fn remove_drawlet(&mut self, id: DrawletID) {
+        let _ = self.drawlets.remove(id);
+    }
+
+    fn get_drawlet_mut(&mut self, id: DrawletID) -> Option<&mut (dyn InstancedColoredMeshDrawletTrait + 'static)> {
+        self.drawlets.get_mut(id).map(|drawlet| drawlet as &mut dyn InstancedColoredMeshDrawletTrait)
+    }
+
+    fn get_drawlet_releases(self: &Self) -> ReleaseQueue<DrawletID> {
+        self.released.clone()
+    }
+}
+
+pub struct InstancedLitMeshDrawlet {
+    device: Weak<Device>,
+    view_proj_buffer: GpuMat4,
+    light_buffer: GpuVec4,
+    view_buffer: GpuVec4,
+    vertex_buffer: rj::Own<WgpuBuffer<NormalColoredVertex>>,
+    index_buffer: rj::Own<WgpuBuffer<u32>>,
+    instances: InstanceBuffer,
+}
+
+impl InstancedLitMeshDrawlet {
+    fn new(
+        device: &Arc<Device>,
+        init_data: &InstancedLitMeshData
+    ) -> Self {
+        let vertex_buffer = init_data.mesh.vertex.buffer.downcast()
+            .expect("failed to cast vertex buffer to drawlet buffer type");
+
+        let index_buffer = init_data.mesh.index.buffer.downcast()
+            .expect("failed to cast index buffer to drawlet buffer type");
+
+        Self {
+            device: Arc::downgrade(device),
+            view_proj_buffer: GpuMat4::from_mat4(&device.device, &init_data.view_proj),
+            light_buffer: GpuVec4::from_vec4(&device.device, &init_data.light_dir),
+            view_buffer: GpuVec4::from_vec4(&device.device, &init_data.view_dir),
+            vertex_buffer,
+            index_buffer,
+            instances: InstanceBuffer::new(&device.device),
+        }
+    }
+}
+
+impl RenderDrawlet for InstancedLitMeshDrawlet {
+    type Data = InstancedLitMeshData;
+}
+
+impl WgpuDrawlet for InstancedLitMeshDrawlet {
+    fn draw(self: &Self, render_pass: &mut wgpu::RenderPass) {
+        render_pass.set_bind_group(0, self.view_proj_buffer.get_bind_group(), &[]);
+        render_pass.set_bind_group(1, self.light_buffer.get_bind_group(), &[]);
+        render_pass.set_bind_group(2, self.view_buffer.get_bind_group(), &[]);
+        draw_instanced(
+            render_pass,
+            &self.vertex_buffer.access(),
+            &self.index_buffer.access(),
+            &self.instances);
+    }
+}
+
+impl DrawletTrait<InstancedLitMesh> for InstancedLitMeshDrawlet {}
+
+impl InstancedDrawletTrait for InstancedLitMeshDrawlet {
+    fn add_instance(self: &mut Self, instance: Instance) -> InstanceID {
+        self.instances.add(&self.device.upgrade().unwrap(), instance)
+    }
+
+    fn remove_instance(self: &mut Self, id: InstanceID) {
+        self.instances.remove(&self.device.upgrade().unwrap(), id);
+    }
+
+    fn update_instance(self: &mut Self, id: InstanceID, instance: Instance) {
+        self.instances.update(&self.device.upgrade().unwrap(), id, instance);
+    }
+
+    fn get_instance(self: &Self, id: InstanceID) -> Option<Instance> {
+        self.instances.get(id)
+    }
+
+    fn instance_count(self: &Self) -> usize {
+        self.instances.len()
+    }
+}
+
+impl InstancedLitMeshDrawletTrait for InstancedLitMeshDrawlet {
+    fn set_view_proj(self: &mut Self, view_proj: Matrix4<f32>) {
+        write_mat4(&self.device.upgrade().unwrap(), &self.view_proj_buffer.buffer, view_proj);
+    }
+
+    fn set_light_dir(self: &mut Self, light_dir: Vector3<f32>) {
+        write_vec3(&self.device.upgrade().unwrap(), &self.light_buffer.buffer, light_dir);
+    }
+
+    fn set_view_dir(self: &mut Self, view_dir: Vector3<f32>) {
+        write_vec3(&self.device.upgrade().unwrap(), &self.view_buffer.buffer, view_dir);
+    }
+}
+
+#[derive(AsAny)]
+pub struct InstancedLitMeshPipeline {
+    device: Weak<Device>,
+    render_pipeline: wgpu::RenderPipeline,
+    drawlets: Registry<DrawletID, InstancedLitMeshDrawlet>,
+    released: ReleaseQueue<DrawletID>,
+}
+
+impl WgpuPipelineDyn for InstancedLitMeshPipeline {
+    fn get_pipeline(self: &Self) -> &wgpu::RenderPipeline {
+        &self.render_pipeline
+    }
+    fn get_instances(self: &Self) -> Box<dyn Iterator<Item=&dyn WgpuDrawletDyn> + '_> {
+        Box::new(self.drawlets.values().map(|drawlet| drawlet as &dyn WgpuDrawletDyn))
+    }
+
+    fn collect_released(self: &mut Self) {
+        for id in self.released.take() {
+            self.drawlets.remove(id);
+        }
+    }
+}
+
+impl RenderPipeline<InstancedLitMesh> for InstancedLitMeshPipeline {}
+
+impl WgpuPipeline<InstancedLitMesh> for InstancedLitMeshPipeline {
+    fn create_drawlet(self: &mut Self, init_data: InstancedLitMeshData) -> DrawletID {
+        let new_drawlet = InstancedLitMeshDrawlet::new(
+            &self.device.upgrade().unwrap(),
+            &init_data);
+
+        self.drawlets.insert(new_drawlet)
+    }
+
+    fn new(device: &Arc<Device>, shader_u8: &[u8], surface_config: &SurfaceConfiguration) -> Self
+    where Self: Sized
+    {
+        let view_proj_bind_group_layout = GpuMat4::create_bind_group_layout(&device.device);
+        let light_dir_bind_group_layout = GpuVec4::create_bind_group_layout(&device.device);
+        let view_dir_bind_group_layout = GpuVec4::create_bind_group_layout(&device.device);
+
+        let render_pipeline = create_render_pipeline(
+            device,
+            shader_u8,
+            surface_config,
+            &[
+                &view_proj_bind_group_layout,
+                &light_dir_bind_group_layout,
+                &view_dir_bind_group_layout,
+            ],
+            &[NormalColoredVertex::desc(), InstanceBuffer::desc(&LIT_INSTANCE_ATTRIBUTES)]);
+
+        Self {
+            device: Arc::downgrade(device),
+            render_pipeline,
+            drawlets: Registry::new(),
+            released: ReleaseQueue::default(),
+        }
+    }
+}
+
+impl PipelineTrait<InstancedLitMesh> for InstancedLitMeshPipeline {
+    fn create_drawlet(&mut self, init_data: InstancedLitMeshData) -> DrawletID {
+        WgpuPipeline::create_drawlet(self, init_data)
+    }
+
+    fn remove_drawlet(&mut self, id: DrawletID) {
+        let _ = self.drawlets.remove(id);
+    }
+
+    fn get_drawlet_mut(&mut self, id: DrawletID) -> Option<&mut (dyn InstancedLitMeshDrawletTrait + 'static)> {
+        self.drawlets.get_mut(id).map(|drawlet| drawlet as &mut dyn InstancedLitMeshDrawletTrait)
+    }
+
+    fn get_drawlet_releases(self: &Self) -> ReleaseQueue<DrawletID> {
+        self.released.clone()
+    }
+}
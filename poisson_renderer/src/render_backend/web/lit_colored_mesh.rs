@@ -1,7 +1,6 @@
 use crate::AsAny;
 use std::any::Any;
 use std::borrow::Cow;
-use std::collections::HashMap;
 use std::ops::Deref;
 use std::sync::{Arc,Weak};
 use cgmath::{Matrix4, Vector3};
@@ -10,9 +9,9 @@ use parking_lot::Mutex;
 use wgpu::{ SurfaceConfiguration};
 use wgpu::util::{DeviceExt, RenderEncoder};
 use poisson_macros::AsAny;
-use rj::Own;
 use crate::render_backend::{DrawletID, PassID, Mat4Ubo, PipelineID, RenderDrawlet, RenderPipeline};
-use crate::render_backend::render_interface::drawlets::{DrawletHandle, DrawletTrait, PipelineTrait};
+use crate::render_backend::render_interface::drawlets::{DrawletTrait, PipelineTrait, ReleaseQueue};
+use crate::render_backend::registry::Registry;
 use crate::render_backend::render_interface::drawlets::colored_mesh::{ColoredMesh, ColoredMeshData, ColoredMeshDrawletTrait, ColoredVertex};
 use crate::render_backend::render_interface::drawlets::lit_colored_mesh::{LitColoredMesh, LitColoredMeshData, LitColoredMeshDrawletTrait, NormalColoredVertex};
 use crate::render_backend::web::{WgpuBuffer, Device, WgpuDrawlet, WgpuDrawletDyn, WgpuPipeline, WgpuPipelineDyn, WgpuRenderObject, WgpuRenderPass};
@@ -30,7 +29,6 @@ impl WgpuRenderObject for LitColoredMesh {
 
 pub struct LitColoredMeshDrawlet {
     device: Weak<Device>,
-    num_indices: u32,
     mvp_buffer: GpuMat4,
     light_buffer: GpuVec4,
     view_buffer: GpuVec4,
@@ -55,7 +53,6 @@ impl LitColoredMeshDrawlet {
 
         Self {
             device: Arc::downgrade(device),
-            num_indices: init_data.mesh.index.get_count() as u32,
             mvp_buffer: uniform_buffer,
             light_buffer: light_dir,
             view_buffer: view_dir,
@@ -76,8 +73,9 @@ impl WgpuDrawlet for LitColoredMeshDrawlet {
         render_pass.set_bind_group(2, self.view_buffer.get_bind_group(), &[]);
 
         render_pass.set_vertex_buffer(0, self.vertex_buffer.access().slice());
-        render_pass.set_index_buffer(self.index_buffer.access().slice(), wgpu::IndexFormat::Uint32);
-        render_pass.draw_indexed(0..self.num_indices, 0, 0..1);
+        let index_buffer = self.index_buffer.access();
+        render_pass.set_index_buffer(index_buffer.slice(), wgpu::IndexFormat::Uint32);
+        render_pass.draw_indexed(0..index_buffer.num_elements(), 0, 0..1);
     }
 }
 
@@ -85,35 +83,34 @@ impl WgpuDrawlet for LitColoredMeshDrawlet {
 pub struct LitColoredMeshPipeline {
     device: Weak<Device>,
     render_pipeline: wgpu::RenderPipeline,
-    drawlets: HashMap<DrawletID, rj::Own<LitColoredMeshDrawlet>>
+    drawlets: Registry<DrawletID, LitColoredMeshDrawlet>,
+    released: ReleaseQueue<DrawletID>,
 }
 
 impl WgpuPipelineDyn for LitColoredMeshPipeline {
     fn get_pipeline(self: &Self) -> &wgpu::RenderPipeline {
         &self.render_pipeline
     }
-    fn get_instances(self: &Self) -> Box<dyn Iterator<Item=rj::Own<dyn WgpuDrawletDyn>> + '_> {
-        Box::new(self.drawlets.iter().map(
-            |(_, x)|
-                rj::Own::<dyn WgpuDrawletDyn>::from_inner(x.clone().into_inner())
-        ))
+    fn get_instances(self: &Self) -> Box<dyn Iterator<Item=&dyn WgpuDrawletDyn> + '_> {
+        Box::new(self.drawlets.values().map(|drawlet| drawlet as &dyn WgpuDrawletDyn))
+    }
+
+    fn collect_released(self: &mut Self) {
+        for id in self.released.take() {
+            self.drawlets.remove(id);
+        }
     }
 }
 
 impl RenderPipeline<LitColoredMesh> for LitColoredMeshPipeline {}
 
 impl WgpuPipeline<LitColoredMesh> for LitColoredMeshPipeline {
-    fn create_drawlet(self: &mut Self, init_data: LitColoredMeshData) -> (DrawletID, rj::Own<LitColoredMeshDrawlet>) {
-        let id = <Self as RenderPipeline<ColoredMesh>>::get_drawlet_id();
+    fn create_drawlet(self: &mut Self, init_data: LitColoredMeshData) -> DrawletID {
         let new_drawlet = LitColoredMeshDrawlet::new(
             &self.device.upgrade().unwrap(),
             &init_data);
 
-        let own = rj::Own::new(new_drawlet);
-
-        self.drawlets.insert(id, own.clone());
-
-        (id, own)
+        self.drawlets.insert(new_drawlet)
     }
 
     fn new(device: &Arc<Device>, shader_u8: &[u8], surface_config: &SurfaceConfiguration) -> Self
@@ -189,12 +186,11 @@ impl WgpuPipeline<LitColoredMesh> for LitColoredMeshPipeline {
             cache: None,
         });
 
-        let drawlets = HashMap::new();
-
         Self {
             device: Arc::downgrade(device),
             render_pipeline,
-            drawlets
+            drawlets: Registry::new(),
+            released: ReleaseQueue::default(),
         }
     }
 }
@@ -231,14 +227,20 @@ impl LitColoredMeshDrawlet {
 }
 
 impl PipelineTrait<LitColoredMesh> for LitColoredMeshPipeline {
-    fn create_drawlet(&mut self, init_data: LitColoredMeshData) -> (DrawletID, Own<(dyn LitColoredMeshDrawletTrait + 'static)>) {
-        let (id, own) = WgpuPipeline::create_drawlet(self, init_data);
+    fn create_drawlet(&mut self, init_data: LitColoredMeshData) -> DrawletID {
+        WgpuPipeline::create_drawlet(self, init_data)
+    }
+
+    fn remove_drawlet(&mut self, id: DrawletID) {
+        let _ = self.drawlets.remove(id);
+    }
 
-        (id, own.upcast())
+    fn get_drawlet_mut(&mut self, id: DrawletID) -> Option<&mut (dyn LitColoredMeshDrawletTrait + 'static)> {
+        self.drawlets.get_mut(id).map(|drawlet| drawlet as &mut dyn LitColoredMeshDrawletTrait)
     }
 
-    fn remove_drawlet(&mut self, drawlet: DrawletHandle<LitColoredMesh>) {
-        let _ = self.drawlets.remove(&drawlet.id);
+    fn get_drawlet_releases(self: &Self) -> ReleaseQueue<DrawletID> {
+        self.released.clone()
     }
 }
 
@@ -1,22 +1,26 @@
 pub mod textured_mesh;
-mod gpu_resources;
-mod per_vertex_impl;
+pub mod gpu_resources;
+pub mod per_vertex_impl;
 pub mod colored_mesh;
-mod lit_colored_mesh;
+pub mod lit_colored_mesh;
+pub mod instanced_mesh;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod reflected_mesh;
+mod frame_capture;
 
-use std::collections::HashMap;
+use std::any::{Any, TypeId};
 use std::fs;
 use std::io::Write;
 use std::marker::PhantomData;
-use std::sync::{Arc};
+use std::sync::{Arc, Weak};
 use winit::window::Window;
-use crate::render_backend::{PipelineID, RenderBackend, RenderDrawlet, PassID, RenderPipeline, DrawletID};
+use crate::render_backend::{PipelineID, RenderBackend, RenderDrawlet, PassID, RenderPipeline, DrawletID, EguiUiShow};
+use crate::render_backend::registry::Registry;
 use wgpu;
 use winit::dpi::PhysicalSize;
 use cfg_if::cfg_if;
 use egui_wgpu::ScreenDescriptor;
-use image::EncodableLayout;
-use wgpu::util::DeviceExt;
+use image::{DynamicImage, EncodableLayout};
 use parking_lot::Mutex;
 use wgpu::{BufferSlice, CommandEncoder, SurfaceConfiguration, TextureFormat, TextureView};
 use winit::event::{WindowEvent};
@@ -24,13 +28,11 @@ use winit::event::{WindowEvent};
 #[cfg(target_arch = "wasm32")]
 use winit::platform::web::WindowExtWebSys;
 
-use crate::{AsAny};
+use crate::{AsAny, PresentMode};
 use crate::render_backend::render_interface::{RenderObject};
-use crate::render_backend::web::gpu_resources::gpu_texture::Texture;
-
-pub trait EguiUiShow {
-    fn show(&mut self, ctx: &egui::Context);
-}
+use crate::render_backend::web::gpu_resources::gpu_texture::{Texture, WgpuTexture};
+use crate::render_backend::web::frame_capture::FrameCapture;
+pub use crate::render_backend::web::frame_capture::ScreenshotHandle;
 
 pub trait WgpuRenderObject: RenderObject + Sized {
     type Drawlet: WgpuDrawlet;
@@ -45,7 +47,7 @@ WgpuPipeline<RenObjType: WgpuRenderObject>: RenderPipeline<RenObjType> + WgpuPip
     fn create_drawlet(
         self: &mut Self,
         init_data: <<RenObjType as WgpuRenderObject>::Drawlet as RenderDrawlet>::Data
-    ) -> (DrawletID, rj::Own<<RenObjType as WgpuRenderObject>::Drawlet>);
+    ) -> DrawletID;
 
     fn new(
         device: &Arc<Device>,
@@ -56,7 +58,8 @@ WgpuPipeline<RenObjType: WgpuRenderObject>: RenderPipeline<RenObjType> + WgpuPip
 
 pub trait WgpuPipelineDyn: AsAny {
     fn get_pipeline(self: &Self) -> &wgpu::RenderPipeline;
-    fn get_instances(self: &Self) -> Box<dyn Iterator<Item=rj::Own<dyn WgpuDrawletDyn>> + '_>;
+    fn get_instances(self: &Self) -> Box<dyn Iterator<Item=&dyn WgpuDrawletDyn> + '_>;
+    fn collect_released(self: &mut Self);
 }
 
 pub trait WgpuDrawlet: RenderDrawlet {
@@ -76,21 +79,52 @@ pub struct WgpuRenderPass {
     device: std::sync::Weak<Device>,
     surface_config: SurfaceConfiguration,
     depth_stencil: Texture,
-    pipelines: HashMap<PipelineID, rj::Own<dyn WgpuPipelineDyn>>
+    pipelines: Registry<PipelineID, Box<dyn WgpuPipelineDyn>>,
+    released: ReleaseQueue<PipelineID>,
+    pipeline_factories: PipelineFactories<WgpuRenderPass>,
 }
 
-impl<T: WgpuRenderObject> CreatePipeline<T> for WgpuRenderPass {
-    fn create_pipeline(&mut self, shader_path: &str, shader_text: &str) -> (PipelineID, Own<(dyn PipelineTrait<T> + 'static)>) {
-        let (id, pipe) = self.create_pipeline::<T>(shader_path, shader_text);
-        (id, pipe.upcast())
-    }
+fn build_pipeline<RenObjType: WgpuRenderObject + 'static>(pass: &mut WgpuRenderPass, shader_path: &str, shader_text: &str)
+    -> Result<(PipelineID, Box<dyn Any>), PipelineError>
+{
+    let id = pass.create_pipeline::<RenObjType>(shader_path, shader_text);
+    Ok(erase_pipeline::<RenObjType, RenObjType::Pipeline>(id))
+}
+
+// compiles `<shader_path>.slang` and writes the wgsl next to it, the web build loads that file instead
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn compile_slang(shader_path: &str) -> slang_refl::LinkedProgram {
+    let compiler = slang_refl::Compiler::new_wgsl_compiler();
+    let linked_program = compiler.linked_program_from_file((shader_path.to_owned() + ".slang").as_str());
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(shader_path.to_owned() + ".wgsl").unwrap();
+
+    file.write_all(linked_program.get_u8()).unwrap();
+
+    linked_program
 }
 
 impl PassTrait for WgpuRenderPass {
+    fn create_erased_pipeline(&mut self, render_object: TypeId, shader_path: &str, shader_text: &str)
+        -> Result<(PipelineID, Box<dyn Any>), PipelineError>
+    {
+        self.pipeline_factories.clone().create_pipeline(self, render_object, shader_path, shader_text)
+    }
+
+    fn get_pipeline_mut(&mut self, id: PipelineID) -> Option<&mut dyn Any> {
+        self.pipelines.get_mut(id).map(|pipeline| pipeline.as_any_mut())
+    }
+
+    fn get_pipeline_releases(self: &Self) -> ReleaseQueue<PipelineID> {
+        self.released.clone()
+    }
 }
 
 impl WgpuRenderPass {
-    fn new(device: &Arc<Device>, surface_configuration: &SurfaceConfiguration) -> Self {
+    fn new(device: &Arc<Device>, surface_configuration: &SurfaceConfiguration, pipeline_factories: &PipelineFactories<WgpuRenderPass>) -> Self {
         Self {
             device: Arc::downgrade(device),
             surface_config: surface_configuration.clone(),
@@ -99,55 +133,37 @@ impl WgpuRenderPass {
                     &device.device,
                     surface_configuration,
                     "depth stencil texture"),
-            pipelines: HashMap::new()
+            pipelines: Registry::new(),
+            released: ReleaseQueue::default(),
+            pipeline_factories: pipeline_factories.clone(),
         }
     }
 
-    pub fn create_pipeline<RenObjType: WgpuRenderObject>(self: &mut Self, shader_path: &str, shader_text: &str) -> (PipelineID, rj::Own<<RenObjType as WgpuRenderObject>::Pipeline>) {
-        let owned_str = shader_path.to_owned();
-        let wgsl_path = owned_str.clone() + ".wgsl";
-        #[cfg(not(target_arch="wasm32"))]
-        {
-            let compiler = slang_refl::Compiler::new_wgsl_compiler();
-            let slang_path = owned_str + ".slang";
-            let linked_program = compiler.linked_program_from_file(slang_path.as_str());
-            let compiled_shader = linked_program.get_u8();
-            let mut file = std::fs::OpenOptions::new()
-                .create(true)
-                .write(true)
-                .truncate(true)
-                .open(wgsl_path.clone()).unwrap();
-
-            file.write_all(compiled_shader).unwrap()
-        }
+    pub fn create_pipeline<RenObjType: WgpuRenderObject>(self: &mut Self, shader_path: &str, shader_text: &str) -> PipelineID {
         let wgsl_code;
 
         cfg_if! {
             if #[cfg(not(target_arch="wasm32"))] {
-                wgsl_code = fs::read(wgsl_path).unwrap();
+                wgsl_code = compile_slang(shader_path).get_u8().to_vec();
             } else {
                 wgsl_code = shader_text.to_owned();
             }
         }
 
-        let inner = Arc::new(Mutex::new(RenObjType::Pipeline::new(
+        let pipeline = RenObjType::Pipeline::new(
             &self.device.upgrade().as_ref().unwrap(), wgsl_code.as_bytes(), &self.surface_config
-        )));
-
-        let pipeline: rj::Own<dyn WgpuPipelineDyn + 'static> =
-            rj::Own::<dyn WgpuPipelineDyn>::from_inner(inner.clone());
-
-        let pipeline_id: PipelineID = Self::get_pipeline_id();
-
-        self.pipelines.insert(pipeline_id.clone(), pipeline.clone());
+        );
 
-        (pipeline_id.clone(), rj::Own::from_inner(inner))
+        self.pipelines.insert(Box::new(pipeline))
     }
 
-    pub fn get_pipeline_id() -> PipelineID {
-        use std::sync::atomic::{AtomicUsize, Ordering};
-        static COUNTER:AtomicUsize = AtomicUsize::new(1);
-        PipelineID(COUNTER.fetch_add(1, Ordering::Relaxed))
+    fn collect_released(self: &mut Self) {
+        for id in self.released.take() {
+            self.pipelines.remove(id);
+        }
+        for pipeline in self.pipelines.values_mut() {
+            pipeline.collect_released();
+        }
     }
 
     fn render(self: &Self, encoder: &mut CommandEncoder, target_view: &TextureView) {
@@ -178,10 +194,10 @@ impl WgpuRenderPass {
             ..Default::default()
         });
 
-        for (_, pipeline) in &self.pipelines {
-            { render_pass.set_pipeline(pipeline.access().get_pipeline()); }
-            for drawlet in pipeline.access().get_instances() {
-                drawlet.access().draw(&mut render_pass);
+        for pipeline in self.pipelines.values() {
+            render_pass.set_pipeline(pipeline.get_pipeline());
+            for drawlet in pipeline.get_instances() {
+                drawlet.draw(&mut render_pass);
             }
         }
     }
@@ -201,26 +217,30 @@ pub struct WgpuRenderBackend {
     size: winit::dpi::PhysicalSize<u32>,
     size_changed: bool,
     max_size: winit::dpi::PhysicalSize<u32>,
-    render_passes: HashMap<PassID, rj::Own<WgpuRenderPass>>,
+    render_passes: Arc<Mutex<Registry<PassID, WgpuRenderPass>>>,
+    released_passes: ReleaseQueue<PassID>,
+    pipeline_factories: PipelineFactories<WgpuRenderPass>,
     egui_renderer: EguiRenderer,
+    frame_capture: FrameCapture,
+    surface_copy_dst: bool,
 }
 
 
 impl RenderBackend for WgpuRenderBackend {
     const PERSPECTIVE_ALIGNMENT: [f32; 3] = [1f32, 1f32, -1f32];
 
-    fn init(backend_clone: Arc<Mutex<Option<Self>>>, window: Arc<Window>) where Self: Sized
+    fn init(backend_clone: Arc<Mutex<Option<Self>>>, window: Arc<Window>, present_mode: PresentMode) where Self: Sized
     {
         cfg_if::cfg_if! {
             if #[cfg(target_arch="wasm32")] {
                 log::info!("running wasm32 backend creation");
                 wasm_bindgen_futures::spawn_local(async move {
-                    let new_backend = WgpuRenderBackend::new(&window).await;
+                    let new_backend = WgpuRenderBackend::new(&window, present_mode).await;
                     let mut locked_backend = backend_clone.lock();
                     *locked_backend = Some(new_backend);
                 });
             } else {
-                let render_backend = pollster::block_on(WgpuRenderBackend::new(&window));
+                let render_backend = pollster::block_on(WgpuRenderBackend::new(&window, present_mode));
                 backend_clone.lock().replace(render_backend);
             }
         }
@@ -228,6 +248,7 @@ impl RenderBackend for WgpuRenderBackend {
 
     fn render(self: &mut Self, window: &Arc<Window>, egui_show_obj: &mut dyn EguiUiShow) {
         self.resize_surface_if_needed(window);
+        self.collect_released();
 
         let screen_descriptor = ScreenDescriptor {
             size_in_pixels: [self.config.width, self.config.height],
@@ -235,10 +256,19 @@ impl RenderBackend for WgpuRenderBackend {
         };
 
         let output = self.surface.get_current_texture().unwrap();
-        let view = output
+        let surface_view = output
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
 
+        // captured frames are drawn offscreen first, then copied to the surface and read back
+        let capture_callbacks = self.frame_capture.take_callbacks();
+        let capturing = !capture_callbacks.is_empty();
+        let view = if capturing {
+            self.frame_capture.get_target_view(&self.device.device, &self.config)
+        } else {
+            surface_view.clone()
+        };
+
         let mut encoder = self
             .device.device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
@@ -246,8 +276,9 @@ impl RenderBackend for WgpuRenderBackend {
             });
 
         {
-            for render_pass in self.render_passes.values() {
-                render_pass.access().render(&mut encoder, &view);
+            // the passes are unlocked again before the ui runs, it may create drawlets
+            for render_pass in self.render_passes.lock().values() {
+                render_pass.render(&mut encoder, &view);
             }
 
             self.egui_renderer.begin_frame(window);
@@ -264,8 +295,28 @@ impl RenderBackend for WgpuRenderBackend {
             );
         }
 
+        if capturing {
+            if self.surface_copy_dst {
+                encoder.copy_texture_to_texture(
+                    self.frame_capture.get_target_texture().as_image_copy(),
+                    output.texture.as_image_copy(),
+                    wgpu::Extent3d {
+                        width: self.config.width,
+                        height: self.config.height,
+                        depth_or_array_layers: 1,
+                    },
+                );
+            } else {
+                self.frame_capture.blit_to(&self.device.device, &mut encoder, &surface_view);
+            }
+            self.frame_capture.copy_to_readback(&self.device.device, &mut encoder, capture_callbacks);
+        }
+
         self.device.queue.submit(Some(encoder.finish()));
+        self.frame_capture.map_pending();
         output.present();
+
+        self.frame_capture.poll(&self.device.device);
     }
 
     fn process_event(self: &mut Self, window: &Window, event: &WindowEvent) {
@@ -287,57 +338,40 @@ impl RenderBackend for WgpuRenderBackend {
         self.size_changed = true;
     }
 
-    fn create_index_buffer(self: &Self, data: &[u32]) -> GpuBufferHandle<u32> {
-        let index_data: &[u8] = unsafe {
-            std::slice::from_raw_parts(
-                data.as_ptr() as *const u8, data.len() * size_of::<u32>()
-            )
-        };
-
-        let index_buffer = self.device.device.create_buffer_init(
-            &wgpu::util::BufferInitDescriptor {
-                label: Some("Index Buffer"),
-                contents: index_data,
-                usage: wgpu::BufferUsages::INDEX,
-            }
+    fn create_render_pass(self: &mut Self) -> PassHandle {
+        let render_pass = WgpuRenderPass::new(
+            &self.device,
+            &self.config,
+            &self.pipeline_factories
         );
+        let id = self.render_passes.lock().insert(render_pass);
 
-        let buffer_own = rj::Own::new(
-            WgpuBuffer::<u32> {
-                buffer: index_buffer,
-                size: data.len(),
-                _phantom_data: PhantomData::default()
-            });
-        
+        PassHandle::new(id, self.render_passes.clone(), &self.released_passes)
+    }
+
+    fn create_index_buffer_with_usage(self: &Self, data: &[u32], usage: BufferUsage) -> GpuBufferHandle<u32> {
+        let buffer_own = rj::Own::new(WgpuBuffer::new(
+            &self.device, data, usage,
+            wgpu::BufferUsages::INDEX, "Index Buffer"
+        ));
 
         GpuBufferHandle::from_own(buffer_own.upcast())
-        
     }
 
-    fn create_vertex_buffer<T:Sized + 'static>(self: &Self, data: &[T]) -> GpuBufferHandle<T> {
-        let vertex_data: &[u8] = unsafe {
-            std::slice::from_raw_parts(
-                data.as_ptr() as *const u8, data.len() * size_of::<T>()
-            )
-        };
+    fn create_vertex_buffer_with_usage<T:Sized + 'static>(self: &Self, data: &[T], usage: BufferUsage) -> GpuBufferHandle<T> {
+        let buffer_own = rj::Own::new(WgpuBuffer::new(
+            &self.device, data, usage,
+            wgpu::BufferUsages::VERTEX, "Vertex Buffer"
+        ));
 
-        let vertex_buffer = self.device.device.create_buffer_init(
-            &wgpu::util::BufferInitDescriptor {
-                label: Some("Vertex Buffer"),
-                contents: vertex_data,
-                usage: wgpu::BufferUsages::VERTEX,
-            }
-        );
+        GpuBufferHandle::from_own(buffer_own.upcast())
+    }
 
-        let buffer_own = rj::Own::new(
-            WgpuBuffer::<T> {
-                buffer: vertex_buffer,
-                size: data.len(),
-                _phantom_data: PhantomData::default()
-            }
-        );
+    fn create_texture_with_options(self: &Self, image: &DynamicImage, options: TextureOptions) -> GpuTextureHandle {
+        let texture = WgpuTexture::new(&self.device, image, options)
+            .expect("failed to create texture");
 
-        GpuBufferHandle::from_own(buffer_own.upcast())
+        GpuTextureHandle::from_own(rj::Own::new(texture).upcast())
     }
 
     fn get_width(self: &Self) -> u32 {
@@ -348,15 +382,30 @@ impl RenderBackend for WgpuRenderBackend {
         self.size.height
     }
 
-    fn get_egui_renderer(self: &Self) -> EguiRenderer {
-        todo!()
+    fn get_egui_renderer(self: &Self) -> Option<&EguiRenderer> {
+        Some(&self.egui_renderer)
+    }
+
+    fn get_egui_context(self: &Self) -> Option<egui::Context> {
+        Some(self.egui_renderer.context().clone())
     }
 }
 
-use rj::Own;
 use crate::egui::EguiRenderer;
-use crate::render_backend::render_interface::drawlets::{CreatePipeline, PassHandle, PassTrait, PipelineTrait};
-use crate::render_backend::render_interface::resources::{GpuBufferHandle, GpuBufferTrait};
+use crate::render_backend::render_interface::drawlets::{erase_pipeline, PassHandle, PassTrait, PipelineError, PipelineFactories, PipelineTrait, ReleaseQueue};
+use crate::render_backend::render_interface::drawlets::colored_mesh::ColoredMesh;
+use crate::render_backend::render_interface::drawlets::lit_colored_mesh::LitColoredMesh;
+use crate::render_backend::render_interface::drawlets::instanced_mesh::{InstancedColoredMesh, InstancedLitMesh};
+use crate::render_backend::render_interface::drawlets::textured_mesh::TexturedMesh;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::render_backend::render_interface::drawlets::{
+    colored_mesh::ColoredVertex,
+    lit_colored_mesh::NormalColoredVertex,
+    reflected_mesh::{ReflectedMesh, ReflectedVertex},
+};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::render_backend::web::reflected_mesh::build_reflected_pipeline;
+use crate::render_backend::render_interface::resources::{BufferUsage, GpuBufferHandle, GpuBufferTrait, GpuTextureHandle, TextureOptions};
 
 #[cfg(target_arch = "wasm32")]
 fn get_canvas_size(window: &Arc<Window>) -> (u32, u32) {
@@ -367,7 +416,38 @@ fn get_canvas_size(window: &Arc<Window>) -> (u32, u32) {
     (width, height)
 }
 
+fn to_wgpu_present_mode(present_mode: PresentMode, surface_caps: &wgpu::SurfaceCapabilities) -> wgpu::PresentMode {
+    let requested = match present_mode {
+        PresentMode::AutoVsync => wgpu::PresentMode::AutoVsync,
+        PresentMode::AutoNoVsync => wgpu::PresentMode::AutoNoVsync,
+        PresentMode::Fifo => wgpu::PresentMode::Fifo,
+        PresentMode::Mailbox => wgpu::PresentMode::Mailbox,
+        PresentMode::Immediate => wgpu::PresentMode::Immediate,
+    };
+
+    // the auto modes always resolve to something, explicit ones may not be supported
+    match requested {
+        wgpu::PresentMode::AutoVsync | wgpu::PresentMode::AutoNoVsync => requested,
+        _ if surface_caps.present_modes.contains(&requested) => requested,
+        _ => {
+            log::warn!("present mode {:?} is not supported by this surface, using vsync", requested);
+            wgpu::PresentMode::AutoVsync
+        }
+    }
+}
+
 impl WgpuRenderBackend {
+    // drops whatever lost its last handle since the previous frame
+    fn collect_released(&mut self) {
+        let mut render_passes = self.render_passes.lock();
+        for id in self.released_passes.take() {
+            render_passes.remove(id);
+        }
+        for render_pass in render_passes.values_mut() {
+            render_pass.collect_released();
+        }
+    }
+
     fn resize_surface_if_needed(&mut self, window: &Arc<Window>) {
         if self.size_changed {
             let mut max_x = u32::MAX;
@@ -387,14 +467,14 @@ impl WgpuRenderBackend {
             
             self.surface.configure(&self.device.device, &self.config);
 
-            for pass in self.render_passes.values_mut() {
-                pass.access().depth_stencil = Texture::create_depth_texture(&self.device.as_ref().device, &self.config, "depth stencil texture")
+            for pass in self.render_passes.lock().values_mut() {
+                pass.depth_stencil = Texture::create_depth_texture(&self.device.as_ref().device, &self.config, "depth stencil texture")
             }
             self.size_changed = false;
         }
     }
 
-    pub async fn new(window: &Arc<Window>) -> Self {
+    pub async fn new(window: &Arc<Window>, present_mode: PresentMode) -> Self {
         #[cfg(any(target_arch = "wasm32"))]
         {
             let canvas = window.canvas().unwrap();
@@ -466,10 +546,31 @@ impl WgpuRenderBackend {
             .unwrap();
         config.format = TextureFormat::Rgba8Unorm;
 
+        let surface_caps = surface.get_capabilities(&adapter);
+        config.present_mode = to_wgpu_present_mode(present_mode, &surface_caps);
+
+        let surface_copy_dst = surface_caps.usages
+            .contains(wgpu::TextureUsages::COPY_DST);
+        if surface_copy_dst {
+            config.usage |= wgpu::TextureUsages::COPY_DST;
+        }
+
         surface.configure(&device, &config);
 
         let egui_renderer = EguiRenderer::new(&device, config.format, None, 1, window.as_ref());
 
+        let pipeline_factories = PipelineFactories::default();
+        pipeline_factories.register::<ColoredMesh>(build_pipeline::<ColoredMesh>);
+        pipeline_factories.register::<LitColoredMesh>(build_pipeline::<LitColoredMesh>);
+        pipeline_factories.register::<TexturedMesh>(build_pipeline::<TexturedMesh>);
+        pipeline_factories.register::<InstancedColoredMesh>(build_pipeline::<InstancedColoredMesh>);
+        pipeline_factories.register::<InstancedLitMesh>(build_pipeline::<InstancedLitMesh>);
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            pipeline_factories.register::<ReflectedMesh<ColoredVertex>>(build_reflected_pipeline::<ColoredVertex>);
+            pipeline_factories.register::<ReflectedMesh<NormalColoredVertex>>(build_reflected_pipeline::<NormalColoredVertex>);
+        }
+
         Self {
             surface,
             _adapter: adapter,
@@ -481,51 +582,192 @@ impl WgpuRenderBackend {
             size,
             size_changed: false,
             max_size: PhysicalSize {width: 800, height: 600},
-            render_passes: HashMap::new(),
+            render_passes: Arc::new(Mutex::new(Registry::new())),
+            released_passes: ReleaseQueue::default(),
+            pipeline_factories,
             egui_renderer,
+            frame_capture: FrameCapture::new(),
+            surface_copy_dst,
         }
     }
-}
 
-impl CreateDrawletWgpu for WgpuRenderBackend
-{
-    fn create_render_pass(self: &mut Self) -> PassHandle {
-        let id = Self::get_render_pass_id();
-        let ret = rj::Own::new(WgpuRenderPass::new(
-            &self.device,
-            &self.config
-        ));
-        self.render_passes.insert(id.clone(), ret.clone());
+    // lets a render object from outside this crate be drawn by this backend,
+    // `PassHandle::create_pipeline::<RenObjType>` works for it afterwards
+    pub fn register_render_object<RenObjType: WgpuRenderObject + 'static>(self: &mut Self) {
+        self.pipeline_factories.register::<RenObjType>(build_pipeline::<RenObjType>);
+    }
 
-        PassHandle { id, ptr: ret.upcast() }
+    // `ReflectedMesh<V>` is already registered for the vertex types of the built-in meshes
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn register_reflected_vertex<V: ReflectedVertex>(self: &mut Self) {
+        self.pipeline_factories.register::<ReflectedMesh<V>>(build_reflected_pipeline::<V>);
     }
-}
 
-pub trait CreateDrawletWgpu
-{
-    fn create_render_pass(
-        self: &mut Self
-    ) -> PassHandle;
+    // the next rendered frame is read back, the handle fills in a few frames later
+    pub fn request_screenshot(self: &mut Self) -> ScreenshotHandle {
+        self.frame_capture.request_handle()
+    }
 
+    pub fn request_screenshot_with(self: &mut Self, callback: impl FnOnce(image::RgbaImage) + Send + 'static) {
+        self.frame_capture.request(Box::new(callback));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn capture_screenshot(self: &mut Self, path: impl AsRef<std::path::Path>) {
+        self.frame_capture.request_png(path.as_ref().to_path_buf());
+    }
+
+    // every frame is saved as frame_000000.png, frame_000001.png, ... until stopped
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn start_png_sequence(self: &mut Self, directory: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        self.frame_capture.start_recording(directory.as_ref())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn stop_png_sequence(self: &mut Self) {
+        self.frame_capture.stop_recording();
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn is_recording_png_sequence(self: &Self) -> bool {
+        self.frame_capture.is_recording()
+    }
 }
 
+// `capacity` elements are allocated on the gpu, only the first `count` are in use
 pub struct WgpuBuffer<T> {
-    size: usize,
+    device: Weak<Device>,
+    count: usize,
+    capacity: usize,
+    usage: BufferUsage,
+    label: &'static str,
+    buffer_usages: wgpu::BufferUsages,
     buffer: wgpu::Buffer,
     _phantom_data: PhantomData<T>
 }
 
+fn as_bytes<T>(data: &[T]) -> &[u8] {
+    unsafe {
+        std::slice::from_raw_parts(
+            data.as_ptr() as *const u8, data.len() * size_of::<T>()
+        )
+    }
+}
+
 impl<T> WgpuBuffer<T> {
-    pub(crate) fn slice(&self) -> BufferSlice<'_> {
+    pub fn new(
+        device: &Arc<Device>,
+        data: &[T],
+        usage: BufferUsage,
+        buffer_usages: wgpu::BufferUsages,
+        label: &'static str
+    ) -> Self {
+        // wgpu copies whole 4 byte words, with elements to match every offset and size
+        // `write` and `resize` pass on are aligned too. vertex strides need this anyway
+        assert!((size_of::<T>() as wgpu::BufferAddress).is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT),
+            "{label} elements are {} bytes, which isn't a multiple of {}", size_of::<T>(), wgpu::COPY_BUFFER_ALIGNMENT);
+
+        // copies between buffers keep the contents when it grows
+        let buffer_usages = buffer_usages | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC;
+        let capacity = usage.initial_capacity(data.len());
+        let buffer = Self::create_buffer(&device.device, capacity, buffer_usages, label);
+        if !data.is_empty() {
+            device.queue.write_buffer(&buffer, 0, as_bytes(data));
+        }
+
+        Self {
+            device: Arc::downgrade(device),
+            count: data.len(),
+            capacity,
+            usage,
+            label,
+            buffer_usages,
+            buffer,
+            _phantom_data: PhantomData::default()
+        }
+    }
+
+    fn create_buffer(device: &wgpu::Device, capacity: usize, usages: wgpu::BufferUsages, label: &str) -> wgpu::Buffer {
+        // never empty so it can always be sliced, and padded for `copy_buffer_to_buffer`
+        let size = wgpu::util::align_to(
+            (capacity.max(1) * size_of::<T>()) as wgpu::BufferAddress,
+            wgpu::COPY_BUFFER_ALIGNMENT
+        );
+
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size,
+            usage: usages,
+            mapped_at_creation: false,
+        })
+    }
+
+    // the whole allocation, draws only read the first `count` elements through their index range
+    pub fn slice(&self) -> BufferSlice<'_> {
         self.buffer.slice(..)
     }
+
+    // read at draw time so resizes show up without recreating the drawlet
+    pub fn num_elements(&self) -> u32 {
+        self.count as u32
+    }
 }
 
 impl<T: 'static> GpuBufferTrait<T> for WgpuBuffer<T> {
     fn get_size_bytes(&self) -> usize {
-        self.size * size_of::<T>()
+        self.count * size_of::<T>()
     }
     fn get_count(&self) -> usize {
-        self.size
+        self.count
+    }
+    fn get_capacity(&self) -> usize {
+        self.capacity
+    }
+    fn get_usage(&self) -> BufferUsage {
+        self.usage
+    }
+
+    fn write(&mut self, offset: usize, data: &[T]) {
+        assert!(offset + data.len() <= self.count,
+            "writing {} elements at {} overflows a buffer of {}", data.len(), offset, self.count);
+
+        let byte_offset = (offset * size_of::<T>()) as wgpu::BufferAddress;
+        let bytes = as_bytes(data);
+        assert!(byte_offset.is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT)
+            && (bytes.len() as wgpu::BufferAddress).is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT),
+            "writing {} bytes at {} isn't aligned to {}", bytes.len(), byte_offset, wgpu::COPY_BUFFER_ALIGNMENT);
+
+        let device = self.device.upgrade().expect("buffer outlived its device");
+        device.queue.write_buffer(&self.buffer, byte_offset, bytes);
+    }
+
+    fn resize(&mut self, count: usize) {
+        let device = self.device.upgrade().expect("buffer outlived its device");
+
+        if count > self.capacity {
+            let capacity = self.usage.grow_capacity(self.capacity, count);
+            let buffer = Self::create_buffer(&device.device, capacity, self.buffer_usages, self.label);
+
+            let used = (self.count * size_of::<T>()) as wgpu::BufferAddress;
+            if self.usage.keeps_contents_on_grow() && used > 0 {
+                let mut encoder = device.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Buffer Grow Encoder"),
+                });
+                encoder.copy_buffer_to_buffer(
+                    &self.buffer, 0, &buffer, 0,
+                    wgpu::util::align_to(used, wgpu::COPY_BUFFER_ALIGNMENT)
+                );
+                device.queue.submit(Some(encoder.finish()));
+            }
+
+            self.buffer = buffer;
+            self.capacity = capacity;
+        } else if count > self.count {
+            // the tail may still hold data from before a shrink
+            let zeros = vec![0u8; (count - self.count) * size_of::<T>()];
+            device.queue.write_buffer(&self.buffer, (self.count * size_of::<T>()) as wgpu::BufferAddress, &zeros);
+        }
+
+        self.count = count;
     }
 }
\ No newline at end of file
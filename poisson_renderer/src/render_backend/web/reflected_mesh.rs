@@ -0,0 +1,554 @@
+use crate::AsAny;
+use std::any::Any;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::{Arc, Weak};
+use wgpu::SurfaceConfiguration;
+use slang_refl::{ShaderStage, SlangProgramReflection, VarType};
+use crate::render_backend::{DrawletID, PipelineID, RenderDrawlet, RenderPipeline};
+use crate::render_backend::registry::Registry;
+use crate::render_backend::render_interface::drawlets::{erase_pipeline, DrawletTrait, PipelineError, PipelineTrait, ReleaseQueue};
+use crate::render_backend::render_interface::drawlets::reflected_mesh::{AttributeFormat, ReflectedMesh, ReflectedMeshData, ReflectedMeshDrawletTrait, ReflectedVertex, ReflectionError, UniformValue};
+use crate::render_backend::web::{compile_slang, Device, WgpuBuffer, WgpuDrawlet, WgpuDrawletDyn, WgpuPipelineDyn, WgpuRenderPass};
+use crate::render_backend::web::gpu_resources::gpu_texture::Texture;
+
+// needs the slang compiler for the reflection, so there is no wasm version
+pub(crate) fn build_reflected_pipeline<V: ReflectedVertex>(pass: &mut WgpuRenderPass, shader_path: &str, _shader_text: &str)
+    -> Result<(PipelineID, Box<dyn Any>), PipelineError>
+{
+    let linked_program = compile_slang(shader_path);
+
+    let pipeline = ReflectedMeshPipeline::<V>::new(
+        &pass.device.upgrade().unwrap(), linked_program.get_u8(), &linked_program.get_reflection(), &pass.surface_config
+    )?;
+
+    let pipeline_id = pass.pipelines.insert(Box::new(pipeline));
+    Ok(erase_pipeline::<ReflectedMesh<V>, ReflectedMeshPipeline<V>>(pipeline_id))
+}
+
+// the struct the vertex entry point takes its attributes in, as in the shipped shaders
+const VERTEX_INPUT: &str = "AssembledVertex";
+
+fn attribute_format(var_type: VarType) -> Option<AttributeFormat> {
+    match var_type {
+        VarType::Float => Some(AttributeFormat::Float),
+        VarType::Float2 => Some(AttributeFormat::Float2),
+        VarType::Float3 => Some(AttributeFormat::Float3),
+        VarType::Float4 => Some(AttributeFormat::Float4),
+        _ => None,
+    }
+}
+
+fn vertex_format(format: AttributeFormat) -> wgpu::VertexFormat {
+    match format {
+        AttributeFormat::Float => wgpu::VertexFormat::Float32,
+        AttributeFormat::Float2 => wgpu::VertexFormat::Float32x2,
+        AttributeFormat::Float3 => wgpu::VertexFormat::Float32x3,
+        AttributeFormat::Float4 => wgpu::VertexFormat::Float32x4,
+    }
+}
+
+// the attributes of `V` the vertex entry point reads, errors if one is missing or has another type
+fn vertex_attributes<V: ReflectedVertex>(reflection: &SlangProgramReflection)
+    -> Result<Vec<wgpu::VertexAttribute>, ReflectionError>
+{
+    let assembled_vertex = reflection.entry_point_reflections.iter()
+        .find(|entry| matches!(entry.stage, ShaderStage::Vertex))
+        .and_then(|entry| entry.struct_reflections.iter().find(|param| param.type_name == VERTEX_INPUT))
+        .ok_or(ReflectionError::MissingVertexInput)?;
+
+    assembled_vertex.fields.iter().map(|field| {
+        let attribute = V::ATTRIBUTES.iter()
+            .find(|attribute| attribute.location == field.location)
+            .ok_or_else(|| ReflectionError::MissingVertexAttribute {
+                name: field.name.clone(),
+                location: field.location,
+            })?;
+
+        if attribute_format(field.var_type) != Some(attribute.format) {
+            return Err(ReflectionError::VertexMismatch {
+                name: field.name.clone(),
+                location: field.location,
+                shader: field.var_type.to_string(),
+                vertex: attribute.format,
+            });
+        }
+
+        Ok(wgpu::VertexAttribute {
+            offset: attribute.offset as wgpu::BufferAddress,
+            shader_location: attribute.location,
+            format: vertex_format(attribute.format),
+        })
+    }).collect()
+}
+
+// type name as in `UniformValue::type_name` and size in bytes
+fn uniform_type(var_type: VarType) -> Option<(&'static str, u64)> {
+    match var_type {
+        VarType::Float => Some(("float", 4)),
+        VarType::Float2 => Some(("float2", 8)),
+        VarType::Float3 => Some(("float3", 12)),
+        VarType::Float4 => Some(("float4", 16)),
+        VarType::Float4x4 => Some(("float4x4", 64)),
+        _ => None,
+    }
+}
+
+struct UniformField {
+    group: usize,
+    // index into the group's blocks, not the binding
+    block: usize,
+    offset: u64,
+    type_name: &'static str,
+}
+
+struct UniformBlock {
+    binding: u32,
+    size: u64,
+}
+
+// shared by the pipeline and its drawlets
+struct UniformLayout {
+    // the uniform blocks of every bind group, empty for groups the shader doesn't use
+    groups: Vec<Vec<UniformBlock>>,
+    fields: HashMap<String, UniformField>,
+}
+
+impl UniformLayout {
+    fn new(reflection: &SlangProgramReflection) -> Result<Self, ReflectionError> {
+        if let Some(name) = reflection.unsupported_parameters.first() {
+            return Err(ReflectionError::UnsupportedParameter(name.clone()));
+        }
+
+        let group_count = reflection.uniform_reflections.iter()
+            .map(|block| block.space as usize + 1)
+            .max()
+            .unwrap_or(0);
+
+        let mut groups: Vec<Vec<UniformBlock>> = (0..group_count).map(|_| Vec::new()).collect();
+        let mut fields = HashMap::new();
+
+        for block in &reflection.uniform_reflections {
+            let group = block.space as usize;
+            if groups[group].iter().any(|other| other.binding == block.binding) {
+                return Err(ReflectionError::SharedBinding {
+                    name: block.name.clone(),
+                    group: block.space,
+                    binding: block.binding,
+                });
+            }
+
+            let block_index = groups[group].len();
+            let mut size = 0;
+            for field in &block.fields {
+                let (type_name, field_size) = uniform_type(field.var_type)
+                    .ok_or_else(|| ReflectionError::UnsupportedUniformType {
+                        name: field.name.clone(),
+                        shader: field.var_type.to_string(),
+                    })?;
+
+                let offset = field.location as u64;
+                size = size.max(offset + field_size);
+
+                let uniform = UniformField { group, block: block_index, offset, type_name };
+                if fields.insert(field.name.clone(), uniform).is_some() {
+                    return Err(ReflectionError::DuplicateUniform(field.name.clone()));
+                }
+            }
+            // uniform buffers are padded out to 16 bytes
+            groups[group].push(UniformBlock {
+                binding: block.binding,
+                size: size.next_multiple_of(16).max(16),
+            });
+        }
+
+        Ok(Self { groups, fields })
+    }
+
+    fn create_bind_group_layouts(self: &Self, device: &wgpu::Device) -> Vec<wgpu::BindGroupLayout> {
+        self.groups.iter().map(|blocks| {
+            let entries: Vec<wgpu::BindGroupLayoutEntry> = blocks.iter().map(|block| wgpu::BindGroupLayoutEntry {
+                binding: block.binding,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }).collect();
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &entries,
+                label: Some("Reflected Bind Group Layout"),
+            })
+        }).collect()
+    }
+}
+
+struct UniformGroup {
+    // one per block of the group, in the layout's order
+    buffers: Vec<wgpu::Buffer>,
+    bind_group: wgpu::BindGroup,
+}
+
+pub struct ReflectedMeshDrawlet<V> {
+    device: Weak<Device>,
+    layout: Arc<UniformLayout>,
+    groups: Vec<UniformGroup>,
+    vertex_buffer: rj::Own<WgpuBuffer<V>>,
+    index_buffer: rj::Own<WgpuBuffer<u32>>
+}
+
+impl<V: ReflectedVertex> ReflectedMeshDrawlet<V> {
+    fn new(
+        device: &Arc<Device>,
+        layout: &Arc<UniformLayout>,
+        bind_group_layouts: &[wgpu::BindGroupLayout],
+        init_data: &ReflectedMeshData<V>
+    ) -> Self {
+        let groups = layout.groups.iter().zip(bind_group_layouts).map(|(blocks, bind_group_layout)| {
+            let buffers: Vec<wgpu::Buffer> = blocks.iter().map(|block| device.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Reflected Uniform Buffer"),
+                size: block.size,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })).collect();
+
+            let entries: Vec<wgpu::BindGroupEntry> = blocks.iter().zip(&buffers).map(|(block, buffer)| wgpu::BindGroupEntry {
+                binding: block.binding,
+                resource: buffer.as_entire_binding(),
+            }).collect();
+
+            let bind_group = device.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: bind_group_layout,
+                entries: &entries,
+                label: Some("reflected_bind_group"),
+            });
+
+            UniformGroup { buffers, bind_group }
+        }).collect();
+
+        let vertex_buffer = init_data.mesh.vertex.buffer.downcast()
+            .expect("failed to cast vertex buffer to drawlet buffer type");
+
+        let index_buffer = init_data.mesh.index.buffer.downcast()
+            .expect("failed to cast index buffer to drawlet buffer type");
+
+        Self {
+            device: Arc::downgrade(device),
+            layout: layout.clone(),
+            groups,
+            vertex_buffer,
+            index_buffer
+        }
+    }
+}
+
+impl<V: ReflectedVertex> RenderDrawlet for ReflectedMeshDrawlet<V> {
+    type Data = ReflectedMeshData<V>;
+}
+
+impl<V: ReflectedVertex> WgpuDrawlet for ReflectedMeshDrawlet<V> {
+    fn draw(self: &Self, render_pass: &mut wgpu::RenderPass) {
+        for (index, group) in self.groups.iter().enumerate() {
+            render_pass.set_bind_group(index as u32, &group.bind_group, &[]);
+        }
+
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.access().slice());
+        let index_buffer = self.index_buffer.access();
+        render_pass.set_index_buffer(index_buffer.slice(), wgpu::IndexFormat::Uint32);
+        render_pass.draw_indexed(0..index_buffer.num_elements(), 0, 0..1);
+    }
+}
+
+impl<V: ReflectedVertex> DrawletTrait<ReflectedMesh<V>> for ReflectedMeshDrawlet<V> {}
+
+impl<V: ReflectedVertex> ReflectedMeshDrawletTrait<V> for ReflectedMeshDrawlet<V> {
+    fn set_uniform(self: &mut Self, name: &str, value: UniformValue) -> Result<(), ReflectionError> {
+        let field = self.layout.fields.get(name)
+            .ok_or_else(|| ReflectionError::UnknownUniform(name.to_owned()))?;
+
+        if field.type_name != value.type_name() {
+            return Err(ReflectionError::UniformTypeMismatch {
+                name: name.to_owned(),
+                shader: field.type_name,
+                value: value.type_name(),
+            });
+        }
+
+        let buffer = &self.groups[field.group].buffers[field.block];
+        self.device.upgrade().as_ref().unwrap().queue
+            .write_buffer(buffer, field.offset, bytemuck::cast_slice(value.as_floats()));
+        Ok(())
+    }
+}
+
+pub struct ReflectedMeshPipeline<V> {
+    device: Weak<Device>,
+    render_pipeline: wgpu::RenderPipeline,
+    layout: Arc<UniformLayout>,
+    bind_group_layouts: Vec<wgpu::BindGroupLayout>,
+    drawlets: Registry<DrawletID, ReflectedMeshDrawlet<V>>,
+    released: ReleaseQueue<DrawletID>,
+}
+
+impl<V: ReflectedVertex> ReflectedMeshPipeline<V> {
+    fn new(
+        device: &Arc<Device>,
+        shader_u8: &[u8],
+        reflection: &SlangProgramReflection,
+        surface_config: &SurfaceConfiguration
+    ) -> Result<Self, ReflectionError> {
+        let attributes = vertex_attributes::<V>(reflection)?;
+        let layout = UniformLayout::new(reflection)?;
+        let bind_group_layouts = layout.create_bind_group_layouts(&device.device);
+
+        let wgsl_str = str::from_utf8(shader_u8).unwrap();
+
+        let shader = device.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::from(wgsl_str)),
+        });
+
+        let render_pipeline_layout =
+            device.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Render Pipeline Layout"),
+                bind_group_layouts: &bind_group_layouts.iter().collect::<Vec<_>>(),
+                push_constant_ranges: &[],
+            });
+
+        let desc = wgpu::VertexBufferLayout {
+            array_stride: size_of::<V>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &attributes,
+        };
+
+        let render_pipeline = device.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Render Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vertex"),
+                buffers: &[desc],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fragment"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_config.format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: Some(
+                wgpu::DepthStencilState {
+                    format: Texture::DEPTH_FORMAT,
+                    depth_write_enabled: true,
+                    depth_compare: wgpu::CompareFunction::Less,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        Ok(Self {
+            device: Arc::downgrade(device),
+            render_pipeline,
+            layout: Arc::new(layout),
+            bind_group_layouts,
+            drawlets: Registry::new(),
+            released: ReleaseQueue::default(),
+        })
+    }
+}
+
+impl<V: ReflectedVertex> AsAny for ReflectedMeshPipeline<V> {
+    fn as_any(self: &Self) -> &dyn Any { self }
+    fn as_any_mut(self: &mut Self) -> &mut dyn Any { self }
+}
+
+impl<V: ReflectedVertex> WgpuPipelineDyn for ReflectedMeshPipeline<V> {
+    fn get_pipeline(self: &Self) -> &wgpu::RenderPipeline {
+        &self.render_pipeline
+    }
+    fn get_instances(self: &Self) -> Box<dyn Iterator<Item=&dyn WgpuDrawletDyn> + '_> {
+        Box::new(self.drawlets.values().map(|drawlet| drawlet as &dyn WgpuDrawletDyn))
+    }
+
+    fn collect_released(self: &mut Self) {
+        for id in self.released.take() {
+            self.drawlets.remove(id);
+        }
+    }
+}
+
+impl<V: ReflectedVertex> RenderPipeline<ReflectedMesh<V>> for ReflectedMeshPipeline<V> {}
+
+impl<V: ReflectedVertex> PipelineTrait<ReflectedMesh<V>> for ReflectedMeshPipeline<V> {
+    fn create_drawlet(&mut self, init_data: ReflectedMeshData<V>) -> DrawletID {
+        let new_drawlet = ReflectedMeshDrawlet::new(
+            &self.device.upgrade().unwrap(),
+            &self.layout,
+            &self.bind_group_layouts,
+            &init_data);
+
+        self.drawlets.insert(new_drawlet)
+    }
+
+    fn remove_drawlet(&mut self, id: DrawletID) {
+        let _ = self.drawlets.remove(id);
+    }
+
+    fn get_drawlet_mut(&mut self, id: DrawletID) -> Option<&mut (dyn ReflectedMeshDrawletTrait<V> + 'static)> {
+        self.drawlets.get_mut(id).map(|drawlet| drawlet as &mut dyn ReflectedMeshDrawletTrait<V>)
+    }
+
+    fn get_drawlet_releases(self: &Self) -> ReleaseQueue<DrawletID> {
+        self.released.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use slang_refl::{SlangEntryPointReflection, SlangFieldReflection, SlangStructReflection};
+    use crate::render_backend::render_interface::drawlets::lit_colored_mesh::NormalColoredVertex;
+
+    fn field(name: &str, location: u32, var_type: VarType) -> SlangFieldReflection {
+        SlangFieldReflection { name: name.to_string(), location, var_type }
+    }
+
+    fn block(name: &str, space: u32, binding: u32, fields: Vec<SlangFieldReflection>) -> SlangStructReflection {
+        SlangStructReflection {
+            name: name.to_string(),
+            type_name: format!("{name}Type"),
+            space,
+            binding,
+            fields,
+        }
+    }
+
+    fn entry_point(stage: ShaderStage, inputs: Vec<SlangStructReflection>) -> SlangEntryPointReflection {
+        SlangEntryPointReflection {
+            name: "main".to_string(),
+            stage,
+            struct_reflections: inputs,
+            misc_reflections: block("misc", 0, 0, vec![]),
+        }
+    }
+
+    fn vertex_input(fields: Vec<SlangFieldReflection>) -> SlangStructReflection {
+        SlangStructReflection { type_name: VERTEX_INPUT.to_string(), ..block("input", 0, 0, fields) }
+    }
+
+    fn program(uniforms: Vec<SlangStructReflection>, vertex_fields: Vec<SlangFieldReflection>) -> SlangProgramReflection {
+        SlangProgramReflection {
+            uniform_reflections: uniforms,
+            entry_point_reflections: vec![
+                entry_point(ShaderStage::Fragment, vec![vertex_input(vec![])]),
+                entry_point(ShaderStage::Vertex, vec![vertex_input(vertex_fields)]),
+            ],
+            unsupported_parameters: vec![],
+        }
+    }
+
+    fn lit_vertex_fields() -> Vec<SlangFieldReflection> {
+        vec![field("pos", 0, VarType::Float3), field("color", 1, VarType::Float4), field("normal", 2, VarType::Float3)]
+    }
+
+    #[test]
+    fn blocks_are_grouped_by_space_and_padded() {
+        let reflection = program(vec![
+            block("camera", 1, 0, vec![field("mvp", 0, VarType::Float4x4), field("eye", 64, VarType::Float3)]),
+            block("light", 1, 2, vec![field("strength", 0, VarType::Float)]),
+            block("empty", 3, 0, vec![]),
+        ], lit_vertex_fields());
+        let layout = UniformLayout::new(&reflection).unwrap();
+
+        let groups: Vec<Vec<(u32, u64)>> = layout.groups.iter()
+            .map(|blocks| blocks.iter().map(|block| (block.binding, block.size)).collect())
+            .collect();
+        assert_eq!(groups, vec![vec![], vec![(0, 80), (2, 16)], vec![], vec![(0, 16)]]);
+
+        let eye = &layout.fields["eye"];
+        assert_eq!((eye.group, eye.block, eye.offset, eye.type_name), (1, 0, 64, "float3"));
+        let strength = &layout.fields["strength"];
+        assert_eq!((strength.group, strength.block, strength.offset, strength.type_name), (1, 1, 0, "float"));
+    }
+
+    #[test]
+    fn conflicting_or_unsupported_uniforms_are_rejected() {
+        let shared = program(vec![
+            block("camera", 0, 1, vec![field("mvp", 0, VarType::Float4x4)]),
+            block("light", 0, 1, vec![field("strength", 0, VarType::Float)]),
+        ], vec![]);
+        assert!(matches!(UniformLayout::new(&shared),
+            Err(ReflectionError::SharedBinding { name, group: 0, binding: 1 }) if name == "light"));
+
+        // the same binding in another space is fine, the same field name isn't
+        let duplicate = program(vec![
+            block("camera", 0, 1, vec![field("mvp", 0, VarType::Float4x4)]),
+            block("shadow", 1, 1, vec![field("mvp", 0, VarType::Float4x4)]),
+        ], vec![]);
+        assert!(matches!(UniformLayout::new(&duplicate), Err(ReflectionError::DuplicateUniform(name)) if name == "mvp"));
+
+        let unsupported_type = program(vec![block("camera", 0, 0, vec![field("mystery", 0, VarType::Undefined)])], vec![]);
+        assert!(matches!(UniformLayout::new(&unsupported_type),
+            Err(ReflectionError::UnsupportedUniformType { name, .. }) if name == "mystery"));
+
+        let mut texture = program(vec![], vec![]);
+        texture.unsupported_parameters.push("albedo".to_string());
+        assert!(matches!(UniformLayout::new(&texture), Err(ReflectionError::UnsupportedParameter(name)) if name == "albedo"));
+    }
+
+    #[test]
+    fn vertex_attributes_follow_the_vertex_entry_point() {
+        let attributes = vertex_attributes::<NormalColoredVertex>(&program(vec![], lit_vertex_fields())).unwrap();
+        assert_eq!(attributes, vec![
+            wgpu::VertexAttribute { offset: 0, shader_location: 0, format: wgpu::VertexFormat::Float32x3 },
+            wgpu::VertexAttribute { offset: 12, shader_location: 1, format: wgpu::VertexFormat::Float32x4 },
+            wgpu::VertexAttribute { offset: 28, shader_location: 2, format: wgpu::VertexFormat::Float32x3 },
+        ]);
+
+        // the shader may skip attributes the vertex has
+        let position_only = program(vec![], vec![field("pos", 0, VarType::Float3)]);
+        assert_eq!(vertex_attributes::<NormalColoredVertex>(&position_only).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn vertex_attributes_must_match_the_vertex() {
+        let mismatch = program(vec![], vec![field("pos", 0, VarType::Float3), field("color", 1, VarType::Float3)]);
+        assert!(matches!(vertex_attributes::<NormalColoredVertex>(&mismatch),
+            Err(ReflectionError::VertexMismatch { name, location: 1, vertex: AttributeFormat::Float4, .. }) if name == "color"));
+
+        let matrix = program(vec![], vec![field("pos", 0, VarType::Float4x4)]);
+        assert!(matches!(vertex_attributes::<NormalColoredVertex>(&matrix),
+            Err(ReflectionError::VertexMismatch { location: 0, vertex: AttributeFormat::Float3, .. })));
+
+        let missing = program(vec![], vec![field("uv", 3, VarType::Float2)]);
+        assert!(matches!(vertex_attributes::<NormalColoredVertex>(&missing),
+            Err(ReflectionError::MissingVertexAttribute { name, location: 3 }) if name == "uv"));
+
+        let mut no_vertex_stage = program(vec![], lit_vertex_fields());
+        no_vertex_stage.entry_point_reflections.remove(1);
+        assert!(matches!(vertex_attributes::<NormalColoredVertex>(&no_vertex_stage), Err(ReflectionError::MissingVertexInput)));
+    }
+}
@@ -1,22 +1,22 @@
 use crate::AsAny;
 use std::any::Any;
 use std::borrow::Cow;
-use std::collections::HashMap;
 use std::sync::{Arc, Weak};
 use cgmath::{Matrix4, Vector3};
 use parking_lot::Mutex;
 use wgpu::{SurfaceConfiguration};
 use wgpu::util::DeviceExt;
 use poisson_macros::AsAny;
-use rj::Own;
 use crate::render_backend::{DrawletID, Mat4Ubo, RenderDrawlet, RenderPipeline};
 use crate::render_backend::render_interface::{RenderObject};
-use crate::render_backend::render_interface::drawlets::{DrawletHandle, DrawletTrait, PipelineTrait};
+use crate::render_backend::render_interface::drawlets::{DrawletTrait, PipelineTrait, ReleaseQueue};
+use crate::render_backend::registry::Registry;
+use crate::render_backend::render_interface::resources::GpuTextureHandle;
 use crate::render_backend::render_interface::drawlets::textured_mesh::{UvVertex, TexturedMesh, TexturedMeshData, TexturedMeshDrawletTrait};
 use crate::render_backend::web::{Device, WgpuBuffer, WgpuDrawlet, WgpuDrawletDyn, WgpuPipeline, WgpuPipelineDyn, WgpuRenderObject};
 use crate::render_backend::web::gpu_resources::{interface::WgpuUniformResource, gpu_texture::ShaderTexture};
 use crate::render_backend::web::gpu_resources::gpu_mat4::GpuMat4;
-use crate::render_backend::web::gpu_resources::gpu_texture::Texture;
+use crate::render_backend::web::gpu_resources::gpu_texture::{Texture, WgpuTexture};
 use crate::render_backend::web::per_vertex_impl::WgpuPerVertex;
 
 impl WgpuRenderObject for TexturedMesh {
@@ -27,8 +27,7 @@ impl WgpuRenderObject for TexturedMesh {
 
 pub struct TexturedMeshDrawlet {
     device: Weak<Device>,
-    num_indices: u32,
-    gpu_texture: ShaderTexture,
+    texture: rj::Own<WgpuTexture>,
     mvp_buffer: GpuMat4,
     vertex_buffer: rj::Own<WgpuBuffer<UvVertex>>,
     index_buffer: rj::Own<WgpuBuffer<u32>>
@@ -41,12 +40,8 @@ impl TexturedMeshDrawlet {
     ) -> Self {
         let uniform_buffer = GpuMat4::from_mat4(&device.device, &init_data.mvp_data);
 
-        let texture = ShaderTexture::from_image(
-            &device.device,
-            &device.queue,
-            &init_data.texture_data, 
-            Some("TexturedMesh")
-        ).expect("failed to create texture");
+        let texture = init_data.texture.texture.downcast()
+            .expect("failed to cast texture to drawlet texture type");
 
         let vertex_buffer = init_data.mesh.vertex.buffer.downcast()
             .expect("failed to cast vertex buffer to drawlet buffer type");
@@ -58,8 +53,7 @@ impl TexturedMeshDrawlet {
         
         Self {
             device: Arc::downgrade(device),
-            num_indices: init_data.mesh.index.get_count() as u32,
-            gpu_texture: texture,
+            texture,
             mvp_buffer: uniform_buffer,
             vertex_buffer,
             index_buffer
@@ -74,10 +68,11 @@ impl RenderDrawlet for TexturedMeshDrawlet {
 impl WgpuDrawlet for TexturedMeshDrawlet {
     fn draw(self: &Self, render_pass: &mut wgpu::RenderPass) {
         render_pass.set_bind_group(0, self.mvp_buffer.get_bind_group(), &[]);
-        render_pass.set_bind_group(1, self.gpu_texture.get_bind_group(), &[]);
+        render_pass.set_bind_group(1, self.texture.access().shader_texture.get_bind_group(), &[]);
         render_pass.set_vertex_buffer(0, self.vertex_buffer.access().slice());
-        render_pass.set_index_buffer(self.index_buffer.access().slice(), wgpu::IndexFormat::Uint32);
-        render_pass.draw_indexed(0..self.num_indices, 0, 0..1);
+        let index_buffer = self.index_buffer.access();
+        render_pass.set_index_buffer(index_buffer.slice(), wgpu::IndexFormat::Uint32);
+        render_pass.draw_indexed(0..index_buffer.num_elements(), 0, 0..1);
     }
 }
 
@@ -85,32 +80,32 @@ impl WgpuDrawlet for TexturedMeshDrawlet {
 pub struct TexturedMeshPipeline {
     device: Weak<Device>,
     render_pipeline: wgpu::RenderPipeline,
-    drawlets: HashMap<DrawletID, rj::Own<TexturedMeshDrawlet>>
+    drawlets: Registry<DrawletID, TexturedMeshDrawlet>,
+    released: ReleaseQueue<DrawletID>,
 }
 
 impl WgpuPipelineDyn for TexturedMeshPipeline {
     fn get_pipeline(self: &Self) -> &wgpu::RenderPipeline {
         &self.render_pipeline
     }
-    fn get_instances(self: &Self) -> Box<dyn Iterator<Item=rj::Own<dyn WgpuDrawletDyn>> + '_> {
-        Box::new(self.drawlets.iter().map(
-            |(_, x)|
-                rj::Own::<dyn WgpuDrawletDyn>::from_inner(x.clone().into_inner())
-        ))
+    fn get_instances(self: &Self) -> Box<dyn Iterator<Item=&dyn WgpuDrawletDyn> + '_> {
+        Box::new(self.drawlets.values().map(|drawlet| drawlet as &dyn WgpuDrawletDyn))
+    }
+
+    fn collect_released(self: &mut Self) {
+        for id in self.released.take() {
+            self.drawlets.remove(id);
+        }
     }
 }
 
 impl WgpuPipeline<TexturedMesh> for TexturedMeshPipeline {
-    fn create_drawlet(self: &mut Self, init_data: TexturedMeshData) -> (DrawletID, rj::Own<TexturedMeshDrawlet>) {
-        let id = <Self as RenderPipeline<TexturedMesh>>::get_drawlet_id();
+    fn create_drawlet(self: &mut Self, init_data: TexturedMeshData) -> DrawletID {
         let new_drawlet = TexturedMeshDrawlet::new(
             &self.device.upgrade().unwrap(),
             &init_data);
-        let own= rj::Own::new(new_drawlet);
-        
-        self.drawlets.insert(id, own.clone());
 
-        (id, own)
+        self.drawlets.insert(new_drawlet)
     }
 
     fn new(device: &Arc<Device>, shader_u8: &[u8], surface_config: &SurfaceConfiguration) -> Self
@@ -186,12 +181,11 @@ impl WgpuPipeline<TexturedMesh> for TexturedMeshPipeline {
             cache: None,
         });
 
-        let drawlets = HashMap::new();
-
         Self {
             device: Arc::downgrade(device),
             render_pipeline,
-            drawlets
+            drawlets: Registry::new(),
+            released: ReleaseQueue::default(),
         }
     }
 }
@@ -210,14 +204,20 @@ impl TexturedMeshDrawlet {
 }
 
 impl PipelineTrait<TexturedMesh> for TexturedMeshPipeline {
-    fn create_drawlet(&mut self, init_data: TexturedMeshData) -> (DrawletID, Own<(dyn TexturedMeshDrawletTrait + 'static)>) {
-        let (id, own) = WgpuPipeline::create_drawlet(self, init_data);
+    fn create_drawlet(&mut self, init_data: TexturedMeshData) -> DrawletID {
+        WgpuPipeline::create_drawlet(self, init_data)
+    }
+
+    fn remove_drawlet(&mut self, id: DrawletID) {
+        let _ = self.drawlets.remove(id);
+    }
 
-        (id, own.upcast())
+    fn get_drawlet_mut(&mut self, id: DrawletID) -> Option<&mut (dyn TexturedMeshDrawletTrait + 'static)> {
+        self.drawlets.get_mut(id).map(|drawlet| drawlet as &mut dyn TexturedMeshDrawletTrait)
     }
 
-    fn remove_drawlet(&mut self, drawlet: DrawletHandle<TexturedMesh>) {
-        let _ = self.drawlets.remove(&drawlet.id);
+    fn get_drawlet_releases(self: &Self) -> ReleaseQueue<DrawletID> {
+        self.released.clone()
     }
 }
 
@@ -227,4 +227,9 @@ impl TexturedMeshDrawletTrait for TexturedMeshDrawlet {
     fn set_mvp(self: &mut Self, mvp: Matrix4<f32>) {
         self.set_mvp(Mat4Ubo { data: mvp });
     }
+
+    fn set_texture(self: &mut Self, texture: &GpuTextureHandle) {
+        self.texture = texture.texture.downcast()
+            .expect("failed to cast texture to drawlet texture type");
+    }
 }
\ No newline at end of file
@@ -0,0 +1,209 @@
+use instant::Instant;
+
+// frame timing owned by the engine, handed to `PoissonGame::fixed_update` and `update`
+pub struct Time {
+    last_instant: Option<Instant>,
+    unscaled_delta: f32,
+    delta: f32,
+    elapsed: f32,
+    frame_index: u64,
+    time_scale: f32,
+    paused: bool,
+    fixed_timestep: f32,
+    fixed_elapsed: f32,
+    fixed_step_index: u64,
+    max_fixed_steps: u32,
+    accumulator: f32,
+    alpha: f32,
+}
+
+impl Time {
+    pub const DEFAULT_FIXED_TIMESTEP: f32 = 1f32 / 60f32;
+
+    pub fn new() -> Self {
+        Self {
+            last_instant: None,
+            unscaled_delta: 0f32,
+            delta: 0f32,
+            elapsed: 0f32,
+            frame_index: 0,
+            time_scale: 1f32,
+            paused: false,
+            fixed_timestep: Self::DEFAULT_FIXED_TIMESTEP,
+            fixed_elapsed: 0f32,
+            fixed_step_index: 0,
+            max_fixed_steps: 8,
+            accumulator: 0f32,
+            alpha: 0f32,
+        }
+    }
+
+    // restarts the clock without clearing elapsed time, so e.g. a slow init isn't counted as a frame
+    pub fn reset_clock(self: &mut Self) {
+        self.last_instant = None;
+    }
+
+    pub(crate) fn advance(self: &mut Self) {
+        let now = Instant::now();
+        let real_delta = match self.last_instant {
+            Some(last) => (now - last).as_secs_f32(),
+            None => 0f32,
+        };
+        self.last_instant = Some(now);
+        self.advance_by(real_delta);
+    }
+
+    // steps the clock by a given amount of real time, used for deterministic runs
+    pub fn advance_by(self: &mut Self, real_delta: f32) {
+        self.unscaled_delta = real_delta;
+        self.delta = if self.paused { 0f32 } else { real_delta * self.time_scale };
+        self.elapsed += self.delta;
+        self.frame_index += 1;
+
+        // clamp how far behind the simulation may fall so a long stall doesn't spiral
+        let max_accumulated = self.fixed_timestep * self.max_fixed_steps as f32;
+        self.accumulator = (self.accumulator + self.delta).min(max_accumulated);
+        self.alpha = self.accumulator / self.fixed_timestep;
+    }
+
+    // true while another fixed step is due, each call consumes one step
+    pub(crate) fn consume_fixed_step(self: &mut Self) -> bool {
+        if self.accumulator < self.fixed_timestep {
+            return false;
+        }
+        self.accumulator -= self.fixed_timestep;
+        self.fixed_elapsed += self.fixed_timestep;
+        self.fixed_step_index += 1;
+        self.alpha = self.accumulator / self.fixed_timestep;
+        true
+    }
+
+    pub fn delta(self: &Self) -> f32 {
+        self.delta
+    }
+
+    pub fn unscaled_delta(self: &Self) -> f32 {
+        self.unscaled_delta
+    }
+
+    pub fn elapsed(self: &Self) -> f32 {
+        self.elapsed
+    }
+
+    pub fn frame_index(self: &Self) -> u64 {
+        self.frame_index
+    }
+
+    pub fn time_scale(self: &Self) -> f32 {
+        self.time_scale
+    }
+
+    pub fn set_time_scale(self: &mut Self, time_scale: f32) {
+        self.time_scale = time_scale.max(0f32);
+    }
+
+    pub fn is_paused(self: &Self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(self: &mut Self, paused: bool) {
+        self.paused = paused;
+    }
+
+    pub fn pause(self: &mut Self) {
+        self.paused = true;
+    }
+
+    pub fn resume(self: &mut Self) {
+        self.paused = false;
+    }
+
+    pub fn fixed_timestep(self: &Self) -> f32 {
+        self.fixed_timestep
+    }
+
+    pub fn set_fixed_timestep(self: &mut Self, fixed_timestep: f32) {
+        assert!(fixed_timestep > 0f32, "fixed timestep must be positive");
+        self.fixed_timestep = fixed_timestep;
+    }
+
+    pub fn set_max_fixed_steps(self: &mut Self, max_fixed_steps: u32) {
+        self.max_fixed_steps = max_fixed_steps.max(1);
+    }
+
+    pub fn fixed_elapsed(self: &Self) -> f32 {
+        self.fixed_elapsed
+    }
+
+    pub fn fixed_step_index(self: &Self) -> u64 {
+        self.fixed_step_index
+    }
+
+    // how far between the last and the next fixed step the current frame is, in [0, 1)
+    pub fn alpha(self: &Self) -> f32 {
+        self.alpha
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count_fixed_steps(time: &mut Time) -> u32 {
+        let mut steps = 0;
+        while time.consume_fixed_step() {
+            steps += 1;
+        }
+        steps
+    }
+
+    #[test]
+    fn runs_one_fixed_step_per_elapsed_timestep() {
+        let mut time = Time::new();
+        time.set_fixed_timestep(0.25f32);
+
+        let steps: Vec<u32> = [0.1f32, 0.2f32, 0.5f32, 0f32, 0.7f32]
+            .into_iter()
+            .map(|delta| {
+                time.advance_by(delta);
+                count_fixed_steps(&mut time)
+            })
+            .collect();
+
+        assert_eq!(steps, vec![0, 1, 2, 0, 3]);
+        assert_eq!(time.fixed_step_index(), 6);
+        assert_eq!(time.fixed_elapsed(), 1.5f32);
+        assert_eq!(time.frame_index(), 5);
+    }
+
+    #[test]
+    fn long_stall_is_clamped_to_max_fixed_steps() {
+        let mut time = Time::new();
+        time.set_fixed_timestep(0.25f32);
+        time.set_max_fixed_steps(4);
+
+        time.advance_by(60f32);
+        assert_eq!(count_fixed_steps(&mut time), 4);
+
+        // the time that was dropped doesn't come back later
+        time.advance_by(0.1f32);
+        assert_eq!(count_fixed_steps(&mut time), 0);
+    }
+
+    #[test]
+    fn alpha_stays_below_one_after_the_due_steps_ran() {
+        let mut time = Time::new();
+        time.set_fixed_timestep(0.25f32);
+
+        for delta in [0.1f32, 0.3f32, 0.05f32, 0.6f32, 2f32] {
+            time.advance_by(delta);
+            count_fixed_steps(&mut time);
+            assert!((0f32..1f32).contains(&time.alpha()), "alpha {} after a delta of {delta}", time.alpha());
+        }
+
+        time.set_paused(true);
+        time.advance_by(0.1f32);
+        assert_eq!(count_fixed_steps(&mut time), 0);
+        assert!((0f32..1f32).contains(&time.alpha()));
+    }
+}
@@ -1,17 +1,112 @@
 use std::sync::Arc;
 use winit::application::ApplicationHandler;
-use winit::dpi::PhysicalSize;
-use winit::event::WindowEvent;
+use winit::dpi::{LogicalSize, PhysicalPosition, PhysicalSize};
+use winit::event::{DeviceEvent, DeviceId, ElementState, KeyEvent, WindowEvent};
 use winit::event_loop::ActiveEventLoop;
-use winit::window::{WindowAttributes, WindowId};
+use winit::keyboard::{KeyCode, PhysicalKey};
+use winit::window::{CursorGrabMode, Fullscreen, Icon, Window, WindowAttributes, WindowId};
 use crate::{PoissonEngine, PoissonGame};
+use crate::context::{CursorGrab, EngineCommand};
 use crate::render_backend::RenderBackend;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FullscreenMode {
+    Windowed,
+    Borderless,
+    // falls back to borderless where video modes can't be changed, e.g. on the web
+    Exclusive,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PresentMode {
+    AutoVsync,
+    AutoNoVsync,
+    Fifo,
+    Mailbox,
+    Immediate,
+}
+
+#[derive(Clone)]
+pub struct WindowConfig {
+    pub title: String,
+    // logical pixels, `None` leaves it to the platform
+    pub size: Option<(u32, u32)>,
+    pub min_size: Option<(u32, u32)>,
+    pub resizable: bool,
+    pub fullscreen: FullscreenMode,
+    // pressing this key flips between windowed and `fullscreen` (or borderless if that is windowed),
+    // off by default and ignored while a text field has focus
+    pub fullscreen_toggle_key: Option<KeyCode>,
+    pub icon: Option<image::RgbaImage>,
+    pub present_mode: PresentMode,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            title: "poisson".to_string(),
+            size: None,
+            min_size: None,
+            resizable: true,
+            fullscreen: FullscreenMode::Windowed,
+            fullscreen_toggle_key: None,
+            icon: None,
+            present_mode: PresentMode::AutoVsync,
+        }
+    }
+}
+
+impl WindowConfig {
+    fn to_window_attributes(self: &Self) -> WindowAttributes {
+        let mut window_attributes = WindowAttributes::default()
+            .with_title(self.title.clone())
+            .with_resizable(self.resizable);
+
+        if let Some((width, height)) = self.size {
+            window_attributes = window_attributes.with_inner_size(LogicalSize::new(width, height));
+        }
+        if let Some((width, height)) = self.min_size {
+            window_attributes = window_attributes.with_min_inner_size(LogicalSize::new(width, height));
+        }
+        if let Some(icon) = &self.icon {
+            match Icon::from_rgba(icon.as_raw().clone(), icon.width(), icon.height()) {
+                Ok(icon) => window_attributes = window_attributes.with_window_icon(Some(icon)),
+                Err(err) => log::warn!("ignoring invalid window icon: {err}"),
+            }
+        }
+
+        window_attributes
+    }
+}
+
+fn to_winit_fullscreen(window: &Window, mode: FullscreenMode) -> Option<Fullscreen> {
+    match mode {
+        FullscreenMode::Windowed => None,
+        FullscreenMode::Borderless => Some(Fullscreen::Borderless(window.current_monitor())),
+        FullscreenMode::Exclusive => {
+            // the monitor's native size at its fastest refresh rate, or the largest mode if
+            // none matches the current size
+            let video_mode = window.current_monitor().and_then(|monitor| {
+                let native_size = monitor.size();
+                monitor.video_modes().max_by_key(|video_mode| {
+                    let size = video_mode.size();
+                    (size == native_size, size.width * size.height, video_mode.refresh_rate_millihertz(), video_mode.bit_depth())
+                })
+            });
+            match video_mode {
+                Some(video_mode) => Some(Fullscreen::Exclusive(video_mode)),
+                None => Some(Fullscreen::Borderless(window.current_monitor())),
+            }
+        }
+    }
+}
+
 impl<GameType: PoissonGame> ApplicationHandler for PoissonEngine<GameType> where
 {
     fn resumed(&mut self, event_loop: &ActiveEventLoop)
     {
-        let window_attributes = WindowAttributes::default().with_resizable(true);
+        let window_config = self.game.window_config();
+        let window_attributes = window_config.to_window_attributes();
 
         self.window = match event_loop.create_window(window_attributes) {
             Ok(window) => Some(Arc::from(window)),
@@ -22,8 +117,23 @@ impl<GameType: PoissonGame> ApplicationHandler for PoissonEngine<GameType> where
             },
         };
 
+        self.context.set_window(self.window.clone().unwrap());
+        self.input.set_scale_factor(self.window.as_ref().unwrap().scale_factor());
+        #[cfg(not(target_arch = "wasm32"))]
+        self.start_input_recording();
+        #[cfg(target_arch = "wasm32")]
+        {
+            use winit::platform::web::WindowExtWebSys;
+            if let Some(canvas) = self.window.as_ref().unwrap().canvas() {
+                self.input.set_drop_target(canvas);
+            }
+        }
+        self.fullscreen_toggle_key = window_config.fullscreen_toggle_key;
+        self.fullscreen_mode = window_config.fullscreen;
+        self.set_fullscreen(window_config.fullscreen);
+
         if let Some(window_value) = self.window.clone() {
-            GameType::Ren::init(self.renderer.clone(), window_value);
+            GameType::Ren::init(self.renderer.clone(), window_value, window_config.present_mode);
         }
     }
 
@@ -34,32 +144,121 @@ impl<GameType: PoissonGame> ApplicationHandler for PoissonEngine<GameType> where
                 event_loop.exit();
             },
             WindowEvent::RedrawRequested { .. } => {
-                self.init_or_update();
+                self.init_or_update(event_loop);
             },
             WindowEvent::Resized(PhysicalSize { width, height }) => {
                 log::info!("resized window to {}x{}", width, height);
                 self.renderer.lock().as_mut().unwrap().resize(*width, *height);
-                self.init_or_update();
+                self.init_or_update(event_loop);
+            },
+            WindowEvent::KeyboardInput {
+                event: KeyEvent {
+                    physical_key: PhysicalKey::Code(key),
+                    state: ElementState::Pressed,
+                    repeat: false, ..
+                }, ..
+            } if Some(*key) == self.fullscreen_toggle_key && !self.input.is_ui_typing() => {
+                self.toggle_fullscreen();
             },
             _ => (),
         }
 
-        // {
-        //     self.renderer.lock().as_mut().unwrap().process_event(self.window.as_ref().unwrap(), &event);
-        // }
-        
+        if let (Some(renderer), Some(window)) = (self.renderer.lock().as_mut(), self.window.as_ref()) {
+            renderer.process_event(window, &event);
+        }
+
         self.input.process_event(&event);
     }
+
+    fn device_event(&mut self, _: &ActiveEventLoop, _: DeviceId, event: DeviceEvent) {
+        self.input.process_device_event(&event);
+    }
+
+    fn exiting(&mut self, _: &ActiveEventLoop) {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.save_input_recording();
+    }
 }
 
 impl<GameType: PoissonGame> PoissonEngine<GameType> {
-    fn init_or_update(&mut self) {
+    fn init_or_update(&mut self, event_loop: &ActiveEventLoop) {
         if !self.done_init {
             self.init();
         } else {
             self.window.as_ref().unwrap().pre_present_notify();
             self.update();
         }
+        self.apply_engine_commands(event_loop);
         self.request_redraw();
     }
-}
\ No newline at end of file
+
+    // game requests are only applied here, between frames, never while the game holds the context
+    fn apply_engine_commands(&mut self, event_loop: &ActiveEventLoop) {
+        for command in self.context.take_commands() {
+            match command {
+                EngineCommand::Exit => event_loop.exit(),
+                EngineCommand::SetTitle(title) => {
+                    if let Some(window) = self.window.as_ref() {
+                        window.set_title(title.as_str());
+                    }
+                },
+                EngineCommand::SetCursorGrab(grab) => self.set_cursor_grab(grab),
+                EngineCommand::SetCursorVisible(visible) => {
+                    if let Some(window) = self.window.as_ref() {
+                        window.set_cursor_visible(visible);
+                    }
+                },
+                EngineCommand::SetFullscreen(mode) => self.set_fullscreen(mode),
+                EngineCommand::ToggleFullscreen => self.toggle_fullscreen(),
+                EngineCommand::SetImeAllowed(allowed) => {
+                    if let Some(window) = self.window.as_ref() {
+                        window.set_ime_allowed(allowed);
+                    }
+                },
+                EngineCommand::SetImeCursorArea { position, size } => {
+                    if let Some(window) = self.window.as_ref() {
+                        window.set_ime_cursor_area(
+                            PhysicalPosition::new(position.0, position.1),
+                            PhysicalSize::new(size.0, size.1),
+                        );
+                    }
+                },
+            }
+        }
+    }
+
+    fn set_cursor_grab(&mut self, grab: CursorGrab) {
+        let Some(window) = self.window.as_ref() else { return; };
+
+        let result = match grab {
+            CursorGrab::None => window.set_cursor_grab(CursorGrabMode::None),
+            CursorGrab::Confined => window.set_cursor_grab(CursorGrabMode::Confined),
+            CursorGrab::Locked => window.set_cursor_grab(CursorGrabMode::Locked)
+                .or_else(|_| window.set_cursor_grab(CursorGrabMode::Confined)),
+        };
+
+        if let Err(err) = result {
+            log::warn!("failed to set cursor grab to {:?}: {err}", grab);
+        }
+    }
+
+    pub(crate) fn set_fullscreen(&mut self, mode: FullscreenMode) {
+        if let Some(window) = self.window.as_ref() {
+            window.set_fullscreen(to_winit_fullscreen(window, mode));
+        }
+    }
+
+    pub(crate) fn toggle_fullscreen(&mut self) {
+        let is_fullscreen = self.window.as_ref()
+            .map(|window| window.fullscreen().is_some())
+            .unwrap_or(false);
+
+        if is_fullscreen {
+            self.set_fullscreen(FullscreenMode::Windowed);
+        } else if self.fullscreen_mode == FullscreenMode::Windowed {
+            self.set_fullscreen(FullscreenMode::Borderless);
+        } else {
+            self.set_fullscreen(self.fullscreen_mode);
+        }
+    }
+}
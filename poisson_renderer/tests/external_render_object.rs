@@ -0,0 +1,308 @@
+// a render object defined outside poisson_renderer, registered with the software backend
+// and drawn, the wgpu side only has to compile since there is no gpu to run it on
+
+use std::any::Any;
+use std::borrow::Cow;
+use std::sync::{Arc, Weak};
+use cgmath::{Matrix4, SquareMatrix, Vector4};
+use poisson_macros::AsAny;
+use poisson_renderer::AsAny;
+use poisson_renderer::render_backend::{DrawletID, RenderBackend, RenderDrawlet, RenderPipeline};
+use poisson_renderer::render_backend::registry::Registry;
+use poisson_renderer::render_backend::render_interface::{Mesh, RenderObject};
+use poisson_renderer::render_backend::render_interface::drawlets::{DrawletHandle, DrawletTrait, PipelineTrait, ReleaseQueue};
+use poisson_renderer::render_backend::software::{SoftwareBuffer, SoftwareDrawlet, SoftwarePipelineConfig, SoftwareRenderBackend, SoftwareRenderObject};
+use poisson_renderer::render_backend::software::raster::SoftwareFramebuffer;
+use poisson_renderer::render_backend::web::{Device, WgpuBuffer, WgpuDrawlet, WgpuDrawletDyn, WgpuPipeline, WgpuPipelineDyn, WgpuRenderBackend, WgpuRenderObject};
+use poisson_renderer::render_backend::web::gpu_resources::gpu_mat4::GpuMat4;
+use poisson_renderer::render_backend::web::gpu_resources::gpu_texture::Texture;
+use poisson_renderer::render_backend::web::gpu_resources::gpu_vec4::GpuVec4;
+use poisson_renderer::render_backend::web::gpu_resources::interface::WgpuUniformResource;
+use poisson_renderer::render_backend::EguiUiShow;
+use wgpu::SurfaceConfiguration;
+
+// a mesh filled with a single color
+pub struct FlatMesh {}
+
+impl RenderObject for FlatMesh {
+    type Data = FlatMeshData;
+    type DynDrawlet = dyn FlatMeshDrawletTrait;
+}
+
+pub struct FlatMeshData {
+    pub mvp: Matrix4<f32>,
+    pub color: [f32; 4],
+    pub mesh: Arc<Mesh<[f32; 3]>>,
+}
+
+pub trait FlatMeshDrawletTrait: DrawletTrait<FlatMesh> {
+    fn set_color(self: &mut Self, color: [f32; 4]);
+}
+
+// software backend
+
+impl SoftwareRenderObject for FlatMesh {
+    type Drawlet = SoftwareFlatMeshDrawlet;
+
+    fn new_drawlet(init_data: FlatMeshData, _config: &SoftwarePipelineConfig) -> SoftwareFlatMeshDrawlet {
+        SoftwareFlatMeshDrawlet {
+            mvp: init_data.mvp,
+            color: init_data.color,
+            vertex_buffer: init_data.mesh.vertex.buffer.downcast()
+                .expect("failed to cast vertex buffer to drawlet buffer type"),
+            index_buffer: init_data.mesh.index.buffer.downcast()
+                .expect("failed to cast index buffer to drawlet buffer type"),
+        }
+    }
+
+    fn upcast_drawlet(drawlet: &mut SoftwareFlatMeshDrawlet) -> &mut (dyn FlatMeshDrawletTrait + 'static) {
+        drawlet
+    }
+}
+
+pub struct SoftwareFlatMeshDrawlet {
+    mvp: Matrix4<f32>,
+    color: [f32; 4],
+    vertex_buffer: rj::Own<SoftwareBuffer<[f32; 3]>>,
+    index_buffer: rj::Own<SoftwareBuffer<u32>>,
+}
+
+impl SoftwareDrawlet for SoftwareFlatMeshDrawlet {
+    fn draw(self: &Self, framebuffer: &mut SoftwareFramebuffer) {
+        let vertices = self.vertex_buffer.access().read();
+        let indices = self.index_buffer.access().read();
+
+        let clip_positions: Vec<Vector4<f32>> = vertices.iter()
+            .map(|v| self.mvp * Vector4::new(v[0], v[1], v[2], 1f32))
+            .collect();
+        let varyings = vec![[0f32; 0]; vertices.len()];
+
+        framebuffer.draw_indexed(&clip_positions, &varyings, &indices, |_| self.color);
+    }
+}
+
+impl DrawletTrait<FlatMesh> for SoftwareFlatMeshDrawlet {}
+impl FlatMeshDrawletTrait for SoftwareFlatMeshDrawlet {
+    fn set_color(self: &mut Self, color: [f32; 4]) {
+        self.color = color;
+    }
+}
+
+// wgpu backend, the pipeline owns its drawlets and drains their releases itself
+
+impl WgpuRenderObject for FlatMesh {
+    type Drawlet = WgpuFlatMeshDrawlet;
+    type Pipeline = WgpuFlatMeshPipeline;
+    type Data = FlatMeshData;
+}
+
+pub struct WgpuFlatMeshDrawlet {
+    device: Weak<Device>,
+    mvp_buffer: GpuMat4,
+    color_buffer: GpuVec4,
+    vertex_buffer: rj::Own<WgpuBuffer<[f32; 3]>>,
+    index_buffer: rj::Own<WgpuBuffer<u32>>,
+}
+
+impl RenderDrawlet for WgpuFlatMeshDrawlet {
+    type Data = FlatMeshData;
+}
+
+impl WgpuDrawlet for WgpuFlatMeshDrawlet {
+    fn draw(self: &Self, render_pass: &mut wgpu::RenderPass) {
+        render_pass.set_bind_group(0, self.mvp_buffer.get_bind_group(), &[]);
+        render_pass.set_bind_group(1, self.color_buffer.get_bind_group(), &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.access().slice());
+        let index_buffer = self.index_buffer.access();
+        render_pass.set_index_buffer(index_buffer.slice(), wgpu::IndexFormat::Uint32);
+        render_pass.draw_indexed(0..index_buffer.num_elements(), 0, 0..1);
+    }
+}
+
+impl DrawletTrait<FlatMesh> for WgpuFlatMeshDrawlet {}
+impl FlatMeshDrawletTrait for WgpuFlatMeshDrawlet {
+    fn set_color(self: &mut Self, color: [f32; 4]) {
+        self.device.upgrade().as_ref().unwrap().queue
+            .write_buffer(&self.color_buffer.buffer, 0, bytemuck::cast_slice(&color));
+    }
+}
+
+#[derive(AsAny)]
+pub struct WgpuFlatMeshPipeline {
+    device: Weak<Device>,
+    render_pipeline: wgpu::RenderPipeline,
+    drawlets: Registry<DrawletID, WgpuFlatMeshDrawlet>,
+    released: ReleaseQueue<DrawletID>,
+}
+
+impl WgpuPipelineDyn for WgpuFlatMeshPipeline {
+    fn get_pipeline(self: &Self) -> &wgpu::RenderPipeline {
+        &self.render_pipeline
+    }
+
+    fn get_instances(self: &Self) -> Box<dyn Iterator<Item=&dyn WgpuDrawletDyn> + '_> {
+        Box::new(self.drawlets.values().map(|drawlet| drawlet as &dyn WgpuDrawletDyn))
+    }
+
+    fn collect_released(self: &mut Self) {
+        for id in self.released.take() {
+            self.drawlets.remove(id);
+        }
+    }
+}
+
+impl WgpuPipeline<FlatMesh> for WgpuFlatMeshPipeline {
+    fn create_drawlet(self: &mut Self, init_data: FlatMeshData) -> DrawletID {
+        let device = self.device.upgrade().unwrap();
+        let [r, g, b, a] = init_data.color;
+
+        self.drawlets.insert(WgpuFlatMeshDrawlet {
+            device: Arc::downgrade(&device),
+            mvp_buffer: GpuMat4::from_mat4(&device.device, &init_data.mvp),
+            color_buffer: GpuVec4::from_vec4(&device.device, &Vector4::new(r, g, b, a)),
+            vertex_buffer: init_data.mesh.vertex.buffer.downcast()
+                .expect("failed to cast vertex buffer to drawlet buffer type"),
+            index_buffer: init_data.mesh.index.buffer.downcast()
+                .expect("failed to cast index buffer to drawlet buffer type"),
+        })
+    }
+
+    fn new(device: &Arc<Device>, shader_u8: &[u8], surface_config: &SurfaceConfiguration) -> Self {
+        let shader = device.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("flat mesh shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::from(str::from_utf8(shader_u8).unwrap())),
+        });
+
+        let layout = device.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("flat mesh pipeline layout"),
+            bind_group_layouts: &[
+                &GpuMat4::create_bind_group_layout(&device.device),
+                &GpuVec4::create_bind_group_layout(&device.device),
+            ],
+            push_constant_ranges: &[],
+        });
+
+        let render_pipeline = device.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("flat mesh pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vertex"),
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &[wgpu::VertexAttribute {
+                        offset: 0,
+                        shader_location: 0,
+                        format: wgpu::VertexFormat::Float32x3,
+                    }],
+                }],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fragment"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_config.format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: Texture::DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        Self {
+            device: Arc::downgrade(device),
+            render_pipeline,
+            drawlets: Registry::new(),
+            released: ReleaseQueue::default(),
+        }
+    }
+}
+
+impl RenderPipeline<FlatMesh> for WgpuFlatMeshPipeline {}
+
+impl PipelineTrait<FlatMesh> for WgpuFlatMeshPipeline {
+    fn create_drawlet(&mut self, init_data: FlatMeshData) -> DrawletID {
+        WgpuPipeline::create_drawlet(self, init_data)
+    }
+
+    fn remove_drawlet(&mut self, id: DrawletID) {
+        let _ = self.drawlets.remove(id);
+    }
+
+    fn get_drawlet_mut(&mut self, id: DrawletID) -> Option<&mut (dyn FlatMeshDrawletTrait + 'static)> {
+        self.drawlets.get_mut(id).map(|drawlet| drawlet as &mut dyn FlatMeshDrawletTrait)
+    }
+
+    fn get_drawlet_releases(self: &Self) -> ReleaseQueue<DrawletID> {
+        self.released.clone()
+    }
+}
+
+// never called, a window and a gpu are needed to build the backend
+#[allow(dead_code)]
+fn register_with_wgpu(backend: &mut WgpuRenderBackend) {
+    backend.register_render_object::<FlatMesh>();
+}
+
+struct NoUi;
+
+impl EguiUiShow for NoUi {
+    fn show(&mut self, _ctx: &egui::Context) {}
+}
+
+fn quad(renderer: &SoftwareRenderBackend) -> Arc<Mesh<[f32; 3]>> {
+    Arc::new(Mesh {
+        vertex: renderer.create_vertex_buffer(&[
+            [-0.5f32, -0.5f32, 0.5f32],
+            [0.5f32, -0.5f32, 0.5f32],
+            [0.5f32, 0.5f32, 0.5f32],
+            [-0.5f32, 0.5f32, 0.5f32],
+        ]),
+        index: renderer.create_index_buffer(&[0, 1, 2, 0, 2, 3]),
+    })
+}
+
+fn set_color(drawlet: &mut DrawletHandle<FlatMesh>, color: [f32; 4]) {
+    drawlet.with_drawlet(|drawlet| drawlet.set_color(color));
+}
+
+#[test]
+fn software_backend_draws_external_render_object() {
+    let mut renderer = SoftwareRenderBackend::new(16, 16);
+    renderer.register_render_object::<FlatMesh>();
+
+    let mut pass = renderer.create_render_pass();
+    let mut pipeline = pass.create_pipeline::<FlatMesh>("flat_mesh", "");
+    let mut drawlet = pipeline.create_drawlet(FlatMeshData {
+        mvp: Matrix4::identity(),
+        color: [1f32, 0f32, 0f32, 1f32],
+        mesh: quad(&renderer),
+    });
+
+    let image = renderer.render_frame(&mut NoUi);
+    assert_eq!(image.get_pixel(8, 8).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(0, 0).0, [255, 255, 255, 255]);
+
+    set_color(&mut drawlet, [0f32, 0f32, 1f32, 1f32]);
+    let image = renderer.render_frame(&mut NoUi);
+    assert_eq!(image.get_pixel(8, 8).0, [0, 0, 255, 255]);
+
+    // the backend removes the drawlet once its last handle is gone
+    let id = drawlet.id();
+    drop(drawlet);
+    renderer.render_frame(&mut NoUi);
+    assert!(!pipeline.contains_drawlet(id));
+}
@@ -0,0 +1,167 @@
+// renders a small scene with the software backend and compares it with a checked-in image,
+// run with UPDATE_GOLDEN=1 to rewrite the images after an intended change in the output
+
+use std::f32::consts::PI;
+use std::path::PathBuf;
+use std::sync::Arc;
+use cgmath::{Matrix4, Point3, Rad, SquareMatrix, Vector3, Vector4};
+use image::RgbaImage;
+use poisson_renderer::PoissonGame;
+use poisson_renderer::context::EngineContext;
+use poisson_renderer::input::Input;
+use poisson_renderer::math::utils::perspective;
+use poisson_renderer::render_backend::{EguiUiShow, RenderBackend};
+use poisson_renderer::render_backend::render_interface::Mesh;
+use poisson_renderer::render_backend::render_interface::drawlets::DrawletHandle;
+use poisson_renderer::render_backend::render_interface::drawlets::colored_mesh::{ColoredMesh, ColoredMeshData, ColoredVertex};
+use poisson_renderer::render_backend::render_interface::drawlets::lit_colored_mesh::{LitColoredMesh, LitColoredMeshData, NormalColoredVertex};
+use poisson_renderer::render_backend::software::{render_frames, SoftwarePipelineConfig, SoftwareRenderBackend};
+use poisson_renderer::render_backend::software::lit_colored_mesh::LitShading;
+use poisson_renderer::time::Time;
+
+const LIT_CONFIG: SoftwarePipelineConfig = SoftwarePipelineConfig {
+    shading: LitShading {
+        ambient: 0.1f32,
+        specular: 0.4f32,
+        shininess: 10f32,
+        alpha_is_gloss: false,
+    },
+};
+
+struct NoUi;
+
+impl EguiUiShow for NoUi {
+    fn show(&mut self, _ctx: &egui::Context) {}
+}
+
+// a flat colored triangle behind a lit pyramid that turns a little every frame
+struct GoldenScene {
+    ui: NoUi,
+    triangle: Option<DrawletHandle<ColoredMesh>>,
+    pyramid: Option<DrawletHandle<LitColoredMesh>>,
+}
+
+impl GoldenScene {
+    fn view_projection() -> Matrix4<f32> {
+        let projection = perspective(PI / 16f32, 1f32, 0.1f32, 10f32, SoftwareRenderBackend::PERSPECTIVE_ALIGNMENT);
+        let view = Matrix4::look_at_rh(Point3::new(0f32, 1f32, 3f32), Point3::new(0f32, 0f32, 0f32), Vector3::unit_y());
+        projection * view
+    }
+}
+
+fn pyramid_vertices() -> Vec<NormalColoredVertex> {
+    let apex = Vector3::new(0f32, 0.8f32, 0f32);
+    let base = [
+        Vector3::new(-0.6f32, -0.4f32, 0.6f32),
+        Vector3::new(0.6f32, -0.4f32, 0.6f32),
+        Vector3::new(0.6f32, -0.4f32, -0.6f32),
+        Vector3::new(-0.6f32, -0.4f32, -0.6f32),
+    ];
+    let colors = [
+        [0.9f32, 0.2f32, 0.2f32, 1f32],
+        [0.2f32, 0.9f32, 0.2f32, 1f32],
+        [0.2f32, 0.2f32, 0.9f32, 1f32],
+        [0.9f32, 0.9f32, 0.2f32, 1f32],
+    ];
+
+    // flat shaded, every side gets its own vertices
+    (0..4).flat_map(|side| {
+        let a = base[side];
+        let b = base[(side + 1) % 4];
+        let normal = (b - a).cross(apex - a);
+        [a, b, apex].map(|p| NormalColoredVertex {
+            pos: p.into(),
+            color: colors[side],
+            normal: normal.into(),
+        })
+    }).collect()
+}
+
+impl PoissonGame for GoldenScene {
+    type Ren = SoftwareRenderBackend;
+
+    fn new() -> Self {
+        Self {
+            ui: NoUi,
+            triangle: None,
+            pyramid: None,
+        }
+    }
+
+    fn pre_init(self: &mut Self, _input: &mut Input) {}
+
+    fn init(self: &mut Self, _input: &mut Input, renderer: &mut Self::Ren, _context: &mut EngineContext) {
+        renderer.set_pipeline_config("assets/shaders/lit_colored_mesh", LIT_CONFIG);
+        let mut pass = renderer.create_render_pass();
+
+        let triangle_mesh = Arc::new(Mesh {
+            vertex: renderer.create_vertex_buffer(&[
+                ColoredVertex { pos: [-1.5f32, -1f32, -2f32], color: [1f32, 0f32, 1f32] },
+                ColoredVertex { pos: [1.5f32, -1f32, -2f32], color: [0f32, 1f32, 1f32] },
+                ColoredVertex { pos: [0f32, 1.5f32, -2f32], color: [1f32, 1f32, 0f32] },
+            ]),
+            index: renderer.create_index_buffer(&[0, 1, 2]),
+        });
+        let mut colored_pipeline = pass.create_pipeline::<ColoredMesh>("assets/shaders/colored_mesh", "");
+        self.triangle = Some(colored_pipeline.create_drawlet(ColoredMeshData {
+            mvp_data: Self::view_projection(),
+            mesh: triangle_mesh,
+        }));
+
+        let vertices = pyramid_vertices();
+        let indices: Vec<u32> = (0..vertices.len() as u32).collect();
+        let pyramid_mesh = Arc::new(Mesh {
+            vertex: renderer.create_vertex_buffer(&vertices),
+            index: renderer.create_index_buffer(&indices),
+        });
+        let mut lit_pipeline = pass.create_pipeline::<LitColoredMesh>("assets/shaders/lit_colored_mesh", "");
+        let mut pyramid = lit_pipeline.create_drawlet(LitColoredMeshData {
+            mvp_data: Matrix4::identity(),
+            light_dir: Vector4::new(1f32, 2f32, 2f32, 0f32),
+            view_dir: Vector4::new(0f32, 1f32, 3f32, 0f32),
+            mesh: pyramid_mesh,
+        });
+        pyramid.set_light_direction(Vector3::new(1f32, 2f32, 2f32));
+        pyramid.set_view_direction(Vector3::new(0f32, 1f32, 3f32));
+        self.pyramid = Some(pyramid);
+
+        // the drawlets don't keep their pipeline or pass alive, these are needed for the whole run
+        colored_pipeline.detach();
+        lit_pipeline.detach();
+        pass.detach();
+    }
+
+    fn update(self: &mut Self, _input: &mut Input, _renderer: &mut Self::Ren, time: &mut Time, _context: &mut EngineContext) {
+        let model = Matrix4::from_angle_y(Rad(time.elapsed()));
+        self.pyramid.as_mut().unwrap().set_mvp(Self::view_projection() * model);
+    }
+
+    fn get_egui_ui_show(self: &mut Self) -> &mut impl EguiUiShow {
+        &mut self.ui
+    }
+}
+
+fn compare_with_golden(name: &str, image: &RgbaImage) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(name);
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        image.save(&path).expect("failed to write golden image");
+        return;
+    }
+
+    let golden = image::open(&path)
+        .unwrap_or_else(|err| panic!("failed to open {}: {err}, run with UPDATE_GOLDEN=1 to create it", path.display()))
+        .to_rgba8();
+
+    if golden != *image {
+        let actual = std::env::temp_dir().join(name);
+        image.save(&actual).expect("failed to write rendered image");
+        panic!("{} differs from the rendered image, which was written to {}", path.display(), actual.display());
+    }
+}
+
+#[test]
+fn software_backend_matches_golden_image() {
+    let image = render_frames::<GoldenScene>(64, 64, 30);
+    compare_with_golden("scene.png", &image);
+}
@@ -0,0 +1,168 @@
+// drives the null backend the way a game would and asserts on the draws it recorded
+
+use std::sync::Arc;
+use cgmath::{Matrix4, SquareMatrix, Vector3, Vector4};
+use poisson_renderer::render_backend::{EguiUiShow, RenderBackend};
+use poisson_renderer::render_backend::null::{NullRenderBackend, NullUniforms};
+use poisson_renderer::render_backend::render_interface::Mesh;
+use poisson_renderer::render_backend::render_interface::drawlets::colored_mesh::{ColoredMesh, ColoredMeshData, ColoredVertex};
+use poisson_renderer::render_backend::render_interface::drawlets::lit_colored_mesh::{LitColoredMesh, LitColoredMeshData, NormalColoredVertex};
+
+struct NoUi;
+
+impl EguiUiShow for NoUi {
+    fn show(&mut self, _ctx: &egui::Context) {}
+}
+
+fn triangle(renderer: &NullRenderBackend) -> Arc<Mesh<ColoredVertex>> {
+    Arc::new(Mesh {
+        vertex: renderer.create_vertex_buffer(&[
+            ColoredVertex { pos: [0f32, 0f32, 0f32], color: [1f32, 0f32, 0f32] },
+            ColoredVertex { pos: [1f32, 0f32, 0f32], color: [0f32, 1f32, 0f32] },
+            ColoredVertex { pos: [0f32, 1f32, 0f32], color: [0f32, 0f32, 1f32] },
+        ]),
+        index: renderer.create_index_buffer(&[0, 1, 2]),
+    })
+}
+
+fn colored_data(renderer: &NullRenderBackend) -> ColoredMeshData {
+    ColoredMeshData {
+        mvp_data: Matrix4::identity(),
+        mesh: triangle(renderer),
+    }
+}
+
+#[test]
+fn records_pass_pipeline_and_drawlet_of_every_draw() {
+    let mut renderer = NullRenderBackend::new(320, 240);
+    let mut pass = renderer.create_render_pass();
+    let mut colored_pipeline = pass.create_pipeline::<ColoredMesh>("shaders/colored_mesh", "");
+    let mut lit_pipeline = pass.create_pipeline::<LitColoredMesh>("shaders/lit_colored_mesh", "");
+
+    let mut colored = colored_pipeline.create_drawlet(colored_data(&renderer));
+    let mvp = Matrix4::from_scale(2f32);
+    colored.set_mvp(mvp);
+
+    let vertex = NormalColoredVertex { pos: [0f32; 3], color: [1f32; 4], normal: [0f32, 1f32, 0f32] };
+    let mut lit = lit_pipeline.create_drawlet(LitColoredMeshData {
+        mvp_data: Matrix4::identity(),
+        light_dir: Vector4::new(0f32, 1f32, 0f32, 0f32),
+        view_dir: Vector4::new(0f32, 0f32, 1f32, 0f32),
+        mesh: Arc::new(Mesh {
+            vertex: renderer.create_vertex_buffer(&[vertex; 4]),
+            index: renderer.create_index_buffer(&[0, 1, 2, 0, 2, 3]),
+        }),
+    });
+    lit.set_light_direction(Vector3::new(1f32, 0f32, 0f32));
+    lit.set_view_direction(Vector3::new(0f32, 0f32, -1f32));
+
+    let frame = renderer.render_frame(&mut NoUi);
+    assert_eq!(frame.index, 0);
+    assert_eq!((frame.width, frame.height), (320, 240));
+    assert_eq!(frame.draw_calls.len(), 2);
+
+    let find_call = |drawlet| frame.draw_calls.iter()
+        .find(|call| call.drawlet == drawlet)
+        .expect("no draw call recorded for the drawlet");
+
+    let colored_call = find_call(colored.id());
+    assert_eq!(colored_call.pipeline, colored_pipeline.id());
+    assert_eq!(colored_call.pass, pass.id());
+    assert_eq!(colored_call.shader_path, "shaders/colored_mesh");
+    assert_eq!((colored_call.num_indices, colored_call.num_vertices), (3, 3));
+    assert_eq!(colored_call.uniforms, NullUniforms { mvp: Some(mvp), light_dir: None, view_dir: None });
+
+    let lit_call = find_call(lit.id());
+    assert_eq!(lit_call.pipeline, lit_pipeline.id());
+    assert_eq!(lit_call.pass, pass.id());
+    assert_eq!(lit_call.shader_path, "shaders/lit_colored_mesh");
+    assert_eq!((lit_call.num_indices, lit_call.num_vertices), (6, 4));
+    assert_eq!(lit_call.uniforms.light_dir, Some(Vector3::new(1f32, 0f32, 0f32)));
+    assert_eq!(lit_call.uniforms.view_dir, Some(Vector3::new(0f32, 0f32, -1f32)));
+}
+
+#[test]
+fn dropped_handles_are_released_before_the_next_frame() {
+    let mut renderer = NullRenderBackend::new(1, 1);
+    let mut pass = renderer.create_render_pass();
+    let mut pipeline = pass.create_pipeline::<ColoredMesh>("shaders/colored_mesh", "");
+
+    let kept = pipeline.create_drawlet(colored_data(&renderer));
+    let dropped = pipeline.create_drawlet(colored_data(&renderer));
+    let dropped_id = dropped.id();
+
+    // a clone keeps the drawlet alive until it is dropped too
+    let clone = dropped.clone();
+    drop(dropped);
+    assert_eq!(renderer.render_frame(&mut NoUi).draw_calls.len(), 2);
+
+    drop(clone);
+    let frame = renderer.render_frame(&mut NoUi);
+    assert_eq!(frame.draw_calls.len(), 1);
+    assert_eq!(frame.draw_calls[0].drawlet, kept.id());
+    assert!(!pipeline.contains_drawlet(dropped_id));
+
+    // detached drawlets stay until their pipeline goes
+    let detached_id = pipeline.create_drawlet(colored_data(&renderer)).detach();
+    assert_eq!(renderer.render_frame(&mut NoUi).draw_calls.len(), 2);
+    assert!(pipeline.contains_drawlet(detached_id));
+
+    // the drawlet handle doesn't keep its pipeline around
+    drop(pipeline);
+    assert!(renderer.render_frame(&mut NoUi).draw_calls.is_empty());
+
+    let mut pipeline = pass.create_pipeline::<ColoredMesh>("shaders/colored_mesh", "");
+    let _drawlet = pipeline.create_drawlet(colored_data(&renderer));
+    assert_eq!(renderer.render_frame(&mut NoUi).draw_calls.len(), 1);
+
+    drop(pass);
+    assert!(renderer.render_frame(&mut NoUi).draw_calls.is_empty());
+}
+
+#[test]
+fn stale_drawlet_ids_never_reach_a_new_drawlet() {
+    let mut renderer = NullRenderBackend::new(1, 1);
+    let mut pass = renderer.create_render_pass();
+    let mut pipeline = pass.create_pipeline::<ColoredMesh>("shaders/colored_mesh", "");
+
+    let first = pipeline.create_drawlet(colored_data(&renderer));
+    let mut stale = first.clone();
+    let stale_id = first.id();
+    pipeline.remove_drawlet(first);
+
+    assert!(!stale.is_alive());
+    assert!(!pipeline.contains_drawlet(stale_id));
+    assert_eq!(stale.with_drawlet(|_| ()), None);
+    assert_eq!(pipeline.with_drawlet(stale_id, |_| ()), None);
+
+    // the new drawlet may reuse the slot, but not the generation
+    let second = pipeline.create_drawlet(colored_data(&renderer));
+    assert_ne!(second.id(), stale_id);
+    assert!(!pipeline.contains_drawlet(stale_id));
+    assert!(second.is_alive());
+
+    stale.set_mvp(Matrix4::from_scale(3f32));
+    let frame = renderer.render_frame(&mut NoUi);
+    assert_eq!(frame.draw_calls.len(), 1);
+    assert_eq!(frame.draw_calls[0].drawlet, second.id());
+    assert_eq!(frame.draw_calls[0].uniforms.mvp, Some(Matrix4::identity()));
+}
+
+#[test]
+fn drawlet_ids_from_another_pipeline_are_never_found() {
+    let mut renderer = NullRenderBackend::new(1, 1);
+    let mut pass = renderer.create_render_pass();
+    let mut first = pass.create_pipeline::<ColoredMesh>("shaders/colored_mesh", "");
+    let mut second = pass.create_pipeline::<ColoredMesh>("shaders/colored_mesh", "");
+
+    // both drawlets sit in the first slot of their pipeline
+    let foreign = first.create_drawlet(colored_data(&renderer));
+    let _own = second.create_drawlet(colored_data(&renderer));
+
+    assert!(!second.contains_drawlet(foreign.id()));
+    assert_eq!(second.with_drawlet(foreign.id(), |_| ()), None);
+    second.remove_drawlet_by_id(foreign.id());
+
+    assert!(foreign.is_alive());
+    assert_eq!(renderer.render_frame(&mut NoUi).draw_calls.len(), 2);
+}
@@ -8,8 +8,9 @@ use interface::{
     SlangComponentListOpaque, 
     SlangComponentOpaque, 
     SlangEntryPointOpaque, 
-    SlangModuleOpaque, 
-    VarType };
+    SlangModuleOpaque };
+pub use interface::VarType;
+pub use interface::ShaderStage;
 pub use interface::SlangEntryPointReflection;
 pub use interface::SlangProgramReflection;
 pub use interface::SlangStructReflection;
@@ -25,10 +26,11 @@ mod interface {
         Float2,
         Float3,
         Float4,
+        Float4x4,
     }
 
     #[derive(Debug)]
-    enum ShaderStage {
+    pub enum ShaderStage {
         None,
         Vertex,
         Fragment,
@@ -48,15 +50,20 @@ mod interface {
 
     #[derive(Debug)]
     struct SlangProgramReflection {
-        // this also assumes uniforms are a single struct of primitives
-        // which is mostly fine
+        // one struct per global parameter block or constant buffer, bound at `binding`
+        // in register space `space`, field locations are byte offsets into the block
         uniform_reflections: Vec<SlangStructReflection>,
-        entry_point_reflections: Vec<SlangEntryPointReflection>
+        entry_point_reflections: Vec<SlangEntryPointReflection>,
+        // names of the global parameters that aren't blocks of uniforms, e.g. textures and samplers
+        unsupported_parameters: Vec<String>,
     }
 
     #[derive(Debug)]
     struct SlangStructReflection {
+        // the parameter's name, `type_name` is the name of its struct
         name: String,
+        type_name: String,
+        space: u32,
         binding: u32,
         fields: Vec<SlangFieldReflection>,
     }
@@ -107,11 +114,15 @@ impl fmt::Display for VarType {
 impl fmt::Display for SlangStructReflection {
     fn fmt(self: &Self, f: &mut Formatter<'_>) -> fmt::Result {
         let mut ret_string = String::new();
-        ret_string.push_str("binding: ");
+        ret_string.push_str("space: ");
+        ret_string.push_str(&self.space.to_string());
+        ret_string.push_str(", binding: ");
         ret_string.push_str(&self.binding.to_string());
         ret_string.push_str(", ");
         ret_string.push_str(&self.name);
         ret_string.push_str(": ");
+        ret_string.push_str(&self.type_name);
+        ret_string.push_str(", ");
         for field in &self.fields {
             ret_string.push_str("location: ");
             ret_string.push_str(&field.location.to_string());
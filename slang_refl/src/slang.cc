@@ -186,6 +186,12 @@ std::unique_ptr<SlangCompilerOpaque> new_wgsl_compiler() {
 //    }
 
 
+// slang returns null for the names of unnamed types
+static rust::String type_name_of(slang::TypeLayoutReflection* type_layout) {
+    const char* name = type_layout->getName();
+    return rust::String(name != nullptr ? name : "");
+}
+
 SlangProgramReflection SlangComponentOpaque::get_program_reflection() const {
     SlangProgramReflection ret;
     Slang::ComPtr<slang::IBlob> diagnostics;
@@ -193,73 +199,87 @@ SlangProgramReflection SlangComponentOpaque::get_program_reflection() const {
     slang::ProgramLayout* programLayout = component->getLayout(0, diagnostics.writeRef());
     auto uniform_struct_layout = programLayout->getGlobalParamsVarLayout()->getTypeLayout();
 
-//    // found the uniforms
-//    if (uniform_struct_layout->getKind() == slang::TypeReflection::Kind::Struct) {
-//        int paramCount = uniform_struct_layout->getFieldCount();
-//
-//        entry_refl.misc_reflections.name = rust::String("misc_params");
-//
-//        for (int j = 0; j < paramCount; j++)
-//        {
-//            auto param = input_struct_layout->getFieldByIndex(j);
-//            auto param_type_layout = param->getTypeLayout();
-//
-//            auto param_kind = param_type_layout->getKind(); // slang::TypeReflection::Kind::
-//            auto param_type = param_type_layout->getType(); // slang::TypeReflection*
-//
-//            if (param_kind != slang::TypeReflection::Kind::Struct) {
-//                std::cout << "found misc param" << std::endl;
-//                // put it into misc
-//                continue;
-//            }
-//
-//            SlangStructReflection struct_param;
-//            struct_param.name = rust::String(param->getName());
-//            auto field_count = param_type_layout->getFieldCount();
-//            for (int k = 0; k < field_count; k++) {
-//
-//                auto field = param_type_layout->getFieldByIndex(k);
-//
-//                auto field_type_layout = field->getTypeLayout();
-//                auto field_kind = field_type_layout->getKind(); // slang::TypeReflection::Kind::
-//
-//                SlangFieldReflection field_refl;
-//                field_refl.name = rust::String(field->getName());
-//                field_refl.var_type = VarType::Undefined;
-//
-//                if (field_kind == slang::TypeReflection::Kind::Struct) {
-//                    std::cout << "found overly nested struct!" << std::endl;
-//                    continue;
-//                }
-//
-//                auto field_type = field_type_layout->getType();
-//
-//                switch (field_kind) {
-//                    case slang::TypeReflection::Kind::Vector:
-//                        auto vec_length = field_type->getElementCount();
-//
-//                        auto element_scalar_type = field_type->getElementType()->getScalarType();
-//
-//                        if (element_scalar_type == slang::TypeReflection::ScalarType::Float32) {
-//                            if (vec_length == 2) {
-//                                field_refl.var_type = VarType::Float2;
-//                            } else if (vec_length == 3) {
-//                                field_refl.var_type = VarType::Float3;
-//                            } else if (vec_length == 4) {
-//                                field_refl.var_type = VarType::Float4;
-//                            }
-//                        }
-//                        break;
-//                }
-//
-//                struct_param.fields.push_back(field_refl);
-//            }
-//
-//            entry_refl.struct_reflections.push_back(struct_param);
-//        }
-//    }
+    // every global parameter block becomes one struct, bound to its own bind group
+    if (uniform_struct_layout->getKind() == slang::TypeReflection::Kind::Struct) {
+        const auto block_count = uniform_struct_layout->getFieldCount();
+
+        for (int j = 0; j < block_count; j++)
+        {
+            const auto block = uniform_struct_layout->getFieldByIndex(j);
+            const auto block_type_layout = block->getTypeLayout();
+            const auto block_kind = block_type_layout->getKind();
+
+            // textures, samplers and loose uniforms aren't reflected, the caller decides what to do about them
+            if (block_kind != slang::TypeReflection::Kind::ParameterBlock &&
+                block_kind != slang::TypeReflection::Kind::ConstantBuffer) {
+                ret.unsupported_parameters.push_back(rust::String(block->getName()));
+                continue;
+            }
+
+            const auto element_type_layout = block_type_layout->getElementTypeLayout();
+            if (element_type_layout->getKind() != slang::TypeReflection::Kind::Struct) {
+                ret.unsupported_parameters.push_back(rust::String(block->getName()));
+                continue;
+            }
+
+            SlangStructReflection struct_param;
+            struct_param.name = rust::String(block->getName());
+            struct_param.type_name = type_name_of(element_type_layout);
+            // a parameter block gets a space to itself with its uniforms at binding 0,
+            // constant buffers share a space and each take a binding in it
+            if (block_kind == slang::TypeReflection::Kind::ParameterBlock) {
+                struct_param.space = block->getOffset(slang::ParameterCategory::SubElementRegisterSpace);
+                struct_param.binding = 0;
+            } else {
+                struct_param.space = block->getBindingSpace();
+                struct_param.binding = block->getBindingIndex();
+            }
+
+            const auto field_count = element_type_layout->getFieldCount();
+            for (int k = 0; k < field_count; k++) {
+                const auto field = element_type_layout->getFieldByIndex(k);
+                const auto field_type_layout = field->getTypeLayout();
+                const auto field_type = field_type_layout->getType();
+
+                SlangFieldReflection field_refl;
+                field_refl.name = rust::String(field->getName());
+                // uniforms have a byte offset into their block instead of a location
+                field_refl.location = field->getOffset(slang::ParameterCategory::Uniform);
+                field_refl.var_type = VarType::Undefined;
+
+                switch (field_type_layout->getKind()) {
+                    case slang::TypeReflection::Kind::Scalar:
+                        if (field_type->getScalarType() == slang::TypeReflection::ScalarType::Float32) {
+                            field_refl.var_type = VarType::Float;
+                        }
+                        break;
+                    case slang::TypeReflection::Kind::Vector:
+                        if (field_type->getElementType()->getScalarType() == slang::TypeReflection::ScalarType::Float32) {
+                            auto vec_length = field_type->getElementCount();
+                            if (vec_length == 2) {
+                                field_refl.var_type = VarType::Float2;
+                            } else if (vec_length == 3) {
+                                field_refl.var_type = VarType::Float3;
+                            } else if (vec_length == 4) {
+                                field_refl.var_type = VarType::Float4;
+                            }
+                        }
+                        break;
+                    case slang::TypeReflection::Kind::Matrix:
+                        if (field_type->getRowCount() == 4 && field_type->getColumnCount() == 4) {
+                            field_refl.var_type = VarType::Float4x4;
+                        }
+                        break;
+                    default:
+                        break;
+                }
 
+                struct_param.fields.push_back(field_refl);
+            }
 
+            ret.uniform_reflections.push_back(struct_param);
+        }
+    }
 
     if (diagnostics != nullptr)
     {
@@ -298,6 +318,8 @@ SlangProgramReflection SlangComponentOpaque::get_program_reflection() const {
             const auto paramCount = input_struct_layout->getFieldCount();
 
             entry_refl.misc_reflections.name = rust::String("misc_params");
+            entry_refl.misc_reflections.space = 0;
+            entry_refl.misc_reflections.binding = 0;
 
             for (int j = 0; j < paramCount; j++)
             {
@@ -317,6 +339,8 @@ SlangProgramReflection SlangComponentOpaque::get_program_reflection() const {
 
                 SlangStructReflection struct_param;
                 struct_param.name = rust::String(param->getName());
+                struct_param.type_name = type_name_of(param_type_layout);
+                struct_param.space = 0;
                 struct_param.binding = j;
                 const auto field_count = param_type_layout->getFieldCount();
                 for (int k = 0; k < field_count; k++) {